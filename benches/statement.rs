@@ -4,7 +4,7 @@ extern crate test;
 
 mod common;
 
-use sqlite::State;
+use sqlite::{State, Value};
 use test::Bencher;
 
 use common::{create, populate};
@@ -47,3 +47,16 @@ fn write(bencher: &mut Bencher) {
         assert_eq!(ok!(statement.next()), State::Done);
     })
 }
+
+#[bench]
+fn write_batch(bencher: &mut Bencher) {
+    let connection = create();
+    let query = "INSERT INTO data (a, b, c, d) VALUES (?, ?, ?, ?)";
+    let mut statement = ok!(connection.prepare(query));
+    let values: [Value; 4] = [42.into(), 42.0.into(), 42.0.into(), 42.0.into()];
+
+    bencher.iter(|| {
+        let count = ok!(statement.execute_batch_in_transaction((0..100).map(|_| &values[..])));
+        assert_eq!(count, 100);
+    })
+}
@@ -0,0 +1,87 @@
+//! Derive macros for the [sqlite][1] package.
+//!
+//! [1]: https://crates.io/crates/sqlite
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Derive the conversions of a newtype from and to values.
+///
+/// The type has to be a tuple struct with a single field, and the conversions delegate to those
+/// of the field, which makes it possible to bind, read, and convert the type just like the field.
+#[proc_macro_derive(SqlNewtype)]
+pub fn derive_sql_newtype(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (implementation, arguments, clause) = input.generics.split_for_impl();
+    let field = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => return error(name, "expected a tuple struct with a single field"),
+        },
+        _ => return error(name, "expected a struct"),
+    };
+    quote! {
+        impl #implementation ::sqlite::BindableWithIndex for #name #arguments #clause {
+            #[inline]
+            fn bind<T: ::sqlite::ParameterIndex>(
+                self,
+                statement: &mut ::sqlite::Statement,
+                index: T,
+            ) -> ::sqlite::Result<()> {
+                ::sqlite::BindableWithIndex::bind(::sqlite::Value::from(self.0), statement, index)
+            }
+        }
+
+        impl #implementation ::sqlite::BindableWithIndex for &#name #arguments #clause {
+            #[inline]
+            fn bind<T: ::sqlite::ParameterIndex>(
+                self,
+                statement: &mut ::sqlite::Statement,
+                index: T,
+            ) -> ::sqlite::Result<()> {
+                ::sqlite::BindableWithIndex::bind(
+                    ::sqlite::Value::from(::core::clone::Clone::clone(&self.0)),
+                    statement,
+                    index,
+                )
+            }
+        }
+
+        impl #implementation ::sqlite::ReadableWithIndex for #name #arguments #clause {
+            #[inline]
+            fn read<T: ::sqlite::ColumnIndex>(
+                statement: &::sqlite::Statement,
+                index: T,
+            ) -> ::sqlite::Result<Self> {
+                <#field as ::sqlite::ReadableWithIndex>::read(statement, index).map(#name)
+            }
+        }
+
+        impl #implementation ::core::convert::TryFrom<&::sqlite::Value>
+            for #name #arguments #clause
+        {
+            type Error = ::sqlite::Error;
+
+            #[inline]
+            fn try_from(value: &::sqlite::Value) -> ::sqlite::Result<Self> {
+                <#field as ::core::convert::TryFrom<&::sqlite::Value>>::try_from(value).map(#name)
+            }
+        }
+
+        impl #implementation ::core::convert::From<#name #arguments> for ::sqlite::Value #clause {
+            #[inline]
+            fn from(value: #name #arguments) -> Self {
+                ::sqlite::Value::from(value.0)
+            }
+        }
+    }
+    .into()
+}
+
+fn error(name: &syn::Ident, message: &str) -> TokenStream {
+    syn::Error::new_spanned(name, message)
+        .to_compile_error()
+        .into()
+}
@@ -0,0 +1,122 @@
+use crate::backoff::Backoff;
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::statement::State;
+use crate::transaction::{quote, TransactionBehavior};
+
+pub fn execute_ddl(connection: &Connection, statement: &str) -> Result<bool> {
+    Backoff::new().retry(|| {
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = read(connection, "schema_version")?;
+        connection.execute(statement)?;
+        let after = read(connection, "schema_version")?;
+        transaction.commit()?;
+        Ok(after != before)
+    })
+}
+
+pub fn rename_table(connection: &Connection, old: &str, new: &str) -> Result<()> {
+    with_legacy_alter_table(connection, false, || {
+        connection.execute(format!(
+            "ALTER TABLE {} RENAME TO {}",
+            quote(old),
+            quote(new),
+        ))
+    })
+}
+
+pub fn rename_column(connection: &Connection, table: &str, old: &str, new: &str) -> Result<()> {
+    with_legacy_alter_table(connection, false, || {
+        connection.execute(format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            quote(table),
+            quote(old),
+            quote(new),
+        ))
+    })
+}
+
+pub fn rebuild_table(
+    connection: &Connection,
+    table: &str,
+    definition: &str,
+    columns: &str,
+) -> Result<()> {
+    let foreign_keys = read(connection, "foreign_keys")? != 0;
+    if foreign_keys {
+        if unsafe { ffi::sqlite3_get_autocommit(connection.as_raw()) } == 0 {
+            raise!("cannot rebuild a table with foreign keys enabled within a transaction");
+        }
+        connection.execute("PRAGMA foreign_keys = OFF")?;
+    }
+    let result = rebuild_table_unchecked(connection, table, definition, columns, foreign_keys);
+    if foreign_keys {
+        connection.execute("PRAGMA foreign_keys = ON")?;
+    }
+    result
+}
+
+fn rebuild_table_unchecked(
+    connection: &Connection,
+    table: &str,
+    definition: &str,
+    columns: &str,
+    foreign_keys: bool,
+) -> Result<()> {
+    let transaction = connection.transaction()?;
+    let mut dependents = Vec::new();
+    let mut statement = connection.prepare(
+        "SELECT sql FROM sqlite_master \
+         WHERE tbl_name = ? AND type IN ('index', 'trigger') AND sql IS NOT NULL",
+    )?;
+    statement.bind((1, table))?;
+    while let State::Row = statement.next()? {
+        dependents.push(statement.read::<String, _>(0)?);
+    }
+    drop(statement);
+    let temporary = quote(&format!("{table}_rebuild"));
+    let quoted = quote(table);
+    connection.execute(format!("CREATE TABLE {temporary} {definition}"))?;
+    connection.execute(format!(
+        "INSERT INTO {temporary} SELECT {columns} FROM {quoted}"
+    ))?;
+    connection.execute(format!("DROP TABLE {quoted}"))?;
+    // The legacy behavior leaves views and triggers referring to the table untouched instead of
+    // failing on them while the table is absent.
+    with_legacy_alter_table(connection, true, || {
+        connection.execute(format!("ALTER TABLE {temporary} RENAME TO {quoted}"))
+    })?;
+    for dependent in dependents {
+        connection.execute(dependent)?;
+    }
+    if foreign_keys {
+        let mut statement = connection.prepare("PRAGMA foreign_key_check")?;
+        if let State::Row = statement.next()? {
+            raise!(
+                "the rebuild violates a foreign key constraint ({})",
+                statement.read::<String, _>(0)?,
+            );
+        }
+    }
+    transaction.commit()
+}
+
+fn with_legacy_alter_table<F>(connection: &Connection, value: bool, callback: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()>,
+{
+    let previous = read(connection, "legacy_alter_table")? != 0;
+    if previous == value {
+        return callback();
+    }
+    connection.execute(format!("PRAGMA legacy_alter_table = {}", value as i64))?;
+    let result = callback();
+    connection.execute(format!("PRAGMA legacy_alter_table = {}", previous as i64))?;
+    result
+}
+
+fn read(connection: &Connection, name: &str) -> Result<i64> {
+    let mut statement = connection.prepare(format!("PRAGMA {name}"))?;
+    statement.next()?;
+    statement.read(0)
+}
@@ -0,0 +1,126 @@
+use core::ffi::c_int;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// A schedule of pauses between repeated attempts.
+///
+/// The pause grows exponentially from an initial value up to a maximum one, and the attempts are
+/// given up after a certain number of them. Pausing is done via `sqlite::sleep`, so the actual
+/// granularity is the one of the operating system as seen by SQLite.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    initial: Duration,
+    maximum: Duration,
+    factor: u32,
+    attempts: usize,
+}
+
+impl Backoff {
+    /// Create a schedule.
+    ///
+    /// By default, the pause starts at 1 millisecond and doubles with each attempt up to 100
+    /// milliseconds, and 20 attempts are made.
+    #[inline]
+    pub fn new() -> Self {
+        Backoff {
+            initial: Duration::from_millis(1),
+            maximum: Duration::from_millis(100),
+            factor: 2,
+            attempts: 20,
+        }
+    }
+
+    /// Set the pause after the first attempt.
+    pub fn with_initial(mut self, value: Duration) -> Self {
+        self.initial = value;
+        self
+    }
+
+    /// Set the longest pause.
+    pub fn with_maximum(mut self, value: Duration) -> Self {
+        self.maximum = value;
+        self
+    }
+
+    /// Set the factor by which the pause grows with each attempt.
+    pub fn with_factor(mut self, value: u32) -> Self {
+        self.factor = value;
+        self
+    }
+
+    /// Set the number of attempts.
+    pub fn with_attempts(mut self, value: usize) -> Self {
+        self.attempts = value;
+        self
+    }
+
+    /// Return the pause after an attempt or `None` if no further attempts should be made.
+    ///
+    /// The first attempt has index 0.
+    pub fn delay(&self, attempt: usize) -> Option<Duration> {
+        if attempt + 1 >= self.attempts {
+            return None;
+        }
+        let mut delay = self.initial;
+        for _ in 0..attempt {
+            if delay >= self.maximum {
+                break;
+            }
+            delay = delay.saturating_mul(self.factor);
+        }
+        Some(delay.min(self.maximum))
+    }
+
+    /// Pause after an attempt and return `false` if no further attempts should be made.
+    ///
+    /// The first attempt has index 0.
+    pub fn pause(&self, attempt: usize) -> bool {
+        match self.delay(attempt) {
+            Some(delay) => {
+                crate::sleep(delay.as_millis().max(1) as usize);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Call a function until it succeeds, the error is other than `SQLITE_BUSY` or
+    /// `SQLITE_LOCKED`, or the attempts are exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::Backoff;
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// Backoff::new().retry(|| connection.execute("CREATE TABLE users (name TEXT)"))?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn retry<F, T>(&self, mut callback: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut attempt = 0;
+        loop {
+            match callback() {
+                Err(error) if is_busy(&error) && self.pause(attempt) => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for Backoff {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn is_busy(error: &Error) -> bool {
+    matches!(
+        error.code.map(|code| code as c_int & 0xff),
+        Some(ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED)
+    )
+}
@@ -0,0 +1,133 @@
+use core::ffi::c_int;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::backoff::is_busy;
+use crate::connection::Connection;
+use crate::error::Result;
+
+/// An online backup.
+pub struct Backup<'l> {
+    raw: (*mut ffi::sqlite3_backup, *mut ffi::sqlite3),
+    phantom: PhantomData<(ffi::sqlite3_backup, &'l ffi::sqlite3)>,
+}
+
+/// Options for backing up a database.
+#[derive(Clone, Copy, Debug)]
+pub struct BackupOptions {
+    pages_per_step: usize,
+    pause: Duration,
+}
+
+impl<'l> Backup<'l> {
+    /// Start a backup of the main database of one connection into the main database of another.
+    #[inline]
+    pub fn new(source: &'l Connection, destination: &'l Connection) -> Result<Self> {
+        Backup::new_with_schemas(source, "main", destination, "main")
+    }
+
+    /// Start a backup of a specific schema of one connection into a specific schema of another.
+    pub fn new_with_schemas<T: AsRef<str>, U: AsRef<str>>(
+        source: &'l Connection,
+        source_schema: T,
+        destination: &'l Connection,
+        destination_schema: U,
+    ) -> Result<Self> {
+        let raw = unsafe {
+            ffi::sqlite3_backup_init(
+                destination.as_raw(),
+                str_to_cstr!(destination_schema.as_ref()).as_ptr(),
+                source.as_raw(),
+                str_to_cstr!(source_schema.as_ref()).as_ptr(),
+            )
+        };
+        if raw.is_null() {
+            error!(destination.as_raw(), ffi::SQLITE_ERROR);
+        }
+        Ok(Backup {
+            raw: (raw, destination.as_raw()),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Copy up to a number of pages.
+    ///
+    /// A negative number corresponds to all remaining pages. The function returns `true` if the
+    /// backup is complete. `SQLITE_BUSY` and `SQLITE_LOCKED` are reported as errors and can be
+    /// retried by calling the function again.
+    pub fn step(&mut self, pages: isize) -> Result<bool> {
+        match unsafe { ffi::sqlite3_backup_step(self.raw.0, pages as c_int) } {
+            ffi::SQLITE_OK => Ok(false),
+            ffi::SQLITE_DONE => Ok(true),
+            code => error!(self.raw.1, code),
+        }
+    }
+
+    /// Return the number of pages still to be copied as of the last step.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        unsafe { ffi::sqlite3_backup_remaining(self.raw.0) as usize }
+    }
+
+    /// Return the total number of pages in the source database as of the last step.
+    #[inline]
+    pub fn page_count(&self) -> usize {
+        unsafe { ffi::sqlite3_backup_pagecount(self.raw.0) as usize }
+    }
+}
+
+impl Drop for Backup<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_backup_finish(self.raw.0) };
+    }
+}
+
+impl BackupOptions {
+    /// Create options for backing up a database.
+    ///
+    /// By default, 100 pages are copied per step with a pause of 10 milliseconds in between.
+    #[inline]
+    pub fn new() -> Self {
+        BackupOptions {
+            pages_per_step: 100,
+            pause: Duration::from_millis(10),
+        }
+    }
+
+    /// Set the number of pages copied per step.
+    pub fn with_pages_per_step(mut self, value: usize) -> Self {
+        self.pages_per_step = value;
+        self
+    }
+
+    /// Set the pause between steps.
+    pub fn with_pause(mut self, value: Duration) -> Self {
+        self.pause = value;
+        self
+    }
+}
+
+impl Default for BackupOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn run<T: AsRef<Path>>(source: &Connection, path: T, options: &BackupOptions) -> Result<()> {
+    let destination = Connection::open(path)?;
+    let mut backup = Backup::new(source, &destination)?;
+    let pages = options.pages_per_step.max(1) as isize;
+    loop {
+        match backup.step(pages) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(error) if is_busy(&error) => {}
+            Err(error) => return Err(error),
+        }
+        crate::sleep(options.pause.as_millis() as usize);
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{ErrorContext, Result};
+use crate::redaction::Redaction;
+use crate::statement::{PrepareFlags, Statement};
+
+/// A prepared statement borrowed from the cache of a connection.
+///
+/// The statement is reset, its bindings are cleared, and it is returned to the cache when the
+/// guard is dropped.
+pub struct CachedStatement<'l> {
+    statement: Option<Statement<'l>>,
+    sql: String,
+    cache: &'l Mutex<Cache>,
+}
+
+pub struct Cache {
+    capacity: usize,
+    entries: VecDeque<(String, Raw)>,
+}
+
+struct Raw(*mut ffi::sqlite3_stmt);
+
+unsafe impl Send for Raw {}
+
+impl<'l> Deref for CachedStatement<'l> {
+    type Target = Statement<'l>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.statement.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for CachedStatement<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.statement.as_mut().unwrap()
+    }
+}
+
+impl Drop for CachedStatement<'_> {
+    fn drop(&mut self) {
+        if let Some(statement) = self.statement.take() {
+            let raw = statement.into_raw();
+            unsafe {
+                ffi::sqlite3_reset(raw);
+                ffi::sqlite3_clear_bindings(raw);
+            }
+            let sql = std::mem::take(&mut self.sql);
+            self.cache.lock().unwrap().insert(sql, Raw(raw));
+        }
+    }
+}
+
+impl Cache {
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Cache {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.entries.pop_back();
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn insert(&mut self, sql: String, raw: Raw) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((sql, raw));
+    }
+
+    fn remove(&mut self, sql: &str) -> Option<Raw> {
+        let position = self.entries.iter().position(|(key, _)| key == sql)?;
+        self.entries.remove(position).map(|(_, raw)| raw)
+    }
+}
+
+impl Drop for Raw {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_finalize(self.0) };
+    }
+}
+
+pub fn prepare<'l>(
+    cache: &'l Mutex<Cache>,
+    raw_connection: *mut ffi::sqlite3,
+    sql: &str,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+) -> Result<CachedStatement<'l>> {
+    let entry = cache.lock().unwrap().remove(sql);
+    let statement = match entry {
+        Some(raw) => {
+            let raw_statement = raw.0;
+            std::mem::forget(raw);
+            unsafe {
+                crate::statement::adopt(raw_statement, raw_connection, error_context, redaction)
+            }
+        }
+        _ => crate::statement::new_with_flags(
+            raw_connection,
+            sql,
+            PrepareFlags::new().with_persistent(),
+            error_context,
+            redaction,
+        )?,
+    };
+    Ok(CachedStatement {
+        statement: Some(statement),
+        sql: sql.to_string(),
+        cache,
+    })
+}
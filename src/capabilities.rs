@@ -0,0 +1,118 @@
+use core::ffi::c_int;
+
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::statement::State;
+
+/// Capabilities of the linked library.
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+    compile_options: Vec<String>,
+    journal_modes: Vec<String>,
+    modules: Vec<String>,
+}
+
+impl Capabilities {
+    /// Return the compile-time options.
+    #[inline]
+    pub fn compile_options(&self) -> &[String] {
+        &self.compile_options
+    }
+
+    /// Check if a compile-time option is set.
+    ///
+    /// The `SQLITE_` prefix is optional, and options with values such as `THREADSAFE=1` can be
+    /// queried by their name only.
+    pub fn has_compile_option(&self, name: &str) -> bool {
+        has(
+            &self.compile_options,
+            name.strip_prefix("SQLITE_").unwrap_or(name),
+        )
+    }
+
+    /// Return the journal modes that could be activated.
+    ///
+    /// The modes are derived from the compile-time options and the file of the main database
+    /// without touching the file system.
+    #[inline]
+    pub fn journal_modes(&self) -> &[String] {
+        &self.journal_modes
+    }
+
+    /// Check if a journal mode could be activated.
+    pub fn supports_journal_mode(&self, mode: &str) -> bool {
+        self.journal_modes
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(mode))
+    }
+
+    /// Return the virtual-table modules available to the connection.
+    ///
+    /// The list covers both built-in extensions such as `fts5` and `rtree` and modules registered
+    /// by loaded extensions.
+    #[inline]
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    /// Check if a virtual-table module is available.
+    pub fn supports_module(&self, name: &str) -> bool {
+        self.modules
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(name))
+    }
+}
+
+pub fn new(connection: &Connection) -> Result<Capabilities> {
+    let mut compile_options = Vec::new();
+    for index in 0.. {
+        let option = unsafe { ffi::sqlite3_compileoption_get(index as c_int) };
+        if option.is_null() {
+            break;
+        }
+        compile_options.push(unsafe { c_str_to_string!(option) });
+    }
+    let mut modules = Vec::new();
+    if let Ok(mut statement) = connection.prepare("SELECT name FROM pragma_module_list") {
+        while let State::Row = statement.next()? {
+            modules.push(statement.read::<String, _>(0)?);
+        }
+    }
+    modules.sort();
+    let mut journal_modes = ["delete", "truncate", "persist", "memory"]
+        .iter()
+        .map(|mode| mode.to_string())
+        .collect::<Vec<_>>();
+    if !has(&compile_options, "OMIT_WAL") && unsafe { supports_shared_memory(connection) } {
+        journal_modes.push("wal".into());
+    }
+    journal_modes.push("off".into());
+    Ok(Capabilities {
+        compile_options,
+        journal_modes,
+        modules,
+    })
+}
+
+// Write-ahead logging requires the file of the main database to provide shared memory. The
+// built-in virtual file systems do, which is assumed when no file has been opened.
+unsafe fn supports_shared_memory(connection: &Connection) -> bool {
+    let mut file: *mut ffi::sqlite3_file = std::ptr::null_mut();
+    ffi::sqlite3_file_control(
+        connection.as_raw(),
+        c"main".as_ptr(),
+        ffi::SQLITE_FCNTL_FILE_POINTER,
+        &mut file as *mut _ as *mut _,
+    );
+    if file.is_null() || (*file).pMethods.is_null() {
+        return true;
+    }
+    let methods = &*(*file).pMethods;
+    methods.iVersion >= 2 && methods.xShmMap.is_some()
+}
+
+fn has(compile_options: &[String], name: &str) -> bool {
+    compile_options.iter().any(|option| {
+        option == name || option.split_once('=').map(|(option, _)| option) == Some(name)
+    })
+}
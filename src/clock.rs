@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::function::Context;
+use crate::value::Value;
+
+// The functions accepting a time value paired with the position of the value.
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("date", 0),
+    ("time", 0),
+    ("datetime", 0),
+    ("julianday", 0),
+    ("unixepoch", 0),
+    ("strftime", 1),
+];
+
+// The keywords referring to the current time paired with the corresponding functions.
+const KEYWORDS: &[(&str, &str)] = &[
+    ("current_date", "date"),
+    ("current_time", "time"),
+    ("current_timestamp", "datetime"),
+];
+
+#[derive(Clone, Copy)]
+enum Clock {
+    Frozen(i64),
+    System,
+}
+
+pub fn freeze(connection: &Connection, seconds: i64) -> Result<()> {
+    register(connection, Clock::Frozen(seconds))
+}
+
+pub fn unfreeze(connection: &Connection) -> Result<()> {
+    let frozen = connection
+        .functions()
+        .contains(&(KEYWORDS[0].0.to_string(), 0));
+    if frozen {
+        register(connection, Clock::System)?;
+    }
+    Ok(())
+}
+
+fn register(connection: &Connection, clock: Clock) -> Result<()> {
+    // The built-in functions are overridden on the connection and thus evaluated elsewhere.
+    let evaluator = Arc::new(Mutex::new(Connection::open(":memory:")?));
+    for &(name, position) in FUNCTIONS {
+        let evaluator = evaluator.clone();
+        connection.create_function(name, -1, move |context| {
+            let mut arguments = (0..context.argument_count())
+                .map(|index| context.argument(index))
+                .collect::<Result<Vec<_>>>()?;
+            if let Clock::Frozen(seconds) = clock {
+                if arguments.len() == position {
+                    arguments.push(Value::from("now"));
+                }
+                if arguments.get(position).is_some_and(is_now) {
+                    arguments[position] = Value::Integer(seconds);
+                    arguments.insert(position + 1, Value::from("unixepoch"));
+                }
+            }
+            evaluate(&evaluator, name, &arguments, context)
+        })?;
+    }
+    for &(keyword, name) in KEYWORDS {
+        let evaluator = evaluator.clone();
+        connection.create_function(keyword, 0, move |context| match clock {
+            Clock::Frozen(seconds) => {
+                let arguments = [Value::Integer(seconds), Value::from("unixepoch")];
+                evaluate(&evaluator, name, &arguments, context)
+            }
+            Clock::System => evaluate(&evaluator, name, &[], context),
+        })?;
+    }
+    Ok(())
+}
+
+fn evaluate(
+    evaluator: &Mutex<Connection>,
+    name: &str,
+    arguments: &[Value],
+    context: &mut Context,
+) -> Result<()> {
+    let placeholders = (1..=arguments.len())
+        .map(|index| format!("?{index}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let evaluator = evaluator.lock().unwrap();
+    let mut statement = evaluator.prepare_cached(format!("SELECT {name}({placeholders})"))?;
+    statement.bind(arguments)?;
+    statement.next()?;
+    context.set_result(statement.read::<Value, _>(0)?);
+    Ok(())
+}
+
+fn is_now(value: &Value) -> bool {
+    matches!(value, Value::String(value) if value.eq_ignore_ascii_case("now"))
+}
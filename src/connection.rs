@@ -1,15 +1,48 @@
 use core::ffi::{c_char, c_int, c_void};
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::error::Result;
-use crate::statement::Statement;
+use crate::backoff::Backoff;
+use crate::backup::BackupOptions;
+use crate::cache::{Cache, CachedStatement};
+use crate::capabilities::Capabilities;
+use crate::cursor::{CursorWithOwnership, Row};
+use crate::error::{BatchError, Error, ErrorContext, Result};
+use crate::function::{Aggregate, Context};
+use crate::header::Encoding;
+#[cfg(feature = "preupdate")]
+use crate::hook::Preupdate;
+use crate::hook::{Action, Authorization, Hook, Kind, Operation, Trace, TraceFlags};
+use crate::pragma::{IndexInfo, TableInfo, TriggerInfo, ViewInfo};
+use crate::recorder::Recorder;
+use crate::redaction::Redaction;
+use crate::statement::{Bindable, PrepareFlags, ReadableWithIndex, State, Statement};
+use crate::transaction::{PinnedReader, Transaction, TransactionBehavior};
+use crate::value::Value;
+use crate::vtab::Module;
 
 /// A connection.
 pub struct Connection {
     raw: Raw,
-    busy_callback: Option<Box<dyn FnMut(usize) -> bool + Send>>,
+    hooks: Mutex<Hooks>,
+    functions: Mutex<BTreeSet<(String, isize)>>,
+    flags: OpenFlags,
+    vfs: Option<String>,
+    initializers: Mutex<Vec<Initializer>>,
+    interrupt: Arc<Mutex<Option<Raw>>>,
+    leak_detection: bool,
+    tag: Arc<Mutex<Option<String>>>,
+    busy: Arc<Mutex<BusyStatistics>>,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+    owned: bool,
+    statements: Mutex<Cache>,
     phantom: PhantomData<ffi::sqlite3>,
 }
 
@@ -18,10 +51,74 @@ pub struct ConnectionThreadSafe(Connection);
 
 /// Flags for opening a connection.
 #[derive(Clone, Copy, Debug)]
-pub struct OpenFlags(c_int);
+pub struct OpenFlags(c_int, Option<Encoding>);
 
+/// A handle for interrupting the statements of a connection from another thread.
+///
+/// The handle can outlive the connection, in which case interrupting has no effect.
+#[derive(Clone)]
+pub struct InterruptHandle(Arc<Mutex<Option<Raw>>>);
+
+/// A handle for reading the label of a connection, for instance, from within callbacks.
+///
+/// The handle reflects later changes to the label and can outlive the connection.
+#[derive(Clone, Debug)]
+pub struct TagHandle(Arc<Mutex<Option<String>>>);
+
+/// Options for attaching a database.
+#[derive(Clone, Debug, Default)]
+pub struct AttachOptions {
+    #[cfg(feature = "encryption")]
+    key: Option<String>,
+    parameters: Vec<(String, String)>,
+}
+
+/// Information about a prepared statement that has not been finalized.
+#[derive(Clone, Debug)]
+pub struct StatementInfo {
+    sql: String,
+    busy: bool,
+}
+
+/// Statistics about busy events of a connection.
+#[derive(Clone, Debug, Default)]
+pub struct BusyStatistics {
+    events: usize,
+    retries: usize,
+    wait: Duration,
+    pub(crate) tag: Option<String>,
+}
+
+/// Options for copying a table between connections.
+#[derive(Clone, Copy, Debug)]
+pub struct CopyOptions {
+    batch_size: usize,
+    indices: bool,
+    replace: bool,
+}
+
+#[derive(Default)]
+struct Hooks {
+    count: usize,
+    callbacks: HashMap<Kind, (usize, Box<dyn Send>)>,
+}
+
+type Initializer = Arc<dyn Fn(&Connection) -> Result<()> + Send + Sync>;
+
+#[derive(Clone, Copy)]
 struct Raw(*mut ffi::sqlite3);
 
+thread_local! {
+    // The busy events of the statement being evaluated on the thread if any.
+    static STEP: Cell<Option<BusyStatistics>> = const { Cell::new(None) };
+}
+
+struct Iteration<F> {
+    callback: F,
+    error: Option<Error>,
+    stopped: bool,
+}
+
 impl Connection {
     /// Open a read-write connection to a new or existing database.
     pub fn open<T: AsRef<Path>>(path: T) -> Result<Connection> {
@@ -30,36 +127,18 @@ impl Connection {
 
     /// Open a connection with specific flags.
     pub fn open_with_flags<T: AsRef<Path>>(path: T, flags: OpenFlags) -> Result<Connection> {
-        let mut raw = std::ptr::null_mut();
-        unsafe {
-            let code = ffi::sqlite3_open_v2(
-                path_to_cstr!(path.as_ref()).as_ptr(),
-                &mut raw,
-                flags.0,
-                std::ptr::null(),
-            );
-            match code {
-                ffi::SQLITE_OK => {}
-                code => match crate::error::last(raw) {
-                    Some(error) => {
-                        ffi::sqlite3_close(raw);
-                        return Err(error);
-                    }
-                    _ => {
-                        ffi::sqlite3_close(raw);
-                        return Err(crate::error::Error {
-                            code: Some(code as isize),
-                            message: None,
-                        });
-                    }
-                },
-            }
-        }
-        Ok(Connection {
-            raw: Raw(raw),
-            busy_callback: None,
-            phantom: PhantomData,
-        })
+        open(path.as_ref(), flags, None)
+    }
+
+    /// Open a connection with specific flags using a specific virtual file system.
+    ///
+    /// See the `vfs` module for further details.
+    pub fn open_with_flags_and_vfs<T: AsRef<Path>, U: AsRef<str>>(
+        path: T,
+        flags: OpenFlags,
+        vfs: U,
+    ) -> Result<Connection> {
+        open(path.as_ref(), flags, Some(vfs.as_ref()))
     }
 
     /// Open a thread-safe read-write connection to a new or existing database.
@@ -82,62 +161,807 @@ impl Connection {
         Connection::open_with_flags(path, flags.with_full_mutex()).map(ConnectionThreadSafe)
     }
 
-    #[doc(hidden)]
+    /// Open a read-write connection to an existing database that cannot be trusted.
+    ///
+    /// The connection is hardened for databases received from users as follows: defensive mode
+    /// is enabled, the schema is not trusted, double-quoted string literals are rejected,
+    /// memory-mapped I/O is disabled, cell sizes are checked, and the database passes a quick
+    /// integrity check before being returned.
+    pub fn open_untrusted<T: AsRef<Path>>(path: T) -> Result<Connection> {
+        let connection = Connection::open_with_flags(path, OpenFlags::new().with_read_write())?;
+        connection.set_defensive(true)?;
+        connection.set_trusted_schema(false)?;
+        db_config(&connection, ffi::SQLITE_DBCONFIG_DQS_DML, false)?;
+        db_config(&connection, ffi::SQLITE_DBCONFIG_DQS_DDL, false)?;
+        connection.execute("PRAGMA cell_size_check = ON; PRAGMA mmap_size = 0")?;
+        let mut statement = connection.prepare("PRAGMA quick_check")?;
+        while let State::Row = statement.next()? {
+            let message = statement.read::<String, _>(0)?;
+            if message != "ok" {
+                raise!("the database has failed the integrity check ({message})");
+            }
+        }
+        drop(statement);
+        Ok(connection)
+    }
+
+    /// Open another connection to the same database.
+    ///
+    /// The new connection is opened with the same flags and virtual file system, and the
+    /// initializers added via `add_initializer` are run on it in the same order. Databases that
+    /// are not stored in files, such as in-memory ones, cannot be cloned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let directory = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = directory.path().join("database.sqlite3");
+    /// let writer = sqlite::open(path)?;
+    /// writer.add_initializer(|connection| connection.execute("PRAGMA foreign_keys = ON"))?;
+    /// let reader = writer.try_clone()?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn try_clone(&self) -> Result<Connection> {
+        let path = unsafe {
+            let path = ffi::sqlite3_db_filename(self.raw.0, c"main".as_ptr());
+            if path.is_null() {
+                String::new()
+            } else {
+                c_str_to_string!(path)
+            }
+        };
+        if path.is_empty() {
+            raise!("cannot clone a connection to a database without a file");
+        }
+        let connection = open(Path::new(&path), self.flags, self.vfs.as_deref())?;
+        let initializers = self.initializers.lock().unwrap().clone();
+        for initializer in initializers.iter() {
+            initializer(&connection)?;
+        }
+        *connection.initializers.lock().unwrap() = initializers;
+        Ok(connection)
+    }
+
+    /// Run a callback on the connection and record it for the connections cloned via
+    /// `try_clone`.
+    ///
+    /// The callback is suitable for setting pragmas and registering functions, collations, and
+    /// modules that should be available to all the connections. It is recorded only if it
+    /// succeeds.
+    pub fn add_initializer<F>(&self, callback: F) -> Result<()>
+    where
+        F: Fn(&Connection) -> Result<()> + Send + Sync + 'static,
+    {
+        callback(self)?;
+        self.initializers.lock().unwrap().push(Arc::new(callback));
+        Ok(())
+    }
+
+    /// Return a handle for interrupting the statements of the connection from another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let handle = connection.interrupt_handle();
+    /// std::thread::spawn(move || handle.interrupt()).join().unwrap();
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle(self.interrupt.clone())
+    }
+
+    /// Create a connection from a raw handle.
+    ///
+    /// The connection takes ownership of the handle and closes it when dropped. The handle is
+    /// assumed to have been opened with `OpenFlags::new().with_create().with_read_write()`, which
+    /// are the flags used by `try_clone`.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to be valid, and it should not be used or closed by anything else while the
+    /// connection is alive.
+    pub unsafe fn from_raw(raw: *mut ffi::sqlite3) -> Connection {
+        adopt(raw, OpenFlags::new().with_create().with_read_write(), None)
+    }
+
+    /// Create a connection from a raw handle owned by someone else.
+    ///
+    /// Unlike `from_raw`, the connection does not close the handle when dropped; it only removes
+    /// the hooks set via it.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to be valid and stay open while the connection is alive.
+    pub unsafe fn from_raw_borrowed(raw: *mut ffi::sqlite3) -> Connection {
+        let mut connection = Connection::from_raw(raw);
+        connection.owned = false;
+        connection
+    }
+
+    /// Return the raw handle.
+    ///
+    /// The handle stays owned by the connection and is valid as long as the connection is alive.
     #[inline]
     pub fn as_raw(&self) -> *mut ffi::sqlite3 {
         self.raw.0
     }
+
+    /// Release the raw handle without closing it.
+    ///
+    /// The hooks set via the connection are removed, the statements cached via `prepare_cached`
+    /// are finalized, and the caller becomes responsible for closing the handle via
+    /// `sqlite3_close` or `sqlite3_close_v2`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let raw = connection.into_raw();
+    /// let connection = unsafe { sqlite::Connection::from_raw(raw) };
+    /// connection.execute("CREATE TABLE users (name TEXT)")?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn into_raw(mut self) -> *mut ffi::sqlite3 {
+        self.clear_statement_cache();
+        let kinds = self
+            .hooks
+            .lock()
+            .unwrap()
+            .callbacks
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        for kind in kinds {
+            self.uninstall_hook(kind);
+        }
+        self.interrupt.lock().unwrap().take();
+        crate::log::unregister_tag(self.raw.0, &self.tag);
+        std::mem::replace(&mut self.raw.0, std::ptr::null_mut())
+    }
 }
 
 impl Connection {
     /// Execute a statement without processing the resulting rows if any.
-    #[inline]
+    ///
+    /// The statement can consist of several statements separated by semicolons. The offset of an
+    /// error, if available, is relative to the beginning of the whole text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let error = connection
+    ///     .execute("CREATE TABLE users (name TEXT); SELECT nme FROM users")
+    ///     .unwrap_err();
+    /// assert_eq!(error.offset(), Some(39));
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
     pub fn execute<T: AsRef<str>>(&self, statement: T) -> Result<()> {
-        unsafe {
-            ok!(
-                self.raw.0,
-                ffi::sqlite3_exec(
-                    self.raw.0,
-                    str_to_cstr!(statement.as_ref()).as_ptr(),
-                    None,
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                )
-            );
+        let statement = str_to_cstr!(statement.as_ref());
+        let start = statement.as_ptr();
+        let mut tail = start;
+        crate::log::scope(self.raw.0, || unsafe {
+            while *tail != 0 {
+                let current = tail;
+                let mut raw = std::ptr::null_mut();
+                let mut code =
+                    ffi::sqlite3_prepare_v2(self.raw.0, current, -1, &mut raw, &mut tail);
+                let prepared = code == ffi::SQLITE_OK;
+                if prepared && !raw.is_null() {
+                    loop {
+                        code = ffi::sqlite3_step(raw);
+                        if code != ffi::SQLITE_ROW {
+                            break;
+                        }
+                    }
+                    if code == ffi::SQLITE_DONE {
+                        code = ffi::SQLITE_OK;
+                    }
+                }
+                let error = match code {
+                    ffi::SQLITE_OK => None,
+                    code => Some(crate::error::from_code(self.raw.0, code)),
+                };
+                ffi::sqlite3_finalize(raw);
+                if let Some(mut error) = error {
+                    let shift = current.offset_from(start) as usize;
+                    error.offset = error.offset.map(|offset| offset + shift as c_int);
+                    if self.error_context != ErrorContext::Disabled {
+                        let rest = std::ffi::CStr::from_ptr(current).to_bytes();
+                        // A statement that could not be prepared is taken to extend to the next
+                        // semicolon, as the tail then points at the error.
+                        let length = if prepared {
+                            tail.offset_from(current) as usize
+                        } else {
+                            rest.iter()
+                                .position(|&byte| byte == b';')
+                                .map_or(rest.len(), |position| position + 1)
+                        };
+                        let sql = String::from_utf8_lossy(&rest[..length]);
+                        error.sql = Some(sql.trim().to_string());
+                    }
+                    return Err(error);
+                }
+                if tail.is_null() || tail == current {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Execute a script statement by statement.
+    ///
+    /// Unlike `execute`, a failure is reported with the index, position, and text of the failed
+    /// statement, which makes it suitable for migration scripts. The statements preceding the
+    /// failed one stay executed. The function returns the number of executed statements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let error = connection
+    ///     .execute_batch("CREATE TABLE users (name TEXT); INSERT INTO user VALUES ('Alice');")
+    ///     .unwrap_err();
+    /// assert_eq!(error.index, 1);
+    /// assert_eq!(error.offset, 32);
+    /// assert_eq!(error.sql, "INSERT INTO user VALUES ('Alice');");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn execute_batch<T: AsRef<str>>(
+        &self,
+        script: T,
+    ) -> std::result::Result<usize, BatchError> {
+        let script = script.as_ref();
+        let mut tail = script;
+        let mut index = 0;
+        loop {
+            let current = tail;
+            let shift = script.len() - current.len();
+            let result =
+                crate::statement::new_next(self.raw.0, &mut tail, ErrorContext::Disabled, None)
+                    .and_then(|statement| match statement {
+                        Some(mut statement) => {
+                            while let State::Row = statement.next()? {}
+                            Ok(true)
+                        }
+                        _ => Ok(false),
+                    });
+            match result {
+                Ok(true) => index += 1,
+                Ok(false) => return Ok(index),
+                Err(mut error) => {
+                    let sql = if tail.len() < current.len() {
+                        &current[..current.len() - tail.len()]
+                    } else {
+                        current
+                            .find(';')
+                            .map_or(current, |position| &current[..=position])
+                    };
+                    let trimmed = sql.trim_start();
+                    error.offset = error.offset.map(|offset| offset + shift as c_int);
+                    return Err(BatchError {
+                        index,
+                        offset: shift + sql.len() - trimmed.len(),
+                        sql: trimmed.trim_end().to_string(),
+                        error,
+                    });
+                }
+            }
         }
+    }
+
+    /// Execute a statement with parameters.
+    ///
+    /// The statement is prepared, bound, evaluated to completion, and finalized. Unlike
+    /// `execute`, only the first statement of the given text is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::Value;
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT, age INTEGER)")?;
+    /// connection.execute_with("INSERT INTO users VALUES (?, 42)", (1, "Alice"))?;
+    /// connection.execute_with(
+    ///     "INSERT INTO users VALUES (:name, :age)",
+    ///     &[(":name", Value::from("Bob")), (":age", Value::from(69))][..],
+    /// )?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn execute_with<T: AsRef<str>, U: Bindable>(&self, statement: T, values: U) -> Result<()> {
+        let mut statement = self.prepare(statement)?;
+        statement.bind(values)?;
+        while let State::Row = statement.next()? {}
         Ok(())
     }
 
+    /// Execute a statement with parameters and return its only row.
+    ///
+    /// It is an error if the statement produces no rows or more than one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice')")?;
+    /// let row = connection.query_row("SELECT name FROM users WHERE rowid = ?", (1, 1))?;
+    /// assert_eq!(row.read::<&str, _>("name"), "Alice");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn query_row<T: AsRef<str>, U: Bindable>(&self, statement: T, values: U) -> Result<Row> {
+        match self.query_optional(statement, values)? {
+            Some(row) => Ok(row),
+            _ => raise!("the statement has produced no rows"),
+        }
+    }
+
+    /// Execute a statement with parameters and return its only row if any.
+    ///
+    /// It is an error if the statement produces more than one row.
+    pub fn query_optional<T: AsRef<str>, U: Bindable>(
+        &self,
+        statement: T,
+        values: U,
+    ) -> Result<Option<Row>> {
+        let mut cursor = self.prepare(statement)?.into_iter().bind(values)?;
+        let row = match cursor.next() {
+            Some(row) => row?,
+            _ => return Ok(None),
+        };
+        if cursor.try_next()?.is_some() {
+            raise!("the statement has produced more than one row");
+        }
+        Ok(Some(row))
+    }
+
+    /// Execute a statement with parameters and return the first column of the first row.
+    ///
+    /// It is an error if the statement produces no rows. Other rows, if any, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (age INTEGER); INSERT INTO users VALUES (42)")?;
+    /// let query = "SELECT count(*) FROM users WHERE age > ?";
+    /// assert_eq!(connection.query_scalar::<i64, _, _>(query, (1, 18))?, 1);
+    /// let age = connection.query_scalar::<Option<i64>, _, _>(
+    ///     "SELECT max(age) FROM users WHERE age > ?",
+    ///     (1, 50),
+    /// )?;
+    /// assert_eq!(age, None);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn query_scalar<T, U, V>(&self, statement: U, values: V) -> Result<T>
+    where
+        T: ReadableWithIndex,
+        U: AsRef<str>,
+        V: Bindable,
+    {
+        let mut statement = self.prepare(statement)?;
+        statement.bind(values)?;
+        if let State::Done = statement.next()? {
+            raise!("the statement has produced no rows");
+        }
+        statement.read(0)
+    }
+
+    /// Execute a statement with parameters and check if it produces at least one row.
+    ///
+    /// The evaluation stops after the first row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice')")?;
+    /// assert!(connection.exists("SELECT 1 FROM users WHERE name = ?", (1, "Alice"))?);
+    /// assert!(!connection.exists("SELECT 1 FROM users WHERE name = ?", (1, "Bob"))?);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn exists<T: AsRef<str>, U: Bindable>(&self, statement: T, values: U) -> Result<bool> {
+        let mut statement = self.prepare(statement)?;
+        statement.bind(values)?;
+        Ok(statement.next()? == State::Row)
+    }
+
+    /// Execute statements changing the schema.
+    ///
+    /// The statements are executed within a transaction started with `BEGIN IMMEDIATE`, so that
+    /// the write lock is taken up front instead of being upgraded to in the middle, and the whole
+    /// transaction is retried with `Backoff::new` while it fails with `SQLITE_BUSY` or
+    /// `SQLITE_LOCKED`. The function returns whether the schema version has changed, which is
+    /// not the case, for instance, for `CREATE TABLE IF NOT EXISTS` with an existing table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let query = "CREATE TABLE IF NOT EXISTS users (name TEXT)";
+    /// assert!(connection.execute_ddl(query)?);
+    /// assert!(!connection.execute_ddl(query)?);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn execute_ddl<T: AsRef<str>>(&self, statement: T) -> Result<bool> {
+        crate::alter::execute_ddl(self, statement.as_ref())
+    }
+
     /// Execute a statement and process the resulting rows as plain text.
     ///
     /// The callback is triggered for each row. If the callback returns `false`, no more rows will
-    /// be processed. For large queries and non-string data types, prepared statement are highly
-    /// preferable; see `prepare`.
+    /// be processed, which is not considered an error. For large queries and non-string data
+    /// types, prepared statement are highly preferable; see `prepare`.
     #[inline]
-    pub fn iterate<T: AsRef<str>, F>(&self, statement: T, callback: F) -> Result<()>
+    pub fn iterate<T: AsRef<str>, F>(&self, statement: T, mut callback: F) -> Result<()>
     where
         F: FnMut(&[(&str, Option<&str>)]) -> bool,
     {
-        unsafe {
-            let callback = Box::new(callback);
-            ok!(
+        self.try_iterate(statement, |pairs| Ok(callback(pairs)))
+    }
+
+    /// Execute a statement and process the resulting rows as plain text with a fallible
+    /// callback.
+    ///
+    /// The callback is triggered for each row. If the callback returns `Ok(false)`, no more rows
+    /// will be processed, which is not considered an error. If the callback returns an error, no
+    /// more rows will be processed either, and the error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (age INTEGER); INSERT INTO users VALUES (42)")?;
+    /// let mut total = 0;
+    /// connection.try_iterate("SELECT age FROM users", |pairs| {
+    ///     total += pairs[0].1.unwrap_or("0").parse::<i64>().map_err(|error| {
+    ///         sqlite::Error::new(None, Some(error.to_string()))
+    ///     })?;
+    ///     Ok(true)
+    /// })?;
+    /// assert_eq!(total, 42);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn try_iterate<T: AsRef<str>, F>(&self, statement: T, callback: F) -> Result<()>
+    where
+        F: FnMut(&[(&str, Option<&str>)]) -> Result<bool>,
+    {
+        let mut iteration = Iteration {
+            callback,
+            error: None,
+            stopped: false,
+        };
+        let statement = str_to_cstr!(statement.as_ref());
+        let code = crate::log::scope(self.raw.0, || unsafe {
+            ffi::sqlite3_exec(
                 self.raw.0,
-                ffi::sqlite3_exec(
-                    self.raw.0,
-                    str_to_cstr!(statement.as_ref()).as_ptr(),
-                    Some(process_callback::<F>),
-                    &*callback as *const F as *mut F as *mut _,
-                    std::ptr::null_mut(),
-                )
-            );
+                statement.as_ptr(),
+                Some(process_callback::<F>),
+                &mut iteration as *mut Iteration<F> as *mut _,
+                std::ptr::null_mut(),
+            )
+        });
+        if let Some(error) = iteration.error {
+            return Err(error);
+        }
+        match code {
+            ffi::SQLITE_OK => Ok(()),
+            ffi::SQLITE_ABORT if iteration.stopped => Ok(()),
+            code => Err(crate::error::from_code(self.raw.0, code)),
         }
-        Ok(())
     }
 
     /// Create a prepared statement.
+    ///
+    /// Only the first statement is prepared, and the rest of the text is ignored; see
+    /// `prepare_next` for processing several statements.
     #[inline]
     pub fn prepare<T: AsRef<str>>(&self, statement: T) -> Result<Statement<'_>> {
-        crate::statement::new(self.raw.0, statement)
+        crate::statement::new(
+            self.raw.0,
+            statement,
+            self.error_context,
+            self.redaction.clone(),
+        )
+    }
+
+    /// Create a prepared statement from the first statement of a script and advance the script
+    /// past it.
+    ///
+    /// Empty statements, whitespace, and comments are skipped, and `None` is returned once
+    /// there are no statements left. In case of an error, the script is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let mut script = "
+    ///     CREATE TABLE users (name TEXT);
+    ///     INSERT INTO users VALUES (:name);
+    /// ";
+    /// while let Some(mut statement) = connection.prepare_next(&mut script)? {
+    ///     if statement.parameter_index(":name")?.is_some() {
+    ///         statement.bind((":name", "Alice"))?;
+    ///     }
+    ///     statement.next()?;
+    /// }
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn prepare_next(&self, statement: &mut &str) -> Result<Option<Statement<'_>>> {
+        crate::statement::new_next(
+            self.raw.0,
+            statement,
+            self.error_context,
+            self.redaction.clone(),
+        )
+    }
+
+    /// Create a prepared statement with specific flags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::PrepareFlags;
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// let flags = PrepareFlags::new().with_persistent();
+    /// let statement = connection.prepare_with_flags("SELECT 1", flags)?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn prepare_with_flags<T: AsRef<str>>(
+        &self,
+        statement: T,
+        flags: PrepareFlags,
+    ) -> Result<Statement<'_>> {
+        crate::statement::new_with_flags(
+            self.raw.0,
+            statement,
+            flags,
+            self.error_context,
+            self.redaction.clone(),
+        )
+    }
+
+    /// Create a prepared statement or reuse one from the cache.
+    ///
+    /// The cache is keyed by the SQL text and evicts the least recently used statements beyond
+    /// its capacity, which is 16 by default. The statement is returned to the cache when the
+    /// guard is dropped. A statement that is in use is not shared; preparing the same text again
+    /// in the meantime yields a new one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT)")?;
+    /// for name in ["Alice", "Bob"] {
+    ///     let mut statement = connection.prepare_cached("INSERT INTO users VALUES (?)")?;
+    ///     statement.bind((1, name))?;
+    ///     statement.next()?;
+    /// }
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn prepare_cached<T: AsRef<str>>(&self, statement: T) -> Result<CachedStatement<'_>> {
+        crate::cache::prepare(
+            &self.statements,
+            self.raw.0,
+            statement.as_ref(),
+            self.error_context,
+            self.redaction.clone(),
+        )
+    }
+
+    /// Set the number of statements kept by `prepare_cached`.
+    ///
+    /// The least recently used statements beyond the capacity are finalized, and zero disables
+    /// caching.
+    #[inline]
+    pub fn set_statement_cache_capacity(&self, capacity: usize) {
+        self.statements.lock().unwrap().set_capacity(capacity);
+    }
+
+    /// Finalize the statements kept by `prepare_cached`.
+    #[inline]
+    pub fn clear_statement_cache(&self) {
+        self.statements.lock().unwrap().clear();
+    }
+
+    /// Return the capabilities of the linked library.
+    #[inline]
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        crate::capabilities::new(self)
+    }
+
+    /// Begin a transaction.
+    ///
+    /// The transaction is rolled back when the returned guard is dropped without an explicit
+    /// commit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// # connection.execute("CREATE TABLE users (name TEXT)").unwrap();
+    /// let transaction = connection.transaction()?;
+    /// transaction.execute("INSERT INTO users VALUES ('Alice')")?;
+    /// transaction.commit()?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn transaction(&self) -> Result<Transaction<'_>> {
+        crate::transaction::new(self, TransactionBehavior::Deferred)
+    }
+
+    /// Begin a read transaction in which successive queries see the same data.
+    ///
+    /// The guard is suitable for serving several pages of a result set consistently. See
+    /// `PinnedReader` for the interaction with writers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let directory = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = directory.path().join("database.sqlite3");
+    /// let connection = sqlite::open(&path)?;
+    /// connection.execute("PRAGMA journal_mode = WAL; CREATE TABLE users (name TEXT)")?;
+    /// let reader = connection.pinned_reader()?;
+    /// sqlite::open(&path)?.execute("INSERT INTO users VALUES ('Alice')")?;
+    /// let mut statement = reader.prepare("SELECT count(*) FROM users")?;
+    /// statement.next()?;
+    /// assert_eq!(statement.read::<i64, _>(0)?, 0);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn pinned_reader(&self) -> Result<PinnedReader<'_>> {
+        crate::transaction::pin(self)
+    }
+
+    /// Begin a transaction with a specific behavior.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::TransactionBehavior;
+    ///
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// # connection.execute("CREATE TABLE users (name TEXT)").unwrap();
+    /// let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    /// transaction.execute("INSERT INTO users VALUES ('Alice')")?;
+    /// transaction.commit()?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn transaction_with_behavior(
+        &self,
+        behavior: TransactionBehavior,
+    ) -> Result<Transaction<'_>> {
+        crate::transaction::new(self, behavior)
+    }
+
+    /// Run a closure within a transaction.
+    ///
+    /// The transaction is committed if the closure succeeds and rolled back if it fails or
+    /// panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// # connection.execute("CREATE TABLE users (name TEXT)").unwrap();
+    /// connection.with_transaction(|transaction| {
+    ///     transaction.execute("INSERT INTO users VALUES ('Alice')")?;
+    ///     transaction.execute("INSERT INTO users VALUES ('Bob')")
+    /// })?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn with_transaction<F, T>(&self, callback: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction) -> Result<T>,
+    {
+        let transaction = self.transaction()?;
+        let value = callback(&transaction)?;
+        transaction.commit()?;
+        Ok(value)
+    }
+
+    /// Run a closure within a transaction if a row has a specific version.
+    ///
+    /// The table is expected to have an integer column named `version`. Within a transaction
+    /// started with `BEGIN IMMEDIATE`, the version of the row with the given rowid is incremented
+    /// if it is equal to the expected one, and the closure is then run and the transaction
+    /// committed. If the row has another version or does not exist, nothing is changed, and the
+    /// function returns `None`, which typically means that the row has been modified since it
+    /// was read, and the caller should reload it and try again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute(
+    ///     "
+    ///     CREATE TABLE users (name TEXT, version INTEGER NOT NULL DEFAULT 0);
+    ///     INSERT INTO users (name) VALUES ('Alice');
+    ///     ",
+    /// )?;
+    /// let result = connection.with_version_check("users", 1, 0, |transaction| {
+    ///     transaction.execute("UPDATE users SET name = 'Bob' WHERE rowid = 1")
+    /// })?;
+    /// assert!(result.is_some());
+    /// let result = connection.with_version_check("users", 1, 0, |_| Ok(()))?;
+    /// assert!(result.is_none());
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn with_version_check<T, F, U>(
+        &self,
+        table: T,
+        rowid: i64,
+        version: i64,
+        callback: F,
+    ) -> Result<Option<U>>
+    where
+        T: AsRef<str>,
+        F: FnOnce(&Transaction) -> Result<U>,
+    {
+        let transaction = self.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut statement = self.prepare(format!(
+            "UPDATE {} SET version = version + 1 WHERE rowid = ? AND version = ?",
+            crate::transaction::quote(table.as_ref()),
+        ))?;
+        statement.bind((1, rowid))?;
+        statement.bind((2, version))?;
+        statement.next()?;
+        drop(statement);
+        if self.change_count() == 0 {
+            transaction.rollback()?;
+            return Ok(None);
+        }
+        let value = callback(&transaction)?;
+        transaction.commit()?;
+        Ok(Some(value))
+    }
+
+    /// Return a number that changes when another connection commits changes to the main
+    /// database.
+    ///
+    /// The number is the one of `PRAGMA data_version`. Changes made via the connection itself do
+    /// not affect it, and it is suitable for detecting whether cached data are stale.
+    pub fn data_version(&self) -> Result<i64> {
+        let mut statement = self.prepare("PRAGMA data_version")?;
+        statement.next()?;
+        statement.read(0)
+    }
+
+    /// Return the names of the schemas.
+    ///
+    /// The list starts with `main` and `temp`, which are followed by the aliases of the attached
+    /// databases in the order of attachment.
+    pub fn schema_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        unsafe {
+            loop {
+                let name = ffi::sqlite3_db_name(self.raw.0, names.len() as c_int);
+                if name.is_null() {
+                    break;
+                }
+                names.push(c_str_to_string!(name));
+            }
+        }
+        names
+    }
+
+    /// Return the rowid of the most recent successful INSERT into a rowid table.
+    ///
+    /// If no such INSERT has been performed on the connection, the function returns zero.
+    #[inline]
+    pub fn last_insert_rowid(&self) -> i64 {
+        unsafe { ffi::sqlite3_last_insert_rowid(self.raw.0) }
     }
 
     /// Return the number of rows inserted, updated, or deleted by the most recent INSERT, UPDATE,
@@ -147,24 +971,799 @@ impl Connection {
         unsafe { ffi::sqlite3_changes(self.raw.0) as usize }
     }
 
-    /// Return the total number of rows inserted, updated, and deleted by all INSERT, UPDATE, and
-    /// DELETE statements since the connection was opened.
+    /// Return the prepared statements that have not been finalized.
+    ///
+    /// The list includes both statements that are being evaluated and those that are idle.
+    pub fn busy_statements(&self) -> Vec<StatementInfo> {
+        let mut statements = Vec::new();
+        unsafe {
+            let mut raw = ffi::sqlite3_next_stmt(self.raw.0, std::ptr::null_mut());
+            while !raw.is_null() {
+                let sql = ffi::sqlite3_sql(raw);
+                statements.push(StatementInfo {
+                    sql: if sql.is_null() {
+                        String::new()
+                    } else {
+                        c_str_to_string!(sql)
+                    },
+                    busy: ffi::sqlite3_stmt_busy(raw) != 0,
+                });
+                raw = ffi::sqlite3_next_stmt(self.raw.0, raw);
+            }
+        }
+        statements
+    }
+
+    /// Return the number of prepared statements that have not been finalized.
+    ///
+    /// If the number is not zero when the connection is dropped, closing the database is
+    /// deferred until the last of the statements is finalized.
+    pub fn strong_statement_count(&self) -> usize {
+        let mut count = 0;
+        unsafe {
+            let mut raw = ffi::sqlite3_next_stmt(self.raw.0, std::ptr::null_mut());
+            while !raw.is_null() {
+                count += 1;
+                raw = ffi::sqlite3_next_stmt(self.raw.0, raw);
+            }
+        }
+        count
+    }
+
+    /// Report the prepared statements that have not been finalized when the connection is
+    /// dropped.
+    ///
+    /// The statements are reported with `SQLITE_WARNING` to the callback set via `config_log`.
+    /// Such statements prevent the database from being closed properly.
+    #[inline]
+    pub fn set_leak_detection(&mut self, enabled: bool) {
+        self.leak_detection = enabled;
+    }
+
+    /// Attach an application-defined label to the connection.
+    ///
+    /// The label is meant to attribute activity in services with many connections. It prefixes
+    /// the messages logged via `config_log` while the connection prepares or evaluates statements,
+    /// including the report of leaked statements, and it is passed to the callback set via
+    /// `set_trace` and included in `busy_statistics`. Other callbacks can read it via
+    /// `tag_handle`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let flags = sqlite::TraceFlags::new().with_statement();
+    /// let _hook = connection.set_trace(flags, |trace, tag| {
+    ///     if let sqlite::Trace::Statement(sql) = trace {
+    ///         eprintln!("[{}] {sql}", tag.unwrap_or_default());
+    ///     }
+    /// });
+    /// connection.set_tag("tenant-42");
+    /// connection.execute("SELECT 1")?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn set_tag<T: Into<String>>(&self, tag: T) {
+        *self.tag.lock().unwrap() = Some(tag.into());
+        crate::log::register_tag(self.raw.0, &self.tag);
+    }
+
+    /// Return the label attached via `set_tag`.
+    #[inline]
+    pub fn tag(&self) -> Option<String> {
+        self.tag.lock().unwrap().clone()
+    }
+
+    /// Return a handle for reading the label attached via `set_tag`.
+    #[inline]
+    pub fn tag_handle(&self) -> TagHandle {
+        TagHandle(self.tag.clone())
+    }
+
+    /// Attach the SQL text of failed statements to errors.
+    ///
+    /// The setting applies to the statements prepared afterwards and is meant for debugging, as
+    /// the text, especially the expanded one, might contain sensitive data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::ErrorContext;
+    ///
+    /// let mut connection = sqlite::open(":memory:")?;
+    /// connection.set_error_context(ErrorContext::ExpandedSql);
+    /// connection.execute("CREATE TABLE users (name TEXT NOT NULL)")?;
+    /// let mut statement = connection.prepare("INSERT INTO users VALUES (?)")?;
+    /// statement.bind((1, None::<&str>))?;
+    /// let error = statement.next().unwrap_err();
+    /// assert_eq!(error.sql().unwrap(), "INSERT INTO users VALUES (NULL)");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn set_error_context(&mut self, value: ErrorContext) {
+        self.error_context = value;
+    }
+
+    /// Set a policy for redacting bound values in the SQL text attached to errors and returned by
+    /// `Statement::expanded_sql`.
+    ///
+    /// The setting applies to the statements prepared afterwards. See `Redaction` for further
+    /// details.
+    #[inline]
+    pub fn set_redaction(&mut self, value: Redaction) {
+        self.redaction = Some(Arc::new(value));
+    }
+
+    /// Return the text encoding of the main database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::{Connection, Encoding, OpenFlags};
+    ///
+    /// let flags = OpenFlags::new()
+    ///     .with_create()
+    ///     .with_read_write()
+    ///     .with_encoding(Encoding::Utf16le);
+    /// let connection = Connection::open_with_flags(":memory:", flags)?;
+    /// assert_eq!(connection.text_encoding()?, Encoding::Utf16le);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn text_encoding(&self) -> Result<Encoding> {
+        let mut statement = self.prepare("PRAGMA encoding")?;
+        statement.next()?;
+        let value = statement.read::<String, _>(0)?;
+        Ok(match value.as_str() {
+            "UTF-8" => Encoding::Utf8,
+            "UTF-16le" => Encoding::Utf16le,
+            "UTF-16be" => Encoding::Utf16be,
+            _ => raise!("the text encoding is unknown ({})", value),
+        })
+    }
+
+    /// Return the total number of rows inserted, updated, and deleted by all INSERT, UPDATE, and
+    /// DELETE statements since the connection was opened.
+    #[inline]
+    pub fn total_change_count(&self) -> usize {
+        unsafe { ffi::sqlite3_total_changes(self.raw.0) as usize }
+    }
+}
+
+impl Connection {
+    /// Attach a database under a schema name.
+    #[inline]
+    pub fn attach<T: AsRef<Path>, U: AsRef<str>>(&self, path: T, schema: U) -> Result<()> {
+        self.attach_with_options(path, schema, &AttachOptions::new())
+    }
+
+    /// Attach a database under a schema name with specific options.
+    ///
+    /// URI parameters are honored only if the connection was opened with `OpenFlags::with_uri`
+    /// or URI processing is enabled globally.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::{AttachOptions, Connection, OpenFlags};
+    ///
+    /// let flags = OpenFlags::new().with_create().with_read_write().with_uri();
+    /// let connection = Connection::open_with_flags(":memory:", flags)?;
+    /// let options = AttachOptions::new().with_parameter("mode", "memory");
+    /// connection.attach_with_options("archive", "archive", &options)?;
+    /// connection.execute("CREATE TABLE archive.users (name TEXT)")?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn attach_with_options<T: AsRef<Path>, U: AsRef<str>>(
+        &self,
+        path: T,
+        schema: U,
+        options: &AttachOptions,
+    ) -> Result<()> {
+        let path = match path.as_ref().to_str() {
+            Some(path) => path,
+            _ => raise!("failed to process a path"),
+        };
+        let path = if options.parameters.is_empty() {
+            path.to_string()
+        } else {
+            let parameters = options
+                .parameters
+                .iter()
+                .map(|(name, value)| format!("{}={}", escape(name), escape(value)))
+                .collect::<Vec<_>>();
+            format!("file:{}?{}", escape(path), parameters.join("&"))
+        };
+        let schema = crate::transaction::quote(schema.as_ref());
+        #[cfg(feature = "encryption")]
+        if let Some(key) = options.key.as_deref() {
+            let mut statement = self.prepare(format!("ATTACH DATABASE ? AS {schema} KEY ?"))?;
+            statement.bind((1, path.as_str()))?;
+            statement.bind((2, key))?;
+            statement.next()?;
+            return Ok(());
+        }
+        let mut statement = self.prepare(format!("ATTACH DATABASE ? AS {schema}"))?;
+        statement.bind((1, path.as_str()))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    /// Detach a database.
+    pub fn detach<T: AsRef<str>>(&self, schema: T) -> Result<()> {
+        let schema = crate::transaction::quote(schema.as_ref());
+        self.execute(format!("DETACH DATABASE {schema}"))
+    }
+
+    /// Register a function.
+    ///
+    /// The callback is called for each invocation of the function and sets the result via the
+    /// context. The number of arguments can be negative, in which case the function accepts any
+    /// number of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// connection.create_function("double", 1, |context| {
+    ///     let value: i64 = context.argument(0)?.try_into()?;
+    ///     context.set_result(2 * value);
+    ///     Ok(())
+    /// })?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn create_function<F>(&self, name: &str, arguments: isize, callback: F) -> Result<()>
+    where
+        F: FnMut(&mut Context) -> Result<()> + Send + 'static,
+    {
+        crate::function::create_function(self, name, arguments, callback)?;
+        self.register_function(name, &[arguments]);
+        Ok(())
+    }
+
+    /// Register a function under several numbers of arguments.
+    ///
+    /// The callback is shared by all the registrations, and it can tell them apart via
+    /// `Context::argument_count`. SQLite prefers a registration with the exact number of arguments
+    /// over a variadic one, which has a negative number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// connection.create_overloaded_function("greet", &[0, 1], |context| {
+    ///     let name = match context.argument_count() {
+    ///         0 => "world".to_string(),
+    ///         _ => context.argument(0)?.try_into()?,
+    ///     };
+    ///     context.set_result(format!("Hello, {name}!"));
+    ///     Ok(())
+    /// })?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn create_overloaded_function<F>(
+        &self,
+        name: &str,
+        arities: &[isize],
+        callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut Context) -> Result<()> + Send + 'static,
+    {
+        crate::function::create_overloaded_function(self, name, arities, callback)?;
+        self.register_function(name, arities);
+        Ok(())
+    }
+
+    /// Register an aggregate function.
+    ///
+    /// The factory is called to create an aggregate for each group of rows. The number of
+    /// arguments can be negative, in which case the function accepts any number of them.
+    #[inline]
+    pub fn create_aggregate<T, F>(&self, name: &str, arguments: isize, factory: F) -> Result<()>
+    where
+        T: Aggregate,
+        F: Fn() -> T + Send + 'static,
+    {
+        crate::function::create_aggregate(self, name, arguments, factory)?;
+        self.register_function(name, &[arguments]);
+        Ok(())
+    }
+
+    /// Remove a function registered with a specific number of arguments.
+    ///
+    /// A function can also be replaced at any time by registering another one under the same name
+    /// and with the same number of arguments.
+    pub fn remove_function(&self, name: &str, arguments: isize) -> Result<()> {
+        unsafe {
+            ok!(
+                self.raw.0,
+                ffi::sqlite3_create_function_v2(
+                    self.raw.0,
+                    str_to_cstr!(name).as_ptr(),
+                    arguments as c_int,
+                    ffi::SQLITE_UTF8,
+                    std::ptr::null_mut(),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            );
+        }
+        self.functions
+            .lock()
+            .unwrap()
+            .remove(&(name.to_ascii_lowercase(), arguments));
+        Ok(())
+    }
+
+    /// Return the names and numbers of arguments of the registered functions.
+    ///
+    /// Built-in functions and functions registered by extensions are not included.
+    pub fn functions(&self) -> Vec<(String, isize)> {
+        self.functions.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Make the date and time functions treat `now` as a fixed point in time.
+    ///
+    /// The time is given in seconds since the Unix epoch. The functions `date`, `time`,
+    /// `datetime`, `julianday`, `unixepoch`, and `strftime` are overridden on the connection,
+    /// which also covers `CURRENT_DATE`, `CURRENT_TIME`, and `CURRENT_TIMESTAMP`, including in
+    /// column defaults. Only `now` is substituted, and the rest is evaluated by the built-in
+    /// functions. The overrides are meant for tests; calling the function again moves the clock,
+    /// and `unfreeze_time` releases it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.freeze_time(1_700_000_000)?;
+    /// let mut statement = connection.prepare("SELECT CURRENT_TIMESTAMP, date('now', '+1 day')")?;
+    /// statement.next()?;
+    /// assert_eq!(statement.read::<String, _>(0)?, "2023-11-14 22:13:20");
+    /// assert_eq!(statement.read::<String, _>(1)?, "2023-11-15");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn freeze_time(&self, unix_seconds: i64) -> Result<()> {
+        crate::clock::freeze(self, unix_seconds)
+    }
+
+    /// Make the date and time functions follow the system clock again.
+    ///
+    /// Removing the overrides installed via `freeze_time` would leave the functions undefined on
+    /// the connection. Instead, they are replaced by ones passing all arguments to the built-in
+    /// functions. The function has no effect if the time has not been frozen.
+    #[inline]
+    pub fn unfreeze_time(&self) -> Result<()> {
+        crate::clock::unfreeze(self)
+    }
+
+    fn register_function(&self, name: &str, arities: &[isize]) {
+        let mut functions = self.functions.lock().unwrap();
+        for &arguments in arities {
+            functions.insert((name.to_ascii_lowercase(), arguments));
+        }
+    }
+
+    /// Register a module of virtual tables.
+    ///
+    /// See the `vtab` module for further details.
+    #[inline]
+    pub fn create_module<T: Module>(&self, name: &str, module: T) -> Result<()> {
+        crate::vtab::create_module(self, name, module)
+    }
+
+    /// Serialize a database into a byte buffer.
+    ///
+    /// The buffer has the same content as the file the database would be stored in on disk.
+    pub fn serialize<T: AsRef<str>>(&self, schema: T) -> Result<Vec<u8>> {
+        let mut size = -1;
+        unsafe {
+            let data = ffi::sqlite3_serialize(
+                self.raw.0,
+                str_to_cstr!(schema.as_ref()).as_ptr(),
+                &mut size,
+                0,
+            );
+            if data.is_null() {
+                if size == 0 {
+                    return Ok(Vec::new());
+                }
+                raise!("failed to serialize a database ({})", schema.as_ref());
+            }
+            let value = std::slice::from_raw_parts(data, size as usize).to_vec();
+            ffi::sqlite3_free(data as *mut _);
+            Ok(value)
+        }
+    }
+
+    /// Replace a database with the content of a byte buffer.
+    ///
+    /// The database is kept in memory and can be modified and grown. The schema should be
+    /// attached beforehand, and it cannot be the one of the temporary database.
+    pub fn deserialize<T: AsRef<str>>(&self, schema: T, data: &[u8]) -> Result<()> {
+        unsafe {
+            let buffer = ffi::sqlite3_malloc64(data.len().max(1) as u64) as *mut u8;
+            if buffer.is_null() {
+                raise!("failed to allocate memory");
+            }
+            std::ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len());
+            ok!(
+                self.raw.0,
+                ffi::sqlite3_deserialize(
+                    self.raw.0,
+                    str_to_cstr!(schema.as_ref()).as_ptr(),
+                    buffer,
+                    data.len() as ffi::sqlite3_int64,
+                    data.len() as ffi::sqlite3_int64,
+                    (ffi::SQLITE_DESERIALIZE_FREEONCLOSE | ffi::SQLITE_DESERIALIZE_RESIZEABLE)
+                        as u32,
+                )
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Connection {
+    /// Set a callback for handling busy events.
+    ///
+    /// The callback is triggered when the database cannot perform an operation due to processing
+    /// of some other request. If the callback returns `true`, the operation will be repeated. The
+    /// callback stays registered until the returned hook is dropped. The events, retries, and time
+    /// spent in the callback are recorded in `busy_statistics`.
+    pub fn set_busy_handler<F>(&self, mut callback: F) -> Result<Hook<'_>>
+    where
+        F: FnMut(usize) -> bool + Send + 'static,
+    {
+        self.remove_busy_handler()?;
+        let statistics = self.busy.clone();
+        self.install_busy_handler(move |attempt| record_busy(&statistics, attempt, &mut callback))
+    }
+
+    /// Set an implicit callback for handling busy events that tries to repeat rejected operations
+    /// until a timeout expires.
+    ///
+    /// The implicit callback pauses as the built-in one of SQLite does, replaces the one set via
+    /// `set_busy_handler` if any, and stays registered until replaced or removed. The events,
+    /// retries, and time spent waiting are recorded in `busy_statistics`. A timeout of zero
+    /// removes the callback.
+    pub fn set_busy_timeout(&self, milliseconds: usize) -> Result<()> {
+        self.remove_busy_handler()?;
+        if milliseconds == 0 {
+            return Ok(());
+        }
+        let statistics = self.busy.clone();
+        let hook = self.install_busy_handler(move |attempt| {
+            record_busy(&statistics, attempt, |attempt| pause(milliseconds, attempt))
+        })?;
+        // The callback is owned by the connection and released when replaced or removed.
+        std::mem::forget(hook);
+        Ok(())
+    }
+
+    /// Set a callback for handling busy events that repeats rejected operations according to a
+    /// backoff schedule.
+    ///
+    /// The callback stays registered until the returned hook is dropped.
+    #[inline]
+    pub fn set_busy_backoff(&self, backoff: Backoff) -> Result<Hook<'_>> {
+        self.set_busy_handler(move |attempt| backoff.pause(attempt))
+    }
+
+    /// Return the statistics about busy events handled via `set_busy_handler`,
+    /// `set_busy_backoff`, or `set_busy_timeout`.
+    ///
+    /// The events of individual statements are available via `Statement::busy_statistics`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let _hook = connection.set_busy_backoff(sqlite::Backoff::new())?;
+    /// connection.execute("CREATE TABLE users (name TEXT)")?;
+    /// let statistics = connection.busy_statistics();
+    /// println!("{} retries in {:?}", statistics.retries(), statistics.wait());
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn busy_statistics(&self) -> BusyStatistics {
+        let mut statistics = self.busy.lock().unwrap().clone();
+        statistics.tag = self.tag();
+        statistics
+    }
+
+    /// Reset the statistics about busy events.
+    #[inline]
+    pub fn reset_busy_statistics(&self) {
+        *self.busy.lock().unwrap() = BusyStatistics::default();
+    }
+
+    /// Remove the callback handling busy events.
+    #[inline]
+    pub fn remove_busy_handler(&self) -> Result<()> {
+        unsafe {
+            ok!(
+                self.raw.0,
+                ffi::sqlite3_busy_handler(self.raw.0, None, std::ptr::null_mut())
+            );
+        }
+        self.hooks.lock().unwrap().callbacks.remove(&Kind::Busy);
+        Ok(())
+    }
+
+    /// Enable or disable the defensive mode.
+    ///
+    /// In the defensive mode, language features that allow ordinary SQL to deliberately corrupt
+    /// the database file are disabled.
+    #[inline]
+    pub fn set_defensive(&self, enabled: bool) -> Result<()> {
+        db_config(self, ffi::SQLITE_DBCONFIG_DEFENSIVE, enabled)
+    }
+
+    /// Specify whether SQL functions and virtual tables are trusted when used in the schema.
+    ///
+    /// If the schema is not trusted, views, triggers, and indices cannot use functions with side
+    /// effects or virtual tables that are not marked as innocuous.
+    #[inline]
+    pub fn set_trusted_schema(&self, trusted: bool) -> Result<()> {
+        db_config(self, ffi::SQLITE_DBCONFIG_TRUSTED_SCHEMA, trusted)
+    }
+}
+
+impl Connection {
+    /// Set a callback for monitoring the progress of long-running statements.
+    ///
+    /// The callback is triggered approximately every given number of virtual-machine
+    /// instructions. If the callback returns `false`, the statement being evaluated is interrupted
+    /// and fails with `SQLITE_INTERRUPT`. The callback stays registered until the returned hook is
+    /// dropped, and it should not modify the database.
+    pub fn set_progress_handler<F>(&self, instructions: usize, callback: F) -> Hook<'_>
+    where
+        F: FnMut() -> bool + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::sqlite3_progress_handler(
+                self.raw.0,
+                instructions.max(1) as c_int,
+                Some(crate::hook::progress_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
+            );
+            self.install_hook(Kind::Progress, callback)
+        }
+    }
+
+    /// Remove the callback monitoring progress.
+    #[inline]
+    pub fn remove_progress_handler(&self) {
+        self.uninstall_hook(Kind::Progress);
+    }
+}
+
+impl Connection {
+    /// Set a callback for observing rollbacks.
+    ///
+    /// The callback is triggered whenever a transaction is rolled back, be it explicitly or due to
+    /// an error or a vetoed commit, but not when the connection is closed. The callback stays
+    /// registered until the returned hook is dropped, and it should not modify the database.
+    pub fn set_rollback_hook<F>(&self, callback: F) -> Hook<'_>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::sqlite3_rollback_hook(
+                self.raw.0,
+                Some(crate::hook::rollback_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
+            );
+            self.install_hook(Kind::Rollback, callback)
+        }
+    }
+
+    /// Remove the callback observing rollbacks.
+    #[inline]
+    pub fn remove_rollback_hook(&self) {
+        self.uninstall_hook(Kind::Rollback);
+    }
+
+    /// Set a callback for observing changes.
+    ///
+    /// The callback is triggered for each row inserted, updated, or deleted in a rowid table and
+    /// receives the kind of the operation, the schema and table names, and the rowid of the row.
+    /// The callback stays registered until the returned hook is dropped, and it should not modify
+    /// the database.
+    pub fn set_update_hook<F>(&self, callback: F) -> Hook<'_>
+    where
+        F: FnMut(Operation, &str, &str, i64) + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::sqlite3_update_hook(
+                self.raw.0,
+                Some(crate::hook::update_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
+            );
+            self.install_hook(Kind::Update, callback)
+        }
+    }
+
+    /// Start recording the statements executed by the connection.
+    ///
+    /// Each statement is recorded with its SQL text with the bound parameters substituted, the
+    /// time it took, and the number of rows it modified once it finishes. The values are redacted
+    /// according to the policy set via `set_redaction` if any. Recording replaces the callback
+    /// set via `set_trace` and stops when the returned recorder is dropped or finished. See
+    /// `Session` for further details.
+    pub fn record(&self) -> Recorder<'_> {
+        let (recording, records) = crate::recorder::recording(self.redaction.clone());
+        unsafe {
+            let recording = Box::new(recording);
+            ffi::sqlite3_trace_v2(
+                self.raw.0,
+                (ffi::SQLITE_TRACE_STMT | ffi::SQLITE_TRACE_PROFILE) as _,
+                Some(crate::recorder::trace_callback),
+                &*recording as *const _ as *mut _,
+            );
+            crate::recorder::new(self.install_hook(Kind::Trace, recording), records)
+        }
+    }
+
+    /// Set a callback for tracing the execution of statements.
+    ///
+    /// The callback is triggered for the events selected via the flags and receives the label
+    /// attached via `set_tag` if any. The callback stays registered until the returned hook is
+    /// dropped, and it should not use the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::{Trace, TraceFlags};
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// let flags = TraceFlags::new().with_profile();
+    /// let _hook = connection.set_trace(flags, |trace, _| {
+    ///     if let Trace::Profile(sql, duration) = trace {
+    ///         eprintln!("{sql} took {duration:?}");
+    ///     }
+    /// });
+    /// connection.execute("CREATE TABLE users (name TEXT)")?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn set_trace<F>(&self, flags: TraceFlags, mut callback: F) -> Hook<'_>
+    where
+        F: FnMut(&Trace, Option<&str>) + Send + 'static,
+    {
+        let tag = self.tag.clone();
+        self.install_trace(flags, move |trace| {
+            let tag = tag.lock().unwrap().clone();
+            callback(trace, tag.as_deref())
+        })
+    }
+
+    /// Remove the callback tracing the execution of statements.
+    #[inline]
+    pub fn remove_trace(&self) {
+        self.uninstall_hook(Kind::Trace);
+    }
+
+    /// Remove the callback observing changes.
+    #[inline]
+    pub fn remove_update_hook(&self) {
+        self.uninstall_hook(Kind::Update);
+    }
+}
+
+impl Connection {
+    /// Set a callback for authorizing the actions of statements being prepared.
+    ///
+    /// The callback is triggered for each action, such as reading a column or inserting into a
+    /// table, while a statement is being prepared. If the callback denies an action or panics,
+    /// the preparation fails with `SQLITE_AUTH`. The callback stays registered until the returned
+    /// hook is dropped, and it should not use the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::Authorization;
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT, email TEXT)")?;
+    /// let _hook = connection.set_authorizer(|action| match action.second() {
+    ///     Some("email") => Authorization::Ignore,
+    ///     _ => Authorization::Allow,
+    /// });
+    /// connection.execute("SELECT email FROM users")?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn set_authorizer<F>(&self, callback: F) -> Hook<'_>
+    where
+        F: FnMut(&Action) -> Authorization + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::sqlite3_set_authorizer(
+                self.raw.0,
+                Some(crate::hook::authorizer_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
+            );
+            self.install_hook(Kind::Authorizer, callback)
+        }
+    }
+
+    /// Remove the callback authorizing the actions of statements.
+    #[inline]
+    pub fn remove_authorizer(&self) {
+        self.uninstall_hook(Kind::Authorizer);
+    }
+}
+
+impl Connection {
+    /// Set a callback for approving commits.
+    ///
+    /// The callback is triggered whenever a transaction is about to be committed. If the callback
+    /// returns an error or panics, the transaction is rolled back instead, and the commit fails
+    /// with `SQLITE_CONSTRAINT_COMMITHOOK`. The callback stays registered until the returned hook
+    /// is dropped, and it should not modify the database.
+    pub fn set_commit_hook<F>(&self, callback: F) -> Hook<'_>
+    where
+        F: FnMut() -> Result<()> + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::sqlite3_commit_hook(
+                self.raw.0,
+                Some(crate::hook::commit_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
+            );
+            self.install_hook(Kind::Commit, callback)
+        }
+    }
+
+    /// Remove the callback approving commits.
+    #[inline]
+    pub fn remove_commit_hook(&self) {
+        self.uninstall_hook(Kind::Commit);
+    }
+}
+
+impl Connection {
+    /// Set a callback for inspecting changes before they are made.
+    ///
+    /// The callback is triggered for each row about to be inserted, updated, or deleted,
+    /// including rows of tables without rowids, and it can read the values of the columns before
+    /// and after the change. The linked library has to be compiled with
+    /// `SQLITE_ENABLE_PREUPDATE_HOOK`. The callback stays registered until the returned hook is
+    /// dropped, and it should not modify the database.
+    #[cfg(feature = "preupdate")]
+    pub fn set_preupdate_hook<F>(&self, callback: F) -> Hook<'_>
+    where
+        F: FnMut(&Preupdate) + Send + 'static,
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            crate::hook::set_preupdate_hook(self.raw.0, Some(&*callback));
+            self.install_hook(Kind::Preupdate, callback)
+        }
+    }
+
+    /// Remove the callback inspecting changes before they are made.
+    #[cfg(feature = "preupdate")]
     #[inline]
-    pub fn total_change_count(&self) -> usize {
-        unsafe { ffi::sqlite3_total_changes(self.raw.0) as usize }
+    pub fn remove_preupdate_hook(&self) {
+        self.uninstall_hook(Kind::Preupdate);
     }
 }
 
 impl Connection {
-    /// Set a callback for handling busy events.
-    ///
-    /// The callback is triggered when the database cannot perform an operation due to processing
-    /// of some other request. If the callback returns `true`, the operation will be repeated.
-    pub fn set_busy_handler<F>(&mut self, callback: F) -> Result<()>
+    fn install_busy_handler<F>(&self, callback: F) -> Result<Hook<'_>>
     where
         F: FnMut(usize) -> bool + Send + 'static,
     {
-        self.remove_busy_handler()?;
         unsafe {
             let callback = Box::new(callback);
             let result = ffi::sqlite3_busy_handler(
@@ -172,36 +1771,73 @@ impl Connection {
                 Some(busy_callback::<F>),
                 &*callback as *const F as *mut F as *mut _,
             );
-            self.busy_callback = Some(callback);
+            let hook = self.install_hook(Kind::Busy, callback);
             ok!(self.raw.0, result);
+            Ok(hook)
         }
-        Ok(())
     }
 
-    /// Set an implicit callback for handling busy events that tries to repeat rejected operations
-    /// until a timeout expires.
-    #[inline]
-    pub fn set_busy_timeout(&mut self, milliseconds: usize) -> Result<()> {
+    fn install_trace<F>(&self, flags: TraceFlags, callback: F) -> Hook<'_>
+    where
+        F: FnMut(&Trace) + Send + 'static,
+    {
         unsafe {
-            ok!(
+            let callback = Box::new(callback);
+            ffi::sqlite3_trace_v2(
                 self.raw.0,
-                ffi::sqlite3_busy_timeout(self.raw.0, milliseconds as c_int)
+                flags.as_raw(),
+                Some(crate::hook::trace_callback::<F>),
+                &*callback as *const F as *mut F as *mut _,
             );
+            self.install_hook(Kind::Trace, callback)
         }
-        Ok(())
     }
 
-    /// Remove the callback handling busy events.
-    #[inline]
-    pub fn remove_busy_handler(&mut self) -> Result<()> {
-        self.busy_callback = None;
+    fn install_hook<F: Send + 'static>(&self, kind: Kind, callback: Box<F>) -> Hook<'_> {
+        let previous;
+        let id = {
+            let mut hooks = self.hooks.lock().unwrap();
+            hooks.count += 1;
+            let id = hooks.count;
+            previous = hooks.callbacks.insert(kind, (id, callback));
+            id
+        };
+        drop(previous);
+        crate::hook::new(self, kind, id)
+    }
+
+    fn uninstall_hook(&self, kind: Kind) {
         unsafe {
-            ok!(
-                self.raw.0,
-                ffi::sqlite3_busy_handler(self.raw.0, None, std::ptr::null_mut())
-            );
+            match kind {
+                Kind::Authorizer => {
+                    ffi::sqlite3_set_authorizer(self.raw.0, None, std::ptr::null_mut());
+                }
+                Kind::Busy => {
+                    ffi::sqlite3_busy_handler(self.raw.0, None, std::ptr::null_mut());
+                }
+                Kind::Commit => {
+                    ffi::sqlite3_commit_hook(self.raw.0, None, std::ptr::null_mut());
+                }
+                #[cfg(feature = "preupdate")]
+                Kind::Preupdate => {
+                    crate::hook::set_preupdate_hook::<fn(&Preupdate)>(self.raw.0, None);
+                }
+                Kind::Progress => {
+                    ffi::sqlite3_progress_handler(self.raw.0, 0, None, std::ptr::null_mut());
+                }
+                Kind::Rollback => {
+                    ffi::sqlite3_rollback_hook(self.raw.0, None, std::ptr::null_mut());
+                }
+                Kind::Trace => {
+                    ffi::sqlite3_trace_v2(self.raw.0, 0, None, std::ptr::null_mut());
+                }
+                Kind::Update => {
+                    ffi::sqlite3_update_hook(self.raw.0, None, std::ptr::null_mut());
+                }
+            }
         }
-        Ok(())
+        let previous = self.hooks.lock().unwrap().callbacks.remove(&kind);
+        drop(previous);
     }
 }
 
@@ -289,12 +1925,542 @@ impl Connection {
     }
 }
 
+impl Connection {
+    /// Scan a table in parallel.
+    ///
+    /// The table is split into at most the given number of partitions by equal ranges of an
+    /// integer key column, such as the rowid, and each partition is processed by the callback on its own
+    /// thread using its own connection cloned via `try_clone`. The callback receives a cursor
+    /// over all the columns of the rows in the partition, and the function returns the results of
+    /// the callback in the order of the partitions. The partitions are read in separate
+    /// transactions, and the database should therefore be in the WAL mode and not modified
+    /// concurrently for the results to be consistent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let directory = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = directory.path().join("database.sqlite3");
+    /// let connection = sqlite::open(path)?;
+    /// connection.execute("CREATE TABLE numbers (value INTEGER)")?;
+    /// connection.execute("INSERT INTO numbers VALUES (1), (2), (3), (4)")?;
+    /// let sums = connection.par_scan("numbers", "rowid", 2, |cursor| {
+    ///     let mut sum = 0;
+    ///     for row in cursor {
+    ///         sum += row?.read::<i64, _>("value");
+    ///     }
+    ///     Ok(sum)
+    /// })?;
+    /// assert_eq!(sums, [3, 7]);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn par_scan<T, U, F, R>(
+        &self,
+        table: T,
+        key_column: U,
+        partitions: usize,
+        callback: F,
+    ) -> Result<Vec<R>>
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+        F: Fn(CursorWithOwnership<'_>) -> Result<R> + Sync,
+        R: Send,
+    {
+        let table = crate::transaction::quote(table.as_ref());
+        let key_column = crate::transaction::quote(key_column.as_ref());
+        let (start, end) = {
+            let mut statement = self.prepare(format!(
+                "SELECT min({key_column}), max({key_column}) FROM {table}"
+            ))?;
+            statement.next()?;
+            match (
+                statement.read::<Option<i64>, _>(0)?,
+                statement.read::<Option<i64>, _>(1)?,
+            ) {
+                (Some(start), Some(end)) => (start as i128, end as i128 + 1),
+                _ => return Ok(Vec::new()),
+            }
+        };
+        let partitions = partitions.clamp(1, usize::try_from(end - start).unwrap_or(usize::MAX));
+        let size = (end - start + partitions as i128 - 1) / partitions as i128;
+        // Rounding the size up might leave the trailing partitions empty.
+        let partitions = ((end - start + size - 1) / size) as usize;
+        let mut connections = Vec::with_capacity(partitions);
+        for _ in 0..partitions {
+            connections.push(self.try_clone()?);
+        }
+        let query = format!(
+            "SELECT * FROM {table} WHERE {key_column} >= ? AND {key_column} <= ? \
+             ORDER BY {key_column}"
+        );
+        let callback = &callback;
+        let query = &query;
+        std::thread::scope(|scope| {
+            let handles = connections
+                .into_iter()
+                .enumerate()
+                .map(|(index, connection)| {
+                    let lower = start + index as i128 * size;
+                    let upper = (lower + size).min(end) - 1;
+                    scope.spawn(move || {
+                        let cursor = connection.prepare(query)?.into_iter().bind(
+                            &[Value::Integer(lower as i64), Value::Integer(upper as i64)][..],
+                        )?;
+                        callback(cursor)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| match handle.join() {
+                    Ok(result) => result,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                })
+                .collect()
+        })
+    }
+
+    /// Back up the main database into a file.
+    ///
+    /// The database is copied in small steps with pauses in between so that the connection and
+    /// other connections to the same database stay responsive. Steps rejected with `SQLITE_BUSY`
+    /// or `SQLITE_LOCKED` are retried, and if the database is modified by another connection
+    /// during the backup, the backup is restarted automatically.
+    #[inline]
+    pub fn backup_to_path<T: AsRef<Path>>(&self, path: T, options: &BackupOptions) -> Result<()> {
+        crate::backup::run(self, path, options)
+    }
+
+    /// Copy a table to another connection.
+    ///
+    /// The table is created in the other connection using the original schema, and the rows are
+    /// transferred in batches, each batch within its own transaction. The function returns the
+    /// number of copied rows.
+    pub fn copy_table_to<T: AsRef<str>>(
+        &self,
+        other: &Connection,
+        table: T,
+        options: CopyOptions,
+    ) -> Result<usize> {
+        let table = table.as_ref();
+        let mut definitions = Vec::new();
+        let mut statement = self.prepare(
+            "SELECT type, sql FROM sqlite_master WHERE tbl_name = ? AND sql IS NOT NULL \
+             ORDER BY type = 'table' DESC",
+        )?;
+        statement.bind((1, table))?;
+        while let State::Row = statement.next()? {
+            let kind = statement.read::<String, _>(0)?;
+            if kind == "table" || (kind == "index" && options.indices) {
+                definitions.push(statement.read::<String, _>(1)?);
+            }
+        }
+        if definitions.is_empty() {
+            raise!("the table does not exist ({})", table);
+        }
+        // Generated columns cannot be written, and implicit row identifiers are not part of `*`.
+        let mut columns = Vec::new();
+        let mut aliased = false;
+        let mut statement = self.prepare(
+            "SELECT name, type, pk, hidden, \
+             (SELECT COUNT(*) FROM pragma_table_xinfo(?1) WHERE pk > 0) = 1 \
+             FROM pragma_table_xinfo(?1)",
+        )?;
+        statement.bind((1, table))?;
+        while let State::Row = statement.next()? {
+            let name = statement.read::<String, _>(0)?;
+            let kind = statement.read::<String, _>(1)?;
+            if statement.read::<i64, _>(2)? > 0 && statement.read::<i64, _>(4)? == 1 {
+                aliased |= kind.eq_ignore_ascii_case("integer");
+            }
+            if statement.read::<i64, _>(3)? == 0 {
+                columns.push(name);
+            }
+        }
+        let mut statement = self.prepare("SELECT wr FROM pragma_table_list(?)")?;
+        statement.bind((1, table))?;
+        let rowid = matches!(statement.next()?, State::Row) && statement.read::<i64, _>(0)? == 0;
+        let rowid = ["rowid", "_rowid_", "oid"]
+            .into_iter()
+            .filter(|_| rowid && !aliased)
+            .find(|name| {
+                !columns
+                    .iter()
+                    .any(|column| column.eq_ignore_ascii_case(name))
+            });
+        let columns = rowid
+            .map(String::from)
+            .into_iter()
+            .chain(
+                columns
+                    .iter()
+                    .map(|column| crate::transaction::quote(column)),
+            )
+            .collect::<Vec<_>>();
+
+        let table = crate::transaction::quote(table);
+        if options.replace {
+            other.execute(format!("DROP TABLE IF EXISTS {table}"))?;
+        }
+        for definition in definitions.iter() {
+            other.execute(definition)?;
+        }
+
+        let names = columns.join(", ");
+        let mut source = self.prepare(format!("SELECT {names} FROM {table}"))?;
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut target = other.prepare(format!(
+            "INSERT INTO {table} ({names}) VALUES ({placeholders})"
+        ))?;
+        let batch_size = options.batch_size.max(1);
+        let mut count = 0;
+        let mut cursor = source.iter();
+        loop {
+            let mut done = true;
+            other.execute("BEGIN")?;
+            let result = (|| {
+                for _ in 0..batch_size {
+                    let Some(values) = cursor.try_next()? else {
+                        done = true;
+                        break;
+                    };
+                    target.reset()?;
+                    target.bind(&values[..])?;
+                    target.next()?;
+                    count += 1;
+                    done = false;
+                }
+                other.execute("COMMIT")
+            })();
+            if let Err(error) = result {
+                let _ = other.execute("ROLLBACK");
+                return Err(error);
+            }
+            if done {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Read the pages of tables into the page cache.
+    ///
+    /// Each table is scanned in full, which reduces the latency of the first queries after the
+    /// database has been opened. If a cache size is given, it is set first via `PRAGMA
+    /// cache_size`, where positive values are in pages and negative ones in kibibytes, since
+    /// the pages do not stay in the cache if it is too small.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT)")?;
+    /// connection.warm_cache(["users"], Some(-64 * 1024))?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn warm_cache<T, U>(&self, tables: T, cache_size: Option<isize>) -> Result<()>
+    where
+        T: IntoIterator<Item = U>,
+        U: AsRef<str>,
+    {
+        if let Some(cache_size) = cache_size {
+            self.execute(format!("PRAGMA cache_size = {cache_size}"))?;
+        }
+        for table in tables {
+            let table = crate::transaction::quote(table.as_ref());
+            let mut statement =
+                self.prepare(format!("SELECT count(*) FROM {table} NOT INDEXED"))?;
+            statement.next()?;
+        }
+        Ok(())
+    }
+
+    /// Query a pragma and read the first column of the first row.
+    ///
+    /// The name can be qualified with a schema name, as in `main.journal_mode`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.pragma_set("user_version", 42)?;
+    /// assert_eq!(connection.pragma_query::<i64>("user_version")?, 42);
+    /// assert_eq!(connection.pragma_query::<String>("main.journal_mode")?, "memory");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn pragma_query<T: ReadableWithIndex>(&self, name: &str) -> Result<T> {
+        crate::pragma::query(self, name)
+    }
+
+    /// Query a pragma and read the first column of all rows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let options = connection.pragma_query_list::<String>("compile_options")?;
+    /// assert!(!options.is_empty());
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn pragma_query_list<T: ReadableWithIndex>(&self, name: &str) -> Result<Vec<T>> {
+        crate::pragma::query_list(self, name)
+    }
+
+    /// Set a pragma.
+    ///
+    /// The value is formatted as a literal, and strings are quoted, so that values such as
+    /// `"WAL"` and `"ON"` can be passed as they are. Null and binary values are rejected.
+    #[inline]
+    pub fn pragma_set<T: Into<Value>>(&self, name: &str, value: T) -> Result<()> {
+        crate::pragma::set(self, name, value.into())
+    }
+
+    /// List the tables and views of all attached databases.
+    ///
+    /// `PRAGMA table_list` is used if available (SQLite 3.37 or later); otherwise, the list is
+    /// assembled from `sqlite_schema`, in which case shadow tables are reported as ordinary ones
+    /// and no table is reported as strict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT) STRICT")?;
+    /// let tables = connection.table_list()?;
+    /// let users = tables.iter().find(|table| table.name() == "users").unwrap();
+    /// assert_eq!(users.column_count(), 2);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn table_list(&self) -> Result<Vec<TableInfo>> {
+        crate::pragma::table_list(self)
+    }
+
+    /// Rename a table.
+    ///
+    /// The references to the table in the indexes, triggers, views, and foreign keys of other
+    /// tables are updated accordingly, regardless of `PRAGMA legacy_alter_table`, which is
+    /// restored afterwards.
+    #[inline]
+    pub fn rename_table<T: AsRef<str>, U: AsRef<str>>(&self, old: T, new: U) -> Result<()> {
+        crate::alter::rename_table(self, old.as_ref(), new.as_ref())
+    }
+
+    /// Rename a column of a table.
+    ///
+    /// The references to the column are updated in the same way as by `rename_table`.
+    #[inline]
+    pub fn rename_column<T, U, V>(&self, table: T, old: U, new: V) -> Result<()>
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+        V: AsRef<str>,
+    {
+        crate::alter::rename_column(self, table.as_ref(), old.as_ref(), new.as_ref())
+    }
+
+    /// Rebuild a table with a new definition.
+    ///
+    /// The function follows the [procedure][1] for schema changes that `ALTER TABLE` does not
+    /// support: a table is created with the given definition (the part after the table name in
+    /// `CREATE TABLE`), filled via `SELECT` with the given column list from the old table, and
+    /// renamed to replace the old one, after which the indexes and triggers of the old table are
+    /// recreated. Everything happens within a transaction. If foreign keys are enabled, they are
+    /// disabled for the duration and checked before committing, which requires the connection
+    /// not to be in a transaction already.
+    ///
+    /// Views referring to the table are kept as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (id INTEGER, name TEXT, age REAL)")?;
+    /// connection.rebuild_table(
+    ///     "users",
+    ///     "(id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+    ///     "id, coalesce(name, '')",
+    /// )?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    ///
+    /// [1]: https://www.sqlite.org/lang_altertable.html#otheralter
+    #[inline]
+    pub fn rebuild_table<T, U, V>(&self, table: T, definition: U, columns: V) -> Result<()>
+    where
+        T: AsRef<str>,
+        U: AsRef<str>,
+        V: AsRef<str>,
+    {
+        crate::alter::rebuild_table(self, table.as_ref(), definition.as_ref(), columns.as_ref())
+    }
+
+    /// List the indexes of a table of the main database.
+    ///
+    /// Expression indexes and partial indexes are reported as such, and their definitions are
+    /// available verbatim.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute(
+    ///     "
+    ///     CREATE TABLE users (name TEXT, deleted INTEGER);
+    ///     CREATE INDEX users_name ON users (lower(name)) WHERE deleted = 0;
+    ///     ",
+    /// )?;
+    /// let indexes = connection.indexes("users")?;
+    /// assert!(indexes[0].has_expressions());
+    /// assert!(indexes[0].is_partial());
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn indexes<T: AsRef<str>>(&self, table: T) -> Result<Vec<IndexInfo>> {
+        crate::pragma::indexes(self, table.as_ref())
+    }
+
+    /// List the views of the main database.
+    #[inline]
+    pub fn views(&self) -> Result<Vec<ViewInfo>> {
+        crate::pragma::views(self)
+    }
+
+    /// List the triggers attached to a table or view of the main database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute(
+    ///     "
+    ///     CREATE TABLE users (name TEXT, updated INTEGER);
+    ///     CREATE TRIGGER touch AFTER UPDATE ON users
+    ///     BEGIN UPDATE users SET updated = unixepoch() WHERE rowid = new.rowid; END;
+    ///     ",
+    /// )?;
+    /// assert_eq!(connection.triggers("users")?[0].name(), "touch");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn triggers<T: AsRef<str>>(&self, table: T) -> Result<Vec<TriggerInfo>> {
+        crate::pragma::triggers(self, table.as_ref())
+    }
+}
+
 impl Drop for Connection {
     #[inline]
     #[allow(unused_must_use)]
     fn drop(&mut self) {
-        self.remove_busy_handler();
-        unsafe { ffi::sqlite3_close(self.raw.0) };
+        if self.raw.0.is_null() {
+            return;
+        }
+        self.clear_statement_cache();
+        if self.leak_detection {
+            for statement in self.busy_statements() {
+                let message = format!(
+                    "a statement has not been finalized (busy: {}): {}",
+                    statement.busy, statement.sql,
+                );
+                if let Ok(message) = std::ffi::CString::new(message) {
+                    crate::log::scope(self.raw.0, || unsafe {
+                        ffi::sqlite3_log(ffi::SQLITE_WARNING, c"%s".as_ptr(), message.as_ptr());
+                    });
+                }
+            }
+        }
+        crate::log::unregister_tag(self.raw.0, &self.tag);
+        let kinds = self
+            .hooks
+            .lock()
+            .unwrap()
+            .callbacks
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        // Without pending statements, closing is immediate, and the trace callback can be kept
+        // to observe it and dropped afterwards together with the connection.
+        let pending = self.strong_statement_count() > 0;
+        for kind in kinds {
+            if kind != Kind::Trace || pending || !self.owned {
+                self.uninstall_hook(kind);
+            }
+        }
+        self.interrupt.lock().unwrap().take();
+        if self.owned {
+            // Closing is deferred until all statements, including leaked ones, have been
+            // finalized.
+            unsafe { ffi::sqlite3_close_v2(self.raw.0) };
+        }
+    }
+}
+
+impl InterruptHandle {
+    /// Interrupt the statements being evaluated, which then fail with `SQLITE_INTERRUPT`.
+    ///
+    /// Statements started after the call are not affected.
+    #[inline]
+    pub fn interrupt(&self) {
+        if let Some(raw) = *self.0.lock().unwrap() {
+            unsafe { ffi::sqlite3_interrupt(raw.0) };
+        }
+    }
+}
+
+impl TagHandle {
+    /// Return the current label of the connection.
+    #[inline]
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl BusyStatistics {
+    fn add(&mut self, other: &BusyStatistics) {
+        self.events += other.events;
+        self.retries += other.retries;
+        self.wait += other.wait;
+    }
+
+    /// Return the number of busy events, that is, operations that found the database locked.
+    #[inline]
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Return the number of times an operation was repeated.
+    #[inline]
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+
+    /// Return the total time spent in the callback handling busy events.
+    #[inline]
+    pub fn wait(&self) -> Duration {
+        self.wait
+    }
+
+    /// Return the label of the connection attached via `Connection::set_tag`.
+    #[inline]
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
+
+impl StatementInfo {
+    /// Return the SQL text.
+    #[inline]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Check if the statement is being evaluated.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.busy
     }
 }
 
@@ -302,7 +2468,7 @@ impl OpenFlags {
     /// Create flags for opening a database connection.
     #[inline]
     pub fn new() -> Self {
-        OpenFlags(0)
+        OpenFlags(0, None)
     }
 
     /// Create the database if it does not already exist.
@@ -339,6 +2505,14 @@ impl OpenFlags {
         self
     }
 
+    /// Set the text encoding of the database if it is created by the connection.
+    ///
+    /// The encoding of an existing database cannot be changed.
+    pub fn with_encoding(mut self, value: Encoding) -> Self {
+        self.1 = Some(value);
+        self
+    }
+
     /// Allow the path to be interpreted as a URI.
     pub fn with_uri(mut self) -> Self {
         self.0 |= ffi::SQLITE_OPEN_URI;
@@ -353,6 +2527,67 @@ impl Default for OpenFlags {
     }
 }
 
+impl AttachOptions {
+    /// Create options for attaching a database.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the encryption key.
+    #[cfg(feature = "encryption")]
+    pub fn with_key<T: Into<String>>(mut self, key: T) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Add a URI parameter such as `mode` or `immutable`.
+    pub fn with_parameter<T: Into<String>, U: Into<String>>(mut self, name: T, value: U) -> Self {
+        self.parameters.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl CopyOptions {
+    /// Create options for copying a table.
+    ///
+    /// By default, rows are transferred in batches of 1000, indices are not copied, and the
+    /// operation fails if the table already exists in the other connection.
+    #[inline]
+    pub fn new() -> Self {
+        CopyOptions {
+            batch_size: 1000,
+            indices: false,
+            replace: false,
+        }
+    }
+
+    /// Set the number of rows transferred within one transaction.
+    pub fn with_batch_size(mut self, value: usize) -> Self {
+        self.batch_size = value;
+        self
+    }
+
+    /// Copy the indices defined on the table.
+    pub fn with_indices(mut self) -> Self {
+        self.indices = true;
+        self
+    }
+
+    /// Drop the table in the other connection if it already exists.
+    pub fn with_replace(mut self) -> Self {
+        self.replace = true;
+        self
+    }
+}
+
+impl Default for CopyOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Deref for ConnectionThreadSafe {
     type Target = Connection;
 
@@ -373,23 +2608,181 @@ unsafe impl Sync for ConnectionThreadSafe {}
 
 unsafe impl Send for Raw {}
 
+fn open(path: &Path, flags: OpenFlags, vfs: Option<&str>) -> Result<Connection> {
+    let mut raw = std::ptr::null_mut();
+    let name = match vfs {
+        Some(vfs) => Some(str_to_cstr!(vfs)),
+        _ => None,
+    };
+    unsafe {
+        let code = ffi::sqlite3_open_v2(
+            path_to_cstr!(path).as_ptr(),
+            &mut raw,
+            flags.0,
+            name.as_ref()
+                .map(|name| name.as_ptr())
+                .unwrap_or(std::ptr::null()),
+        );
+
+        match code {
+            ffi::SQLITE_OK => {}
+            code => match crate::error::last(raw) {
+                Some(error) => {
+                    ffi::sqlite3_close(raw);
+                    return Err(error);
+                }
+                _ => {
+                    ffi::sqlite3_close(raw);
+                    return Err(crate::error::Error::new(Some(code as isize), None));
+                }
+            },
+        }
+    }
+    let connection = unsafe { adopt(raw, flags, vfs) };
+    if let Some(encoding) = flags.1 {
+        let encoding = match encoding {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16le => "UTF-16le",
+            Encoding::Utf16be => "UTF-16be",
+        };
+        connection.execute(format!("PRAGMA encoding = '{encoding}'"))?;
+    }
+    Ok(connection)
+}
+
+unsafe fn adopt(raw: *mut ffi::sqlite3, flags: OpenFlags, vfs: Option<&str>) -> Connection {
+    Connection {
+        raw: Raw(raw),
+        hooks: Mutex::new(Hooks::default()),
+        functions: Mutex::new(BTreeSet::new()),
+        flags,
+        vfs: vfs.map(|vfs| vfs.to_string()),
+        initializers: Mutex::new(Vec::new()),
+        interrupt: Arc::new(Mutex::new(Some(Raw(raw)))),
+        leak_detection: false,
+        tag: Arc::new(Mutex::new(None)),
+        busy: Arc::new(Mutex::new(BusyStatistics::default())),
+        error_context: ErrorContext::Disabled,
+        redaction: None,
+        owned: true,
+        statements: Mutex::new(Cache::new(16)),
+        phantom: PhantomData,
+    }
+}
+
+fn db_config(connection: &Connection, option: c_int, enabled: bool) -> Result<()> {
+    unsafe {
+        ok!(
+            connection.raw.0,
+            ffi::sqlite3_db_config(
+                connection.raw.0,
+                option,
+                enabled as c_int,
+                std::ptr::null_mut::<c_int>(),
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn release_hook(connection: &Connection, kind: Kind, id: usize) {
+    let current = connection
+        .hooks
+        .lock()
+        .unwrap()
+        .callbacks
+        .get(&kind)
+        .map(|(current, _)| *current);
+    if current == Some(id) {
+        connection.uninstall_hook(kind);
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '%' | '?' | '#' | '&' | '=' => result.push_str(&format!("%{:02X}", character as u8)),
+            _ => result.push(character),
+        }
+    }
+    result
+}
+
+// Pause after an attempt following the schedule of the built-in busy handler of SQLite.
+fn pause(timeout: usize, attempt: usize) -> bool {
+    const DELAYS: [usize; 12] = [1, 2, 5, 10, 15, 20, 25, 25, 25, 50, 50, 100];
+    const TOTALS: [usize; 12] = [0, 1, 3, 8, 18, 33, 53, 78, 103, 128, 178, 228];
+    let (mut delay, prior) = match DELAYS.get(attempt) {
+        Some(&delay) => (delay, TOTALS[attempt]),
+        _ => (100, 228 + 100 * (attempt - 11)),
+    };
+    if prior + delay > timeout {
+        if prior >= timeout {
+            return false;
+        }
+        delay = timeout - prior;
+    }
+    crate::sleep(delay);
+    true
+}
+
+fn record_busy<F>(statistics: &Mutex<BusyStatistics>, attempt: usize, callback: F) -> bool
+where
+    F: FnOnce(usize) -> bool,
+{
+    let start = Instant::now();
+    let retry = callback(attempt);
+    let current = BusyStatistics {
+        events: usize::from(attempt == 0),
+        retries: usize::from(retry),
+        wait: start.elapsed(),
+        tag: None,
+    };
+    statistics.lock().unwrap().add(&current);
+    STEP.with(|step| {
+        if let Some(mut statistics) = step.take() {
+            statistics.add(&current);
+            step.set(Some(statistics));
+        }
+    });
+    retry
+}
+
+// Attribute the busy events occurring while a callback runs to a statement.
+pub(crate) fn track_busy<F, T>(statistics: &mut BusyStatistics, callback: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let previous = STEP.with(|step| step.replace(Some(BusyStatistics::default())));
+    let result = callback();
+    if let Some(current) = STEP.with(|step| step.replace(previous)) {
+        statistics.add(&current);
+    }
+    result
+}
+
 extern "C" fn busy_callback<F>(callback: *mut c_void, attempts: c_int) -> c_int
 where
     F: FnMut(usize) -> bool,
 {
-    unsafe { c_int::from((*(callback as *mut F))(attempts as usize)) }
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+        (*(callback as *mut F))(attempts as usize)
+    }));
+    c_int::from(result.unwrap_or(false))
 }
 
 extern "C" fn process_callback<F>(
-    callback: *mut c_void,
+    iteration: *mut c_void,
     count: c_int,
     values: *mut *mut c_char,
     columns: *mut *mut c_char,
 ) -> c_int
 where
-    F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    F: FnMut(&[(&str, Option<&str>)]) -> Result<bool>,
 {
     unsafe {
+        let iteration = &mut *(iteration as *mut Iteration<F>);
         let mut pairs = Vec::with_capacity(count as usize);
         for index in 0..(count as isize) {
             let column = {
@@ -407,6 +2800,16 @@ where
             };
             pairs.push((column, value));
         }
-        c_int::from(!(*(callback as *mut F))(&pairs))
+        match (iteration.callback)(&pairs) {
+            Ok(true) => 0,
+            Ok(false) => {
+                iteration.stopped = true;
+                1
+            }
+            Err(error) => {
+                iteration.error = Some(error);
+                1
+            }
+        }
     }
 }
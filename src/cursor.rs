@@ -1,17 +1,23 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
-use std::ops::{Deref, Index};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Index};
 use std::rc::Rc;
+use std::sync::mpsc::{sync_channel, Receiver};
+
+use smallvec::SmallVec;
 
 use crate::error::{Error, Result};
 use crate::statement::{Bindable, State, Statement};
-use crate::value::Value;
+use crate::value::{Type, Value};
 
 /// An iterator for a prepared statement.
 pub struct Cursor<'l, 'm> {
     column_count: usize,
     statement: &'m mut Statement<'l>,
     poisoned: bool,
+    arena: Option<Rc<RefCell<Arena>>>,
 }
 
 /// An iterator for a prepared statement with ownership.
@@ -19,15 +25,35 @@ pub struct CursorWithOwnership<'l> {
     column_count: usize,
     statement: Statement<'l>,
     poisoned: bool,
+    arena: Option<Rc<RefCell<Arena>>>,
 }
 
 /// A row.
 #[derive(Debug)]
 pub struct Row {
     column_mapping: Rc<HashMap<String, usize>>,
-    values: Vec<Value>,
+    values: Values,
+    arena: Option<Rc<RefCell<Arena>>>,
+}
+
+/// An iterator over rows read ahead on another thread.
+pub struct Prefetch {
+    column_mapping: Rc<HashMap<String, usize>>,
+    receiver: Receiver<Result<Values>>,
 }
 
+/// An iterator converting rows into values of a specific type.
+pub struct MapInto<I, T> {
+    inner: I,
+    phantom: PhantomData<T>,
+}
+
+/// The values of a row.
+///
+/// Up to eight values are stored inline without allocating memory on the heap.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Values(SmallVec<[Value; 8]>);
+
 /// A type suitable for indexing columns in a row.
 pub trait RowIndex: std::fmt::Debug + std::fmt::Display {
     /// Check if the index is present in a row.
@@ -39,6 +65,15 @@ pub trait RowIndex: std::fmt::Debug + std::fmt::Display {
     fn index(self, row: &Row) -> usize;
 }
 
+struct Sendable<T>(T);
+
+#[derive(Debug)]
+struct Arena {
+    capacity: usize,
+    blobs: VecDeque<Vec<u8>>,
+    strings: VecDeque<String>,
+}
+
 macro_rules! implement(
     ($type:ident<$($lifetime:lifetime),+>) => {
         impl<$($lifetime),+> $type<$($lifetime),+> {
@@ -67,6 +102,148 @@ macro_rules! implement(
                 Ok(cursor)
             }
 
+            /// Bind values to named parameters.
+            #[deprecated(note = "use `bind_iter` instead")]
+            pub fn bind_by_name<T, U>(self, values: T) -> Result<Self>
+            where
+                T: IntoIterator<Item = (U, Value)>,
+                U: AsRef<str>,
+            {
+                self.bind_iter(
+                    values
+                        .into_iter()
+                        .map(|(name, value)| (name.as_ref().to_string(), value)),
+                )
+            }
+
+            /// Reuse the buffers of strings and binary values of dropped rows for reading
+            /// subsequent rows.
+            ///
+            /// At most the given number of buffers of each kind is retained, and they are reused
+            /// in the order in which they were released. The setting is beneficial when rows are
+            /// processed one by one and dropped before the next one is read.
+            pub fn with_arena(mut self, capacity: usize) -> Self {
+                self.arena = Some(Rc::new(RefCell::new(Arena {
+                    capacity,
+                    blobs: VecDeque::with_capacity(capacity),
+                    strings: VecDeque::with_capacity(capacity),
+                })));
+                self
+            }
+
+            /// Read rows ahead on another thread while processing them with a callback.
+            ///
+            /// At most the given number of rows is read ahead, and reading stops once the
+            /// callback returns. The connection has to be in the serialized threading mode, which
+            /// is the case unless it is opened with `OpenFlags::with_no_mutex`, since it can be
+            /// used by the callback in the meantime.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// let connection = sqlite::Connection::open_thread_safe(":memory:")?;
+            /// connection.execute("CREATE TABLE users (name TEXT)")?;
+            /// connection.execute("INSERT INTO users VALUES ('Alice'), ('Bob')")?;
+            /// let mut statement = connection.prepare("SELECT name FROM users")?;
+            /// let names = statement.iter().prefetch(16, |rows| {
+            ///     rows.map(|row| row.map(|row| row.read::<String, _>(0)))
+            ///         .collect::<sqlite::Result<Vec<_>>>()
+            /// })??;
+            /// assert_eq!(names, ["Alice", "Bob"]);
+            /// # Ok::<(), sqlite::Error>(())
+            /// ```
+            pub fn prefetch<F, T>(&mut self, depth: usize, callback: F) -> Result<T>
+            where
+                F: FnOnce(Prefetch) -> T,
+            {
+                let raw = unsafe { ffi::sqlite3_db_handle(self.statement.as_raw()) };
+                if unsafe { ffi::sqlite3_db_mutex(raw) }.is_null() {
+                    raise!("prefetching requires a connection in the serialized threading mode");
+                }
+                if self.poisoned {
+                    return Ok(callback(Prefetch {
+                        column_mapping: self.statement.column_mapping(),
+                        receiver: sync_channel(0).1,
+                    }));
+                }
+                // The mapping is copied so that the two threads do not share a reference counter.
+                let column_mapping = Rc::new((*self.statement.column_mapping()).clone());
+                let arena = self.arena.take();
+                let (sender, receiver) = sync_channel(depth);
+                let cursor = Sendable(&mut *self);
+                let result = std::thread::scope(|scope| {
+                    let producer = scope.spawn(move || {
+                        let cursor = cursor.into_inner();
+                        loop {
+                            match cursor.try_next() {
+                                Ok(Some(values)) => {
+                                    if sender.send(Ok(values)).is_err() {
+                                        return false;
+                                    }
+                                }
+                                Ok(None) => return false,
+                                Err(error) => {
+                                    let _ = sender.send(Err(error));
+                                    return true;
+                                }
+                            }
+                        }
+                    });
+                    let result = callback(Prefetch {
+                        column_mapping,
+                        receiver,
+                    });
+                    (result, producer.join())
+                });
+                self.arena = arena;
+                match result {
+                    (result, Ok(poisoned)) => {
+                        self.poisoned = poisoned;
+                        Ok(result)
+                    }
+                    (_, Err(payload)) => std::panic::resume_unwind(payload),
+                }
+            }
+
+            /// Convert the rows into values of a specific type.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use sqlite::{Error, Row};
+            ///
+            /// struct User {
+            ///     name: String,
+            /// }
+            ///
+            /// impl TryFrom<Row> for User {
+            ///     type Error = Error;
+            ///
+            ///     fn try_from(row: Row) -> Result<Self, Self::Error> {
+            ///         Ok(User { name: row.try_read::<&str, _>("name")?.into() })
+            ///     }
+            /// }
+            ///
+            /// let connection = sqlite::open(":memory:")?;
+            /// connection.execute("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice')")?;
+            /// let mut statement = connection.prepare("SELECT name FROM users")?;
+            /// for user in statement.iter().map_into::<User>() {
+            ///     assert_eq!(user?.name, "Alice");
+            /// }
+            /// # Ok::<(), sqlite::Error>(())
+            /// ```
+            #[inline]
+            pub fn map_into<T>(self) -> MapInto<Self, T>
+            where
+                T: TryFrom<Row>,
+                T::Error: Into<Error>,
+            {
+                MapInto {
+                    inner: self,
+                    phantom: PhantomData,
+                }
+            }
+
             /// Reset the internal state.
             #[allow(unused_mut)]
             pub fn reset(mut self) -> Result<Self> {
@@ -76,15 +253,25 @@ macro_rules! implement(
             }
 
             /// Advance to the next row and read all columns.
-            pub fn try_next(&mut self) -> Result<Option<Vec<Value>>> {
+            pub fn try_next(&mut self) -> Result<Option<Values>> {
                 if self.statement.next()? == State::Done {
                     return Ok(None);
                 }
-                let mut values = Vec::with_capacity(self.column_count);
-                for index in 0..self.column_count {
-                    values.push(self.statement.read(index)?);
+                let mut values = SmallVec::with_capacity(self.column_count);
+                match self.arena {
+                    Some(ref arena) => {
+                        let mut arena = arena.borrow_mut();
+                        for index in 0..self.column_count {
+                            values.push(arena.read(&self.statement, index)?);
+                        }
+                    }
+                    _ => {
+                        for index in 0..self.column_count {
+                            values.push(self.statement.read(index)?);
+                        }
+                    }
                 }
-                Ok(Some(values))
+                Ok(Some(Values(values)))
             }
         }
 
@@ -109,6 +296,7 @@ macro_rules! implement(
                         value.map(|values| Ok(Row {
                             column_mapping: self.statement.column_mapping(),
                             values,
+                            arena: self.arena.clone(),
                         }))
                     }
                     Err(error) => {
@@ -131,6 +319,77 @@ impl<'l> From<CursorWithOwnership<'l>> for Statement<'l> {
     }
 }
 
+impl<I, T> Iterator for MapInto<I, T>
+where
+    I: Iterator<Item = Result<Row>>,
+    T: TryFrom<Row>,
+    T::Error: Into<Error>,
+{
+    type Item = Result<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|row| row.and_then(|row| T::try_from(row).map_err(Into::into)))
+    }
+}
+
+impl Iterator for Prefetch {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let values = self.receiver.recv().ok()?;
+        Some(values.map(|values| Row {
+            column_mapping: self.column_mapping.clone(),
+            values,
+            arena: None,
+        }))
+    }
+}
+
+impl<T> Sendable<T> {
+    #[inline]
+    fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+// The statement of a cursor is used by one thread at a time, and the connection is serialized.
+unsafe impl<T> Send for Sendable<T> {}
+
+impl Arena {
+    fn read(&mut self, statement: &Statement, index: usize) -> Result<Value> {
+        Ok(match statement.column_type(index)? {
+            Type::Binary => {
+                let mut buffer = self.blobs.pop_front().unwrap_or_default();
+                crate::statement::read_blob_into(statement, index, &mut buffer);
+                Value::Binary(buffer)
+            }
+            Type::String => {
+                let mut buffer = self.strings.pop_front().unwrap_or_default();
+                crate::statement::read_text_into(statement, index, &mut buffer)?;
+                Value::String(buffer)
+            }
+            _ => statement.read(index)?,
+        })
+    }
+
+    fn release(&mut self, values: Values) {
+        for value in values.0 {
+            match value {
+                Value::Binary(buffer) if self.blobs.len() < self.capacity => {
+                    self.blobs.push_back(buffer)
+                }
+                Value::String(buffer) if self.strings.len() < self.capacity => {
+                    self.strings.push_back(buffer)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 impl Row {
     /// Check if the row contains a column.
     ///
@@ -186,12 +445,151 @@ impl Row {
         }
         T::try_from(&self.values[column.index(self)])
     }
+
+    /// Try to take the value from a column.
+    ///
+    /// In case of integer indices, the first column has index 0. Any subsequent invocation will
+    /// result in `Value::Null`.
+    #[inline]
+    pub fn try_take<U>(&mut self, column: U) -> Result<Value>
+    where
+        U: RowIndex,
+    {
+        if !column.contains(self) {
+            raise!("the index is out of range ({column})");
+        }
+        Ok(self.take(column))
+    }
+
+    /// Split the row into one with certain columns and one with the rest.
+    ///
+    /// The values are moved without cloning, and the columns of the first row are in the given
+    /// order. In case of integer indices, the first column has index 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let mut statement = connection.prepare("SELECT 1 AS id, 'Alice' AS name, 42 AS age")?;
+    /// let row = statement.iter().next().unwrap()?;
+    /// let (key, rest) = row.split(["id"])?;
+    /// assert_eq!(key.read::<i64, _>("id"), 1);
+    /// assert_eq!(rest.read::<&str, _>("name"), "Alice");
+    /// assert!(!rest.contains("id"));
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn split<T, U>(mut self, columns: T) -> Result<(Row, Row)>
+    where
+        T: IntoIterator<Item = U>,
+        U: RowIndex,
+    {
+        let mut indices = Vec::new();
+        for column in columns {
+            if !column.contains(&self) {
+                raise!("the index is out of range ({column})");
+            }
+            let index = column.index(&self);
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        let mut names = vec![Vec::new(); self.values.len()];
+        for (name, index) in self.column_mapping.iter() {
+            names[*index].push(name);
+        }
+        let mut values = std::mem::take(&mut self.values)
+            .0
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut split = |indices: &mut dyn Iterator<Item = usize>| {
+            let mut column_mapping = HashMap::new();
+            let mut part = SmallVec::new();
+            for index in indices {
+                if let Some(value) = values[index].take() {
+                    for name in names[index].iter() {
+                        column_mapping.insert(name.to_string(), part.len());
+                    }
+                    part.push(value);
+                }
+            }
+            Row {
+                column_mapping: Rc::new(column_mapping),
+                values: Values(part),
+                arena: self.arena.clone(),
+            }
+        };
+        let first = split(&mut indices.into_iter());
+        let second = split(&mut (0..names.len()));
+        Ok((first, second))
+    }
+}
+
+impl Drop for Row {
+    fn drop(&mut self) {
+        if let Some(arena) = self.arena.take() {
+            if let Ok(mut arena) = arena.try_borrow_mut() {
+                arena.release(std::mem::take(&mut self.values));
+            }
+        }
+    }
 }
 
 impl From<Row> for Vec<Value> {
     #[inline]
+    fn from(mut row: Row) -> Self {
+        std::mem::take(&mut row.values).into_vec()
+    }
+}
+
+impl Values {
+    /// Convert into a vector.
+    #[inline]
+    pub fn into_vec(self) -> Vec<Value> {
+        self.0.into_vec()
+    }
+}
+
+impl Deref for Values {
+    type Target = [Value];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Values {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Values> for Vec<Value> {
+    #[inline]
+    fn from(values: Values) -> Self {
+        values.into_vec()
+    }
+}
+
+impl From<Row> for HashMap<String, Value> {
+    fn from(mut row: Row) -> Self {
+        let mut values = std::mem::take(&mut row.values);
+        row.column_mapping
+            .iter()
+            .map(|(name, index)| (name.clone(), std::mem::take(&mut values[*index])))
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<Row> for serde_json::Map<String, serde_json::Value> {
     fn from(row: Row) -> Self {
-        row.values
+        HashMap::<String, Value>::from(row)
+            .into_iter()
+            .map(|(name, value)| (name, value.into()))
+            .collect()
     }
 }
 
@@ -237,6 +635,7 @@ pub fn new<'l, 'm>(statement: &'m mut Statement<'l>) -> Cursor<'l, 'm> {
         column_count: statement.column_count(),
         statement,
         poisoned: false,
+        arena: None,
     }
 }
 
@@ -245,5 +644,6 @@ pub fn new_with_ownership(statement: Statement<'_>) -> CursorWithOwnership<'_> {
         column_count: statement.column_count(),
         statement,
         poisoned: false,
+        arena: None,
     }
 }
@@ -0,0 +1,134 @@
+use time::format_description::well_known::Rfc3339;
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::error::{Error, Result};
+use crate::statement::{
+    BindableWithIndex, ColumnIndex, ParameterIndex, ReadableWithIndex, Statement,
+};
+use crate::value::{Type, Value};
+
+const DATE: &[BorrowedFormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+const TIME: &[BorrowedFormatItem<'static>] =
+    format_description!("[hour]:[minute]:[second].[subsecond]");
+
+const TIME_LENIENT: &[BorrowedFormatItem<'static>] =
+    format_description!("[hour]:[minute][optional [:[second][optional [.[subsecond]]]]]");
+
+const DATE_TIME_LENIENT: &[BorrowedFormatItem<'static>] = format_description!(
+    "[year]-[month]-[day]T[hour]:[minute][optional [:[second][optional [.[subsecond]]]]]"
+);
+
+macro_rules! implement(
+    ($type:ty, $format:ident, $parse:ident) => {
+        impl BindableWithIndex for $type {
+            #[inline]
+            fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+                $format(&self)?.as_str().bind(statement, index)
+            }
+        }
+
+        impl BindableWithIndex for &$type {
+            #[inline]
+            fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+                $format(self)?.as_str().bind(statement, index)
+            }
+        }
+
+        impl ReadableWithIndex for $type {
+            fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+                match statement.column_type(index)? {
+                    Type::Integer => $parse(&Value::Integer(statement.read(index)?)),
+                    _ => $parse(&Value::String(statement.read(index)?)),
+                }
+            }
+        }
+
+        impl TryFrom<&Value> for $type {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                $parse(value)
+            }
+        }
+
+        impl TryFrom<&Value> for Option<$type> {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let Value::Null = value {
+                    return Ok(None);
+                }
+                $parse(value).map(Some)
+            }
+        }
+    };
+);
+
+implement!(Date, format_date, parse_date);
+implement!(Time, format_time, parse_time);
+implement!(OffsetDateTime, format_date_time, parse_date_time);
+
+fn format_date(value: &Date) -> Result<String> {
+    match value.format(DATE) {
+        Ok(value) => Ok(value),
+        Err(error) => raise!("failed to format a date ({error})"),
+    }
+}
+
+fn format_time(value: &Time) -> Result<String> {
+    match value.format(TIME) {
+        Ok(value) => Ok(value),
+        Err(error) => raise!("failed to format a time ({error})"),
+    }
+}
+
+fn format_date_time(value: &OffsetDateTime) -> Result<String> {
+    match value.format(&Rfc3339) {
+        Ok(value) => Ok(value),
+        Err(error) => raise!("failed to format a date and time ({error})"),
+    }
+}
+
+fn parse_date(value: &Value) -> Result<Date> {
+    match value {
+        Value::String(value) => match Date::parse(value, DATE) {
+            Ok(value) => Ok(value),
+            Err(error) => raise!("failed to parse a date ({error})"),
+        },
+        _ => raise!("failed to convert"),
+    }
+}
+
+fn parse_time(value: &Value) -> Result<Time> {
+    match value {
+        Value::String(value) => match Time::parse(value, TIME_LENIENT) {
+            Ok(value) => Ok(value),
+            Err(error) => raise!("failed to parse a time ({error})"),
+        },
+        _ => raise!("failed to convert"),
+    }
+}
+
+fn parse_date_time(value: &Value) -> Result<OffsetDateTime> {
+    match value {
+        Value::Integer(value) => match OffsetDateTime::from_unix_timestamp(*value) {
+            Ok(value) => Ok(value),
+            Err(error) => raise!("failed to convert a timestamp ({error})"),
+        },
+        Value::String(value) => {
+            if let Ok(value) = OffsetDateTime::parse(value, &Rfc3339) {
+                return Ok(value);
+            }
+            match PrimitiveDateTime::parse(&value.replacen(' ', "T", 1), DATE_TIME_LENIENT) {
+                Ok(value) => Ok(value.assume_utc()),
+                Err(error) => raise!("failed to parse a date and time ({error})"),
+            }
+        }
+        _ => raise!("failed to convert"),
+    }
+}
@@ -1,25 +1,297 @@
+use core::ffi::c_int;
 use std::{error, fmt};
 
 /// An error.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Error {
     /// The error code.
     pub code: Option<isize>,
     /// The error message.
     pub message: Option<String>,
+    pub(crate) extended_code: Option<c_int>,
+    pub(crate) offset: Option<c_int>,
+    pub(crate) sql: Option<String>,
+}
+
+/// An error of a statement within a script.
+#[derive(Clone, Debug)]
+pub struct BatchError {
+    /// The index of the failed statement, which is also the number of statements executed.
+    pub index: usize,
+    /// The byte offset of the failed statement in the script, which is also the number of bytes
+    /// executed.
+    pub offset: usize,
+    /// The SQL text of the failed statement.
+    ///
+    /// If the statement could not be prepared, the text extends to the next semicolon.
+    pub sql: String,
+    /// The underlying error, whose offset, if available, is relative to the beginning of the
+    /// script.
+    pub error: Error,
+}
+
+/// The context attached to errors.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ErrorContext {
+    /// Attach nothing.
+    #[default]
+    Disabled,
+    /// Attach the SQL text of the statement that failed.
+    Sql,
+    /// Attach the SQL text of the statement that failed with the bound parameters substituted.
+    ExpandedSql,
 }
 
 /// A result.
 pub type Result<T> = std::result::Result<T, Error>;
 
+macro_rules! result_codes(
+    ($($(#[$attribute:meta])* $variant:ident = $constant:ident,)*) => (
+        /// A [result code][1], which is either a primary or an extended one.
+        ///
+        /// [1]: https://www.sqlite.org/rescode.html
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        #[non_exhaustive]
+        pub enum ResultCode {
+            $($(#[$attribute])* $variant,)*
+            /// A code unknown to the crate.
+            Other(isize),
+        }
+
+        impl ResultCode {
+            /// Create a result code from its numeric value.
+            pub fn from_raw(code: isize) -> Self {
+                match code {
+                    $(code if code == ffi::$constant as isize => ResultCode::$variant,)*
+                    code => ResultCode::Other(code),
+                }
+            }
+
+            /// Return the numeric value.
+            pub fn as_raw(&self) -> isize {
+                match self {
+                    $(ResultCode::$variant => ffi::$constant as isize,)*
+                    ResultCode::Other(code) => *code,
+                }
+            }
+        }
+    );
+);
+
+result_codes! {
+    /// `SQLITE_ERROR`
+    Error = SQLITE_ERROR,
+    /// `SQLITE_ERROR_MISSING_COLLSEQ`
+    ErrorMissingCollatingSequence = SQLITE_ERROR_MISSING_COLLSEQ,
+    /// `SQLITE_ERROR_RETRY`
+    ErrorRetry = SQLITE_ERROR_RETRY,
+    /// `SQLITE_ERROR_SNAPSHOT`
+    ErrorSnapshot = SQLITE_ERROR_SNAPSHOT,
+    /// `SQLITE_INTERNAL`
+    Internal = SQLITE_INTERNAL,
+    /// `SQLITE_PERM`
+    Permission = SQLITE_PERM,
+    /// `SQLITE_ABORT`
+    Abort = SQLITE_ABORT,
+    /// `SQLITE_ABORT_ROLLBACK`
+    AbortRollback = SQLITE_ABORT_ROLLBACK,
+    /// `SQLITE_BUSY`
+    Busy = SQLITE_BUSY,
+    /// `SQLITE_BUSY_RECOVERY`
+    BusyRecovery = SQLITE_BUSY_RECOVERY,
+    /// `SQLITE_BUSY_SNAPSHOT`
+    BusySnapshot = SQLITE_BUSY_SNAPSHOT,
+    /// `SQLITE_BUSY_TIMEOUT`
+    BusyTimeout = SQLITE_BUSY_TIMEOUT,
+    /// `SQLITE_LOCKED`
+    Locked = SQLITE_LOCKED,
+    /// `SQLITE_LOCKED_SHAREDCACHE`
+    LockedSharedCache = SQLITE_LOCKED_SHAREDCACHE,
+    /// `SQLITE_LOCKED_VTAB`
+    LockedVirtualTable = SQLITE_LOCKED_VTAB,
+    /// `SQLITE_NOMEM`
+    NoMemory = SQLITE_NOMEM,
+    /// `SQLITE_READONLY`
+    ReadOnly = SQLITE_READONLY,
+    /// `SQLITE_READONLY_RECOVERY`
+    ReadOnlyRecovery = SQLITE_READONLY_RECOVERY,
+    /// `SQLITE_READONLY_CANTLOCK`
+    ReadOnlyCantLock = SQLITE_READONLY_CANTLOCK,
+    /// `SQLITE_READONLY_ROLLBACK`
+    ReadOnlyRollback = SQLITE_READONLY_ROLLBACK,
+    /// `SQLITE_READONLY_DBMOVED`
+    ReadOnlyDatabaseMoved = SQLITE_READONLY_DBMOVED,
+    /// `SQLITE_READONLY_CANTINIT`
+    ReadOnlyCantInit = SQLITE_READONLY_CANTINIT,
+    /// `SQLITE_READONLY_DIRECTORY`
+    ReadOnlyDirectory = SQLITE_READONLY_DIRECTORY,
+    /// `SQLITE_INTERRUPT`
+    Interrupt = SQLITE_INTERRUPT,
+    /// `SQLITE_IOERR`
+    IoError = SQLITE_IOERR,
+    /// `SQLITE_IOERR_READ`
+    IoErrorRead = SQLITE_IOERR_READ,
+    /// `SQLITE_IOERR_SHORT_READ`
+    IoErrorShortRead = SQLITE_IOERR_SHORT_READ,
+    /// `SQLITE_IOERR_WRITE`
+    IoErrorWrite = SQLITE_IOERR_WRITE,
+    /// `SQLITE_IOERR_FSYNC`
+    IoErrorFsync = SQLITE_IOERR_FSYNC,
+    /// `SQLITE_IOERR_DIR_FSYNC`
+    IoErrorDirectoryFsync = SQLITE_IOERR_DIR_FSYNC,
+    /// `SQLITE_IOERR_TRUNCATE`
+    IoErrorTruncate = SQLITE_IOERR_TRUNCATE,
+    /// `SQLITE_IOERR_FSTAT`
+    IoErrorFstat = SQLITE_IOERR_FSTAT,
+    /// `SQLITE_IOERR_UNLOCK`
+    IoErrorUnlock = SQLITE_IOERR_UNLOCK,
+    /// `SQLITE_IOERR_RDLOCK`
+    IoErrorReadLock = SQLITE_IOERR_RDLOCK,
+    /// `SQLITE_IOERR_DELETE`
+    IoErrorDelete = SQLITE_IOERR_DELETE,
+    /// `SQLITE_IOERR_BLOCKED`
+    IoErrorBlocked = SQLITE_IOERR_BLOCKED,
+    /// `SQLITE_IOERR_NOMEM`
+    IoErrorNoMemory = SQLITE_IOERR_NOMEM,
+    /// `SQLITE_IOERR_ACCESS`
+    IoErrorAccess = SQLITE_IOERR_ACCESS,
+    /// `SQLITE_IOERR_CHECKRESERVEDLOCK`
+    IoErrorCheckReservedLock = SQLITE_IOERR_CHECKRESERVEDLOCK,
+    /// `SQLITE_IOERR_LOCK`
+    IoErrorLock = SQLITE_IOERR_LOCK,
+    /// `SQLITE_IOERR_CLOSE`
+    IoErrorClose = SQLITE_IOERR_CLOSE,
+    /// `SQLITE_IOERR_DIR_CLOSE`
+    IoErrorDirectoryClose = SQLITE_IOERR_DIR_CLOSE,
+    /// `SQLITE_IOERR_SHMOPEN`
+    IoErrorShmOpen = SQLITE_IOERR_SHMOPEN,
+    /// `SQLITE_IOERR_SHMSIZE`
+    IoErrorShmSize = SQLITE_IOERR_SHMSIZE,
+    /// `SQLITE_IOERR_SHMLOCK`
+    IoErrorShmLock = SQLITE_IOERR_SHMLOCK,
+    /// `SQLITE_IOERR_SHMMAP`
+    IoErrorShmMap = SQLITE_IOERR_SHMMAP,
+    /// `SQLITE_IOERR_SEEK`
+    IoErrorSeek = SQLITE_IOERR_SEEK,
+    /// `SQLITE_IOERR_DELETE_NOENT`
+    IoErrorDeleteNoEntry = SQLITE_IOERR_DELETE_NOENT,
+    /// `SQLITE_IOERR_MMAP`
+    IoErrorMmap = SQLITE_IOERR_MMAP,
+    /// `SQLITE_IOERR_GETTEMPPATH`
+    IoErrorGetTempPath = SQLITE_IOERR_GETTEMPPATH,
+    /// `SQLITE_IOERR_CONVPATH`
+    IoErrorConvPath = SQLITE_IOERR_CONVPATH,
+    /// `SQLITE_IOERR_VNODE`
+    IoErrorVnode = SQLITE_IOERR_VNODE,
+    /// `SQLITE_IOERR_AUTH`
+    IoErrorAuth = SQLITE_IOERR_AUTH,
+    /// `SQLITE_IOERR_BEGIN_ATOMIC`
+    IoErrorBeginAtomic = SQLITE_IOERR_BEGIN_ATOMIC,
+    /// `SQLITE_IOERR_COMMIT_ATOMIC`
+    IoErrorCommitAtomic = SQLITE_IOERR_COMMIT_ATOMIC,
+    /// `SQLITE_IOERR_ROLLBACK_ATOMIC`
+    IoErrorRollbackAtomic = SQLITE_IOERR_ROLLBACK_ATOMIC,
+    /// `SQLITE_IOERR_DATA`
+    IoErrorData = SQLITE_IOERR_DATA,
+    /// `SQLITE_IOERR_CORRUPTFS`
+    IoErrorCorruptFs = SQLITE_IOERR_CORRUPTFS,
+    /// `SQLITE_IOERR_IN_PAGE`
+    IoErrorInPage = SQLITE_IOERR_IN_PAGE,
+    /// `SQLITE_CORRUPT`
+    Corrupt = SQLITE_CORRUPT,
+    /// `SQLITE_CORRUPT_VTAB`
+    CorruptVirtualTable = SQLITE_CORRUPT_VTAB,
+    /// `SQLITE_CORRUPT_SEQUENCE`
+    CorruptSequence = SQLITE_CORRUPT_SEQUENCE,
+    /// `SQLITE_CORRUPT_INDEX`
+    CorruptIndex = SQLITE_CORRUPT_INDEX,
+    /// `SQLITE_NOTFOUND`
+    NotFound = SQLITE_NOTFOUND,
+    /// `SQLITE_FULL`
+    Full = SQLITE_FULL,
+    /// `SQLITE_CANTOPEN`
+    CantOpen = SQLITE_CANTOPEN,
+    /// `SQLITE_CANTOPEN_NOTEMPDIR`
+    CantOpenNoTempDirectory = SQLITE_CANTOPEN_NOTEMPDIR,
+    /// `SQLITE_CANTOPEN_ISDIR`
+    CantOpenIsDirectory = SQLITE_CANTOPEN_ISDIR,
+    /// `SQLITE_CANTOPEN_FULLPATH`
+    CantOpenFullPath = SQLITE_CANTOPEN_FULLPATH,
+    /// `SQLITE_CANTOPEN_CONVPATH`
+    CantOpenConvPath = SQLITE_CANTOPEN_CONVPATH,
+    /// `SQLITE_CANTOPEN_DIRTYWAL`
+    CantOpenDirtyWal = SQLITE_CANTOPEN_DIRTYWAL,
+    /// `SQLITE_CANTOPEN_SYMLINK`
+    CantOpenSymlink = SQLITE_CANTOPEN_SYMLINK,
+    /// `SQLITE_PROTOCOL`
+    Protocol = SQLITE_PROTOCOL,
+    /// `SQLITE_EMPTY`
+    Empty = SQLITE_EMPTY,
+    /// `SQLITE_SCHEMA`
+    Schema = SQLITE_SCHEMA,
+    /// `SQLITE_TOOBIG`
+    TooBig = SQLITE_TOOBIG,
+    /// `SQLITE_CONSTRAINT`
+    Constraint = SQLITE_CONSTRAINT,
+    /// `SQLITE_CONSTRAINT_CHECK`
+    ConstraintCheck = SQLITE_CONSTRAINT_CHECK,
+    /// `SQLITE_CONSTRAINT_COMMITHOOK`
+    ConstraintCommitHook = SQLITE_CONSTRAINT_COMMITHOOK,
+    /// `SQLITE_CONSTRAINT_FOREIGNKEY`
+    ConstraintForeignKey = SQLITE_CONSTRAINT_FOREIGNKEY,
+    /// `SQLITE_CONSTRAINT_FUNCTION`
+    ConstraintFunction = SQLITE_CONSTRAINT_FUNCTION,
+    /// `SQLITE_CONSTRAINT_NOTNULL`
+    ConstraintNotNull = SQLITE_CONSTRAINT_NOTNULL,
+    /// `SQLITE_CONSTRAINT_PRIMARYKEY`
+    ConstraintPrimaryKey = SQLITE_CONSTRAINT_PRIMARYKEY,
+    /// `SQLITE_CONSTRAINT_TRIGGER`
+    ConstraintTrigger = SQLITE_CONSTRAINT_TRIGGER,
+    /// `SQLITE_CONSTRAINT_UNIQUE`
+    ConstraintUnique = SQLITE_CONSTRAINT_UNIQUE,
+    /// `SQLITE_CONSTRAINT_VTAB`
+    ConstraintVirtualTable = SQLITE_CONSTRAINT_VTAB,
+    /// `SQLITE_CONSTRAINT_ROWID`
+    ConstraintRowid = SQLITE_CONSTRAINT_ROWID,
+    /// `SQLITE_CONSTRAINT_PINNED`
+    ConstraintPinned = SQLITE_CONSTRAINT_PINNED,
+    /// `SQLITE_CONSTRAINT_DATATYPE`
+    ConstraintDatatype = SQLITE_CONSTRAINT_DATATYPE,
+    /// `SQLITE_MISMATCH`
+    Mismatch = SQLITE_MISMATCH,
+    /// `SQLITE_MISUSE`
+    Misuse = SQLITE_MISUSE,
+    /// `SQLITE_NOLFS`
+    NoLargeFile = SQLITE_NOLFS,
+    /// `SQLITE_AUTH`
+    Auth = SQLITE_AUTH,
+    /// `SQLITE_AUTH_USER`
+    AuthUser = SQLITE_AUTH_USER,
+    /// `SQLITE_FORMAT`
+    Format = SQLITE_FORMAT,
+    /// `SQLITE_RANGE`
+    Range = SQLITE_RANGE,
+    /// `SQLITE_NOTADB`
+    NotADatabase = SQLITE_NOTADB,
+    /// `SQLITE_NOTICE`
+    Notice = SQLITE_NOTICE,
+    /// `SQLITE_NOTICE_RECOVER_WAL`
+    NoticeRecoverWal = SQLITE_NOTICE_RECOVER_WAL,
+    /// `SQLITE_NOTICE_RECOVER_ROLLBACK`
+    NoticeRecoverRollback = SQLITE_NOTICE_RECOVER_ROLLBACK,
+    /// `SQLITE_NOTICE_RBU`
+    NoticeRbu = SQLITE_NOTICE_RBU,
+    /// `SQLITE_WARNING`
+    Warning = SQLITE_WARNING,
+    /// `SQLITE_WARNING_AUTOINDEX`
+    WarningAutoIndex = SQLITE_WARNING_AUTOINDEX,
+}
+
 macro_rules! error(
     ($connection:expr, $code:expr) => (
         match crate::error::last($connection) {
             Some(error) => return Err(error),
-            _ => return Err(crate::error::Error {
-                code: Some($code as isize),
-                message: None,
-            }),
+            _ => return Err(crate::error::Error::new(Some($code as isize), None)),
         }
     );
 );
@@ -34,23 +306,106 @@ macro_rules! ok(
     ($result:expr) => (
         match $result {
             crate::ffi::SQLITE_OK => {}
-            code => return Err(crate::error::Error {
-                code: Some(code as isize),
-                message: None,
-            }),
+            code => return Err(crate::error::Error::new(Some(code as isize), None)),
         }
     );
 );
 
 macro_rules! raise(
     ($message:expr $(, $($token:tt)* )?) => (
-        return Err(crate::error::Error {
-            code: None,
-            message: Some(format!($message $(, $($token)* )*)),
-        })
+        return Err(crate::error::Error::new(None, Some(format!($message $(, $($token)* )*))))
     );
 );
 
+impl Error {
+    /// Create an error.
+    #[inline]
+    pub fn new(code: Option<isize>, message: Option<String>) -> Self {
+        Error {
+            code,
+            message,
+            extended_code: None,
+            offset: None,
+            sql: None,
+        }
+    }
+
+    /// Return the byte offset of the error in the SQL text, which is available for such errors as
+    /// syntax errors.
+    #[inline]
+    pub fn offset(&self) -> Option<usize> {
+        self.offset.map(|offset| offset as usize)
+    }
+
+    /// Return the SQL text of the statement that failed, which is available if enabled via
+    /// `Connection::set_error_context`.
+    #[inline]
+    pub fn sql(&self) -> Option<&str> {
+        self.sql.as_deref()
+    }
+
+    /// Return the [extended result code][1], such as `SQLITE_CONSTRAINT_UNIQUE` or
+    /// `SQLITE_BUSY_SNAPSHOT`, falling back to the code when no extended one is available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT UNIQUE)")?;
+    /// connection.execute("INSERT INTO users VALUES ('Alice')")?;
+    /// let error = connection
+    ///     .execute("INSERT INTO users VALUES ('Alice')")
+    ///     .unwrap_err();
+    /// assert_eq!(error.code, Some(sqlite::ffi::SQLITE_CONSTRAINT as isize));
+    /// assert_eq!(
+    ///     error.extended_code(),
+    ///     Some(sqlite::ffi::SQLITE_CONSTRAINT_UNIQUE as isize),
+    /// );
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    ///
+    /// [1]: https://www.sqlite.org/rescode.html#extrc
+    #[inline]
+    pub fn extended_code(&self) -> Option<isize> {
+        self.extended_code.map(|code| code as isize).or(self.code)
+    }
+
+    /// Return the result code, which is the extended one if available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::ResultCode;
+    ///
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name TEXT NOT NULL)")?;
+    /// let error = connection
+    ///     .execute("INSERT INTO users VALUES (NULL)")
+    ///     .unwrap_err();
+    /// assert_eq!(error.result_code(), Some(ResultCode::ConstraintNotNull));
+    /// assert_eq!(error.result_code().unwrap().primary(), ResultCode::Constraint);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn result_code(&self) -> Option<ResultCode> {
+        self.extended_code().map(ResultCode::from_raw)
+    }
+}
+
+impl ResultCode {
+    /// Return the primary result code, which is the code itself if it is not an extended one.
+    #[inline]
+    pub fn primary(&self) -> Self {
+        ResultCode::from_raw(self.as_raw() & 0xff)
+    }
+
+    /// Check if the code is an extended one.
+    #[inline]
+    pub fn is_extended(&self) -> bool {
+        self.as_raw() > 0xff
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match (self.code, &self.message) {
@@ -58,10 +413,38 @@ impl fmt::Display for Error {
             (Some(code), _) => write!(formatter, "an SQLite error (code {code})"),
             (_, Some(message)) => message.fmt(formatter),
             _ => write!(formatter, "an SQLite error"),
+        }?;
+        match self.sql {
+            Some(ref sql) => write!(formatter, " in {sql:?}"),
+            _ => Ok(()),
         }
     }
 }
 
+impl fmt::Display for BatchError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "statement {} at byte {} has failed: {} in {:?}",
+            self.index, self.offset, self.error, self.sql,
+        )
+    }
+}
+
+impl error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<BatchError> for Error {
+    fn from(error: BatchError) -> Self {
+        let BatchError { sql, mut error, .. } = error;
+        error.sql.get_or_insert(sql);
+        error
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         match self.message {
@@ -71,6 +454,10 @@ impl error::Error for Error {
     }
 }
 
+pub fn from_code(raw: *mut ffi::sqlite3, code: core::ffi::c_int) -> Error {
+    last(raw).unwrap_or(Error::new(Some(code as isize), None))
+}
+
 pub fn last(raw: *mut ffi::sqlite3) -> Option<Error> {
     unsafe {
         let code = ffi::sqlite3_errcode(raw);
@@ -81,9 +468,13 @@ pub fn last(raw: *mut ffi::sqlite3) -> Option<Error> {
         if message.is_null() {
             return None;
         }
+        let offset = ffi::sqlite3_error_offset(raw);
         Some(Error {
             code: Some(code as isize),
             message: Some(c_str_to_string!(message)),
+            extended_code: Some(ffi::sqlite3_extended_errcode(raw)),
+            offset: (offset >= 0).then_some(offset),
+            sql: None,
         })
     }
 }
@@ -0,0 +1,353 @@
+use core::ffi::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::value::{Type, Value};
+
+/// An aggregate function.
+///
+/// A new instance is created for each group of rows, and it is fed the arguments of each row of
+/// the group before producing the result.
+pub trait Aggregate: Send + 'static {
+    /// Process the arguments of a row.
+    fn step(&mut self, arguments: &[Value]) -> Result<()>;
+
+    /// Produce the result.
+    fn finalize(self) -> Result<Value>;
+}
+
+/// The context of a function call.
+///
+/// The context gives access to the arguments and allows for setting the result. If no result is
+/// set, the result is `NULL`.
+pub struct Context<'l> {
+    raw: *mut ffi::sqlite3_context,
+    arguments: &'l [*mut ffi::sqlite3_value],
+}
+
+impl<'l> Context<'l> {
+    /// Return the number of arguments.
+    #[inline]
+    pub fn argument_count(&self) -> usize {
+        self.arguments.len()
+    }
+
+    /// Return the type of an argument.
+    ///
+    /// The first argument has index 0.
+    pub fn argument_type(&self, index: usize) -> Result<Type> {
+        Ok(
+            match unsafe { ffi::sqlite3_value_type(self.argument_raw(index)?) } {
+                ffi::SQLITE_BLOB => Type::Binary,
+                ffi::SQLITE_FLOAT => Type::Float,
+                ffi::SQLITE_INTEGER => Type::Integer,
+                ffi::SQLITE_TEXT => Type::String,
+                _ => Type::Null,
+            },
+        )
+    }
+
+    /// Return an argument.
+    ///
+    /// The first argument has index 0.
+    #[inline]
+    pub fn argument(&self, index: usize) -> Result<Value> {
+        Ok(unsafe { read(self.argument_raw(index)?) })
+    }
+
+    /// Set the result.
+    #[inline]
+    pub fn set_result<T: Into<Value>>(&mut self, value: T) {
+        unsafe { set_result(self.raw, &value.into()) };
+    }
+
+    /// Set the result to `NULL`.
+    #[inline]
+    pub fn set_null(&mut self) {
+        unsafe { ffi::sqlite3_result_null(self.raw) };
+    }
+
+    /// Set the result to a blob written in place.
+    ///
+    /// The callback fills a zeroed buffer of the given length, which is allocated by SQLite and
+    /// handed over to it without copying.
+    pub fn set_blob_with<F: FnOnce(&mut [u8])>(&mut self, length: usize, callback: F) {
+        if length == 0 {
+            callback(&mut []);
+            unsafe { set_result(self.raw, &Value::Binary(Vec::new())) };
+            return;
+        }
+        unsafe {
+            let pointer = ffi::sqlite3_malloc64(length as u64) as *mut u8;
+            if pointer.is_null() {
+                ffi::sqlite3_result_error_nomem(self.raw);
+                return;
+            }
+            std::ptr::write_bytes(pointer, 0, length);
+            let buffer = std::slice::from_raw_parts_mut(pointer, length);
+            if let Err(error) = catch_unwind(AssertUnwindSafe(|| callback(buffer))) {
+                ffi::sqlite3_free(pointer as *mut c_void);
+                resume_unwind(error);
+            }
+            ffi::sqlite3_result_blob64(
+                self.raw,
+                pointer as *const c_void,
+                length as u64,
+                Some(ffi::sqlite3_free),
+            );
+        }
+    }
+
+    /// Set the result to an error with a message and an optional code.
+    pub fn set_error(&mut self, message: &str, code: Option<isize>) {
+        unsafe { set_error(self.raw, &Error::new(code, Some(message.into()))) };
+    }
+
+    fn argument_raw(&self, index: usize) -> Result<*mut ffi::sqlite3_value> {
+        match self.arguments.get(index) {
+            Some(raw) => Ok(*raw),
+            _ => raise!("the index is out of range ({})", index),
+        }
+    }
+}
+
+pub fn create_function<F>(
+    connection: &Connection,
+    name: &str,
+    arguments: isize,
+    callback: F,
+) -> Result<()>
+where
+    F: FnMut(&mut Context) -> Result<()> + Send + 'static,
+{
+    unsafe {
+        ok!(
+            connection.as_raw(),
+            ffi::sqlite3_create_function_v2(
+                connection.as_raw(),
+                str_to_cstr!(name).as_ptr(),
+                arguments as c_int,
+                ffi::SQLITE_UTF8,
+                Box::into_raw(Box::new(callback)) as *mut c_void,
+                Some(function_call::<F>),
+                None,
+                None,
+                Some(destroy::<F>),
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn create_overloaded_function<F>(
+    connection: &Connection,
+    name: &str,
+    arities: &[isize],
+    callback: F,
+) -> Result<()>
+where
+    F: FnMut(&mut Context) -> Result<()> + Send + 'static,
+{
+    if arities.is_empty() {
+        raise!("at least one number of arguments should be given");
+    }
+    let callback = Arc::new(Mutex::new(callback));
+    for &arguments in arities {
+        let callback = callback.clone();
+        create_function(connection, name, arguments, move |context| {
+            let mut callback = callback.lock().unwrap();
+            callback(context)
+        })?;
+    }
+    Ok(())
+}
+
+pub fn create_aggregate<T, F>(
+    connection: &Connection,
+    name: &str,
+    arguments: isize,
+    factory: F,
+) -> Result<()>
+where
+    T: Aggregate,
+    F: Fn() -> T + Send + 'static,
+{
+    unsafe {
+        ok!(
+            connection.as_raw(),
+            ffi::sqlite3_create_function_v2(
+                connection.as_raw(),
+                str_to_cstr!(name).as_ptr(),
+                arguments as c_int,
+                ffi::SQLITE_UTF8,
+                Box::into_raw(Box::new(factory)) as *mut c_void,
+                None,
+                Some(aggregate_step::<T, F>),
+                Some(aggregate_final::<T, F>),
+                Some(destroy::<F>),
+            )
+        );
+    }
+    Ok(())
+}
+
+pub unsafe fn read(raw: *mut ffi::sqlite3_value) -> Value {
+    match ffi::sqlite3_value_type(raw) {
+        ffi::SQLITE_BLOB => {
+            let pointer = ffi::sqlite3_value_blob(raw) as *const u8;
+            let count = ffi::sqlite3_value_bytes(raw) as usize;
+            if pointer.is_null() {
+                Value::Binary(Vec::new())
+            } else {
+                Value::Binary(std::slice::from_raw_parts(pointer, count).to_vec())
+            }
+        }
+        ffi::SQLITE_FLOAT => Value::Float(ffi::sqlite3_value_double(raw)),
+        ffi::SQLITE_INTEGER => Value::Integer(ffi::sqlite3_value_int64(raw)),
+        ffi::SQLITE_TEXT => {
+            let pointer = ffi::sqlite3_value_text(raw);
+            let count = ffi::sqlite3_value_bytes(raw) as usize;
+            if pointer.is_null() {
+                Value::String(String::new())
+            } else {
+                let bytes = std::slice::from_raw_parts(pointer, count);
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+        }
+        _ => Value::Null,
+    }
+}
+
+unsafe fn arguments(count: c_int, values: *mut *mut ffi::sqlite3_value) -> Vec<Value> {
+    (0..count as usize)
+        .map(|index| read(*values.add(index)))
+        .collect()
+}
+
+pub unsafe fn set_result(context: *mut ffi::sqlite3_context, value: &Value) {
+    match value {
+        Value::Binary(value) => ffi::sqlite3_result_blob64(
+            context,
+            value.as_ptr() as *const c_void,
+            value.len() as u64,
+            transient!(),
+        ),
+        Value::Float(value) => ffi::sqlite3_result_double(context, *value),
+        Value::Integer(value) => ffi::sqlite3_result_int64(context, *value),
+        Value::String(value) => ffi::sqlite3_result_text64(
+            context,
+            value.as_ptr() as *const c_char,
+            value.len() as u64,
+            transient!(),
+            ffi::SQLITE_UTF8 as u8,
+        ),
+        Value::Null => ffi::sqlite3_result_null(context),
+    }
+}
+
+unsafe fn set_error(context: *mut ffi::sqlite3_context, error: &Error) {
+    let message = error.message.as_deref().unwrap_or("an error in a function");
+    ffi::sqlite3_result_error(
+        context,
+        message.as_ptr() as *const c_char,
+        message.len() as c_int,
+    );
+    if let Some(code) = error.code {
+        ffi::sqlite3_result_error_code(context, code as c_int);
+    }
+}
+
+unsafe fn set_panic(context: *mut ffi::sqlite3_context) {
+    let message = "a function has panicked";
+    ffi::sqlite3_result_error(
+        context,
+        message.as_ptr() as *const c_char,
+        message.len() as c_int,
+    );
+}
+
+extern "C" fn function_call<F>(
+    context: *mut ffi::sqlite3_context,
+    count: c_int,
+    values: *mut *mut ffi::sqlite3_value,
+) where
+    F: FnMut(&mut Context) -> Result<()>,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let callback = &mut *(ffi::sqlite3_user_data(context) as *mut F);
+        let arguments = if count > 0 {
+            std::slice::from_raw_parts(values, count as usize)
+        } else {
+            &[]
+        };
+        let mut value = Context {
+            raw: context,
+            arguments,
+        };
+        if let Err(error) = callback(&mut value) {
+            set_error(context, &error);
+        }
+    }));
+    if result.is_err() {
+        unsafe { set_panic(context) };
+    }
+}
+
+extern "C" fn aggregate_step<T, F>(
+    context: *mut ffi::sqlite3_context,
+    count: c_int,
+    values: *mut *mut ffi::sqlite3_value,
+) where
+    T: Aggregate,
+    F: Fn() -> T,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let state = ffi::sqlite3_aggregate_context(context, std::mem::size_of::<*mut T>() as c_int)
+            as *mut *mut T;
+        if state.is_null() {
+            ffi::sqlite3_result_error_nomem(context);
+            return;
+        }
+        if (*state).is_null() {
+            let factory = &*(ffi::sqlite3_user_data(context) as *const F);
+            *state = Box::into_raw(Box::new(factory()));
+        }
+        if let Err(error) = (**state).step(&arguments(count, values)) {
+            set_error(context, &error);
+        }
+    }));
+    if result.is_err() {
+        unsafe { set_panic(context) };
+    }
+}
+
+extern "C" fn aggregate_final<T, F>(context: *mut ffi::sqlite3_context)
+where
+    T: Aggregate,
+    F: Fn() -> T,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let state = ffi::sqlite3_aggregate_context(context, 0) as *mut *mut T;
+        let aggregate = if state.is_null() || (*state).is_null() {
+            let factory = &*(ffi::sqlite3_user_data(context) as *const F);
+            factory()
+        } else {
+            let aggregate = Box::from_raw(*state);
+            *state = std::ptr::null_mut();
+            *aggregate
+        };
+        match aggregate.finalize() {
+            Ok(value) => set_result(context, &value),
+            Err(error) => set_error(context, &error),
+        }
+    }));
+    if result.is_err() {
+        unsafe { set_panic(context) };
+    }
+}
+
+extern "C" fn destroy<F>(data: *mut c_void) {
+    let _ = catch_unwind(|| unsafe { drop(Box::from_raw(data as *mut F)) });
+}
@@ -0,0 +1,279 @@
+//! Handle-oriented interface.
+//!
+//! The interface refers to connections and prepared statements via opaque integer handles
+//! instead of borrowed objects, which makes it suitable for bridging the crate to environments
+//! that cannot hold Rust references, such as host functions exposed to WebAssembly plugins. All
+//! objects are owned by a `Registry`, and values cross the boundary as `Value`.
+//!
+//! # Examples
+//!
+//! ```
+//! use sqlite::handles::Registry;
+//! use sqlite::{State, Value};
+//!
+//! let mut registry = Registry::new();
+//! let connection = registry.open(":memory:")?;
+//! registry.execute(connection, "CREATE TABLE users (name TEXT)")?;
+//! let statement = registry.prepare(connection, "INSERT INTO users VALUES (?)")?;
+//! registry.bind(statement, 1, Value::from("Alice"))?;
+//! assert_eq!(registry.step(statement)?, State::Done);
+//! registry.finalize(statement)?;
+//! let statement = registry.prepare(connection, "SELECT name FROM users")?;
+//! assert_eq!(registry.step(statement)?, State::Row);
+//! assert_eq!(registry.read(statement, 0)?, Value::from("Alice"));
+//! registry.close(connection)?;
+//! assert!(registry.step(statement).is_err());
+//! # Ok::<(), sqlite::Error>(())
+//! ```
+
+use std::collections::HashMap;
+
+use crate::connection::{Connection, OpenFlags};
+use crate::error::{ErrorContext, Result};
+use crate::redaction::Redaction;
+use crate::statement::{State, Statement};
+use crate::value::Value;
+
+/// A handle of a connection.
+///
+/// The handle can be converted to and from `u32` for passing it across the boundary.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ConnectionHandle(u32);
+
+/// A handle of a prepared statement.
+///
+/// The handle can be converted to and from `u32` for passing it across the boundary.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct StatementHandle(u32);
+
+/// A registry of connections and prepared statements.
+///
+/// Handles are never reused within a registry. Closing a connection finalizes its statements.
+#[derive(Default)]
+pub struct Registry {
+    // The statements are declared first so that they are dropped before their connections.
+    statements: HashMap<StatementHandle, (ConnectionHandle, Statement<'static>)>,
+    connections: HashMap<ConnectionHandle, Connection>,
+    next: u32,
+}
+
+impl Registry {
+    /// Create a registry.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a read-write connection to a new or existing database.
+    #[inline]
+    pub fn open<T: AsRef<str>>(&mut self, path: T) -> Result<ConnectionHandle> {
+        self.open_with_flags(path, OpenFlags::new().with_create().with_read_write())
+    }
+
+    /// Open a connection with specific flags.
+    pub fn open_with_flags<T: AsRef<str>>(
+        &mut self,
+        path: T,
+        flags: OpenFlags,
+    ) -> Result<ConnectionHandle> {
+        let connection = Connection::open_with_flags(path.as_ref(), flags)?;
+        let handle = ConnectionHandle(self.allocate()?);
+        self.connections.insert(handle, connection);
+        Ok(handle)
+    }
+
+    /// Close a connection and finalize its statements.
+    pub fn close(&mut self, connection: ConnectionHandle) -> Result<()> {
+        if !self.connections.contains_key(&connection) {
+            raise!("the connection handle is invalid ({})", connection.0);
+        }
+        self.statements.retain(|_, (owner, _)| *owner != connection);
+        self.connections.remove(&connection);
+        Ok(())
+    }
+
+    /// Execute a statement without processing the resulting rows if any.
+    ///
+    /// See `Connection::execute` for further details.
+    #[inline]
+    pub fn execute<T: AsRef<str>>(&self, connection: ConnectionHandle, statement: T) -> Result<()> {
+        self.connection(connection)?.execute(statement)
+    }
+
+    /// Return the number of rows modified by the last statement.
+    #[inline]
+    pub fn change_count(&self, connection: ConnectionHandle) -> Result<usize> {
+        Ok(self.connection(connection)?.change_count())
+    }
+
+    /// Return the rowid of the last inserted row.
+    #[inline]
+    pub fn last_insert_rowid(&self, connection: ConnectionHandle) -> Result<i64> {
+        Ok(self.connection(connection)?.last_insert_rowid())
+    }
+
+    /// Attach the SQL text of failed statements to errors.
+    ///
+    /// See `Connection::set_error_context` for further details.
+    #[inline]
+    pub fn set_error_context(
+        &mut self,
+        connection: ConnectionHandle,
+        value: ErrorContext,
+    ) -> Result<()> {
+        self.connection_mut(connection)?.set_error_context(value);
+        Ok(())
+    }
+
+    /// Set a policy for redacting bound values in the SQL text attached to errors.
+    ///
+    /// See `Connection::set_redaction` for further details.
+    #[inline]
+    pub fn set_redaction(&mut self, connection: ConnectionHandle, value: Redaction) -> Result<()> {
+        self.connection_mut(connection)?.set_redaction(value);
+        Ok(())
+    }
+
+    /// Create a prepared statement.
+    pub fn prepare<T: AsRef<str>>(
+        &mut self,
+        connection: ConnectionHandle,
+        statement: T,
+    ) -> Result<StatementHandle> {
+        let statement = self.connection(connection)?.prepare(statement)?;
+        // The statement outlives the borrow of the connection, which stays in the registry until
+        // all its statements are finalized.
+        let statement =
+            unsafe { std::mem::transmute::<Statement<'_>, Statement<'static>>(statement) };
+        let handle = StatementHandle(self.allocate()?);
+        self.statements.insert(handle, (connection, statement));
+        Ok(handle)
+    }
+
+    /// Bind a value to a parameter.
+    ///
+    /// The first parameter has index 1.
+    #[inline]
+    pub fn bind(&mut self, statement: StatementHandle, index: usize, value: Value) -> Result<()> {
+        self.statement_mut(statement)?.bind((index, value))
+    }
+
+    /// Bind a value to a named parameter.
+    #[inline]
+    pub fn bind_by_name(
+        &mut self,
+        statement: StatementHandle,
+        name: &str,
+        value: Value,
+    ) -> Result<()> {
+        self.statement_mut(statement)?.bind((name, value))
+    }
+
+    /// Advance to the next state.
+    #[inline]
+    pub fn step(&mut self, statement: StatementHandle) -> Result<State> {
+        self.statement_mut(statement)?.next()
+    }
+
+    /// Read a value from a column.
+    ///
+    /// The first column has index 0.
+    #[inline]
+    pub fn read(&self, statement: StatementHandle, index: usize) -> Result<Value> {
+        self.statement(statement)?.read(index)
+    }
+
+    /// Return the number of columns.
+    #[inline]
+    pub fn column_count(&self, statement: StatementHandle) -> Result<usize> {
+        Ok(self.statement(statement)?.column_count())
+    }
+
+    /// Return the name of a column.
+    ///
+    /// The first column has index 0.
+    #[inline]
+    pub fn column_name(&self, statement: StatementHandle, index: usize) -> Result<String> {
+        self.statement(statement)?
+            .column_name(index)
+            .map(String::from)
+    }
+
+    /// Reset the statement, keeping its bindings.
+    #[inline]
+    pub fn reset(&mut self, statement: StatementHandle) -> Result<()> {
+        self.statement_mut(statement)?.reset()
+    }
+
+    /// Finalize a statement.
+    pub fn finalize(&mut self, statement: StatementHandle) -> Result<()> {
+        match self.statements.remove(&statement) {
+            Some(_) => Ok(()),
+            _ => raise!("the statement handle is invalid ({})", statement.0),
+        }
+    }
+
+    fn allocate(&mut self) -> Result<u32> {
+        let Some(next) = self.next.checked_add(1) else {
+            raise!("the handles are exhausted");
+        };
+        self.next = next;
+        Ok(next)
+    }
+
+    fn connection(&self, handle: ConnectionHandle) -> Result<&Connection> {
+        match self.connections.get(&handle) {
+            Some(connection) => Ok(connection),
+            _ => raise!("the connection handle is invalid ({})", handle.0),
+        }
+    }
+
+    fn connection_mut(&mut self, handle: ConnectionHandle) -> Result<&mut Connection> {
+        match self.connections.get_mut(&handle) {
+            Some(connection) => Ok(connection),
+            _ => raise!("the connection handle is invalid ({})", handle.0),
+        }
+    }
+
+    fn statement(&self, handle: StatementHandle) -> Result<&Statement<'static>> {
+        match self.statements.get(&handle) {
+            Some((_, statement)) => Ok(statement),
+            _ => raise!("the statement handle is invalid ({})", handle.0),
+        }
+    }
+
+    fn statement_mut(&mut self, handle: StatementHandle) -> Result<&mut Statement<'static>> {
+        match self.statements.get_mut(&handle) {
+            Some((_, statement)) => Ok(statement),
+            _ => raise!("the statement handle is invalid ({})", handle.0),
+        }
+    }
+}
+
+impl From<ConnectionHandle> for u32 {
+    #[inline]
+    fn from(handle: ConnectionHandle) -> Self {
+        handle.0
+    }
+}
+
+impl From<u32> for ConnectionHandle {
+    #[inline]
+    fn from(value: u32) -> Self {
+        ConnectionHandle(value)
+    }
+}
+
+impl From<StatementHandle> for u32 {
+    #[inline]
+    fn from(handle: StatementHandle) -> Self {
+        handle.0
+    }
+}
+
+impl From<u32> for StatementHandle {
+    #[inline]
+    fn from(value: u32) -> Self {
+        StatementHandle(value)
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::connection::{Connection, OpenFlags};
+use crate::error::Result;
+use crate::statement::State;
+
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// The [header][1] of a database file.
+///
+/// [1]: https://www.sqlite.org/fileformat.html#the_database_header
+#[derive(Clone, Debug)]
+pub struct Header {
+    page_size: u32,
+    write_version: u8,
+    read_version: u8,
+    change_counter: u32,
+    page_count: u32,
+    encoding: Encoding,
+    user_version: i32,
+    application_id: i32,
+    library_version: u32,
+}
+
+/// A text encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// UTF-8.
+    Utf8,
+    /// UTF-16 little endian.
+    Utf16le,
+    /// UTF-16 big endian.
+    Utf16be,
+}
+
+/// The extent of validating a database.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Check {
+    /// Validate the header only.
+    Header,
+    /// Validate the header and run `PRAGMA quick_check`, which skips verifying indices.
+    Quick,
+    /// Validate the header and run `PRAGMA integrity_check`.
+    Full,
+}
+
+/// A database given either as a path or as bytes.
+#[derive(Clone, Copy, Debug)]
+pub enum Source<'l> {
+    /// A path to a file.
+    Path(&'l Path),
+    /// The content of a file.
+    Bytes(&'l [u8]),
+}
+
+impl Header {
+    /// Return the page size in bytes.
+    #[inline]
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Return the file format write version, which is 1 for rollback journals and 2 for WAL.
+    #[inline]
+    pub fn write_version(&self) -> u8 {
+        self.write_version
+    }
+
+    /// Return the file format read version, which is 1 for rollback journals and 2 for WAL.
+    #[inline]
+    pub fn read_version(&self) -> u8 {
+        self.read_version
+    }
+
+    /// Return the file change counter.
+    #[inline]
+    pub fn change_counter(&self) -> u32 {
+        self.change_counter
+    }
+
+    /// Return the size of the database in pages.
+    ///
+    /// The value might be outdated if the database was last written by a legacy version of
+    /// SQLite.
+    #[inline]
+    pub fn page_count(&self) -> u32 {
+        self.page_count
+    }
+
+    /// Return the text encoding.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Return the user version.
+    #[inline]
+    pub fn user_version(&self) -> i32 {
+        self.user_version
+    }
+
+    /// Return the application identifier.
+    #[inline]
+    pub fn application_id(&self) -> i32 {
+        self.application_id
+    }
+
+    /// Return the version number of SQLite that last modified the database.
+    ///
+    /// For instance, the version `3.8.11.1` corresponds to the integer `3008011`.
+    #[inline]
+    pub fn library_version(&self) -> u32 {
+        self.library_version
+    }
+}
+
+impl<'l> From<&'l Path> for Source<'l> {
+    #[inline]
+    fn from(value: &'l Path) -> Self {
+        Source::Path(value)
+    }
+}
+
+impl<'l> From<&'l PathBuf> for Source<'l> {
+    #[inline]
+    fn from(value: &'l PathBuf) -> Self {
+        Source::Path(value)
+    }
+}
+
+impl<'l> From<&'l str> for Source<'l> {
+    #[inline]
+    fn from(value: &'l str) -> Self {
+        Source::Path(Path::new(value))
+    }
+}
+
+impl<'l> From<&'l [u8]> for Source<'l> {
+    #[inline]
+    fn from(value: &'l [u8]) -> Self {
+        Source::Bytes(value)
+    }
+}
+
+impl<'l> From<&'l Vec<u8>> for Source<'l> {
+    #[inline]
+    fn from(value: &'l Vec<u8>) -> Self {
+        Source::Bytes(value)
+    }
+}
+
+pub fn is_database(source: Source, check: Check) -> bool {
+    let header = match source {
+        Source::Path(path) => inspect(path),
+        Source::Bytes(bytes) => match bytes.get(..100) {
+            Some(buffer) => parse(buffer.try_into().unwrap()),
+            _ => return false,
+        },
+    };
+    if header.is_err() {
+        return false;
+    }
+    let pragma = match check {
+        Check::Header => return true,
+        Check::Quick => "PRAGMA quick_check",
+        Check::Full => "PRAGMA integrity_check",
+    };
+    let connection = match source {
+        Source::Path(path) => {
+            Connection::open_with_flags(path, OpenFlags::new().with_read_only().with_no_mutex())
+        }
+        Source::Bytes(bytes) => Connection::open(":memory:")
+            .and_then(|connection| connection.deserialize("main", bytes).map(|_| connection)),
+    };
+    let check = |connection: Connection| -> Result<bool> {
+        let mut statement = connection.prepare(pragma)?;
+        let mut valid = true;
+        while let State::Row = statement.next()? {
+            valid &= statement.read::<String, _>(0)? == "ok";
+        }
+        Ok(valid)
+    };
+    connection.and_then(check).unwrap_or(false)
+}
+
+pub fn inspect(path: &Path) -> Result<Header> {
+    let mut buffer = [0u8; 100];
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) => raise!("failed to open a file ({})", error),
+    };
+    if file.read_exact(&mut buffer).is_err() {
+        raise!("the file is not an SQLite database");
+    }
+    parse(&buffer)
+}
+
+pub fn parse(buffer: &[u8; 100]) -> Result<Header> {
+    if &buffer[..16] != MAGIC {
+        raise!("the file is not an SQLite database");
+    }
+    let u32_at = |offset: usize| {
+        u32::from_be_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ])
+    };
+    let page_size = match u16::from_be_bytes([buffer[16], buffer[17]]) {
+        1 => 65536,
+        value if value >= 512 && value.is_power_of_two() => value as u32,
+        value => raise!("the page size is invalid ({})", value),
+    };
+    let encoding = match u32_at(56) {
+        1 => Encoding::Utf8,
+        2 => Encoding::Utf16le,
+        3 => Encoding::Utf16be,
+        value => raise!("the text encoding is invalid ({})", value),
+    };
+    Ok(Header {
+        page_size,
+        write_version: buffer[18],
+        read_version: buffer[19],
+        change_counter: u32_at(24),
+        page_count: u32_at(28),
+        encoding,
+        user_version: u32_at(60) as i32,
+        application_id: u32_at(68) as i32,
+        library_version: u32_at(96),
+    })
+}
@@ -0,0 +1,483 @@
+use core::ffi::{c_char, c_int, c_uint, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
+
+use crate::connection::Connection;
+use crate::error::Result;
+#[cfg(feature = "preupdate")]
+use crate::value::Value;
+
+/// A registered hook.
+///
+/// The callback is unregistered when the hook is dropped unless it has been replaced by another
+/// one in the meantime.
+#[must_use = "the callback is unregistered when the hook is dropped"]
+pub struct Hook<'l> {
+    connection: &'l Connection,
+    kind: Kind,
+    id: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    Authorizer,
+    Busy,
+    Commit,
+    #[cfg(feature = "preupdate")]
+    Preupdate,
+    Progress,
+    Rollback,
+    Trace,
+    Update,
+}
+
+/// An action checked by an authorizer while a statement is being prepared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Action<'l> {
+    code: isize,
+    first: Option<&'l str>,
+    second: Option<&'l str>,
+    database: Option<&'l str>,
+    accessor: Option<&'l str>,
+}
+
+/// A decision made by an authorizer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Authorization {
+    /// Allow the action.
+    Allow,
+    /// Reject the statement, which then fails with `SQLITE_AUTH`.
+    Deny,
+    /// Disallow the specific action but allow the statement, which, for instance, reads `NULL`
+    /// instead of the column.
+    Ignore,
+}
+
+/// An event reported by a trace callback.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trace<'l> {
+    /// A statement starting to run, with its SQL text or, for triggers, a comment naming the
+    /// trigger.
+    Statement(&'l str),
+    /// A statement finished running, with its SQL text and the time it took.
+    Profile(&'l str, Duration),
+    /// A statement producing a row, with its SQL text.
+    Row(&'l str),
+    /// The connection being closed.
+    Close,
+}
+
+/// Events reported by a trace callback.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceFlags(c_uint);
+
+/// An operation reported by an update hook.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// An insert.
+    Insert,
+    /// An update.
+    Update,
+    /// A delete.
+    Delete,
+}
+
+/// A change about to be made, which is reported by a preupdate hook.
+///
+/// The change is accessible only within the callback.
+#[cfg(feature = "preupdate")]
+pub struct Preupdate<'l> {
+    raw: *mut ffi::sqlite3,
+    operation: Operation,
+    database: &'l str,
+    table: &'l str,
+    old_rowid: i64,
+    new_rowid: i64,
+}
+
+#[cfg(feature = "preupdate")]
+extern "C" {
+    fn sqlite3_preupdate_hook(
+        db: *mut ffi::sqlite3,
+        callback: Option<
+            unsafe extern "C" fn(
+                *mut c_void,
+                *mut ffi::sqlite3,
+                c_int,
+                *const c_char,
+                *const c_char,
+                ffi::sqlite3_int64,
+                ffi::sqlite3_int64,
+            ),
+        >,
+        data: *mut c_void,
+    ) -> *mut c_void;
+    fn sqlite3_preupdate_old(
+        db: *mut ffi::sqlite3,
+        index: c_int,
+        value: *mut *mut ffi::sqlite3_value,
+    ) -> c_int;
+    fn sqlite3_preupdate_new(
+        db: *mut ffi::sqlite3,
+        index: c_int,
+        value: *mut *mut ffi::sqlite3_value,
+    ) -> c_int;
+    fn sqlite3_preupdate_count(db: *mut ffi::sqlite3) -> c_int;
+    fn sqlite3_preupdate_depth(db: *mut ffi::sqlite3) -> c_int;
+}
+
+#[cfg(feature = "preupdate")]
+impl<'l> Preupdate<'l> {
+    /// Return the kind of the operation.
+    #[inline]
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Return the schema name.
+    #[inline]
+    pub fn database(&self) -> &str {
+        self.database
+    }
+
+    /// Return the table name.
+    #[inline]
+    pub fn table(&self) -> &str {
+        self.table
+    }
+
+    /// Return the rowid of the row before the change.
+    ///
+    /// The value is meaningless for inserts.
+    #[inline]
+    pub fn old_rowid(&self) -> i64 {
+        self.old_rowid
+    }
+
+    /// Return the rowid of the row after the change.
+    ///
+    /// The value is meaningless for deletes.
+    #[inline]
+    pub fn new_rowid(&self) -> i64 {
+        self.new_rowid
+    }
+
+    /// Return the number of columns.
+    #[inline]
+    pub fn column_count(&self) -> usize {
+        unsafe { sqlite3_preupdate_count(self.raw) as usize }
+    }
+
+    /// Return the depth of the trigger causing the change.
+    ///
+    /// The depth is 0 for direct changes, 1 for changes caused by top-level triggers, and so on.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        unsafe { sqlite3_preupdate_depth(self.raw) as usize }
+    }
+
+    /// Read the value of a column before the change.
+    ///
+    /// The value is available only for updates and deletes. The first column has index 0.
+    pub fn read_old(&self, index: usize) -> Result<Value> {
+        if self.operation == Operation::Insert {
+            raise!("the old values are not available for inserts");
+        }
+        let mut value = std::ptr::null_mut();
+        unsafe {
+            ok!(
+                self.raw,
+                sqlite3_preupdate_old(self.raw, index as c_int, &mut value)
+            );
+            Ok(crate::function::read(value))
+        }
+    }
+
+    /// Read the value of a column after the change.
+    ///
+    /// The value is available only for inserts and updates. The first column has index 0.
+    pub fn read_new(&self, index: usize) -> Result<Value> {
+        if self.operation == Operation::Delete {
+            raise!("the new values are not available for deletes");
+        }
+        let mut value = std::ptr::null_mut();
+        unsafe {
+            ok!(
+                self.raw,
+                sqlite3_preupdate_new(self.raw, index as c_int, &mut value)
+            );
+            Ok(crate::function::read(value))
+        }
+    }
+}
+
+impl<'l> Hook<'l> {
+    /// Keep the callback registered until it is replaced or removed explicitly or the connection
+    /// is closed.
+    #[inline]
+    pub fn keep(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl<'l> Drop for Hook<'l> {
+    #[inline]
+    fn drop(&mut self) {
+        crate::connection::release_hook(self.connection, self.kind, self.id);
+    }
+}
+
+impl TraceFlags {
+    /// Create flags for tracing no events.
+    #[inline]
+    pub fn new() -> Self {
+        TraceFlags(0)
+    }
+
+    /// Report statements starting to run.
+    pub fn with_statement(mut self) -> Self {
+        self.0 |= ffi::SQLITE_TRACE_STMT as c_uint;
+        self
+    }
+
+    /// Report statements finished running along with the time they took.
+    pub fn with_profile(mut self) -> Self {
+        self.0 |= ffi::SQLITE_TRACE_PROFILE as c_uint;
+        self
+    }
+
+    /// Report rows produced by statements.
+    pub fn with_row(mut self) -> Self {
+        self.0 |= ffi::SQLITE_TRACE_ROW as c_uint;
+        self
+    }
+
+    /// Report the connection being closed.
+    pub fn with_close(mut self) -> Self {
+        self.0 |= ffi::SQLITE_TRACE_CLOSE as c_uint;
+        self
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn as_raw(&self) -> c_uint {
+        self.0
+    }
+}
+
+impl Default for TraceFlags {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Action<'_> {
+    /// Return the action code, such as `SQLITE_READ` or `SQLITE_INSERT`.
+    #[inline]
+    pub fn code(&self) -> isize {
+        self.code
+    }
+
+    /// Return the first argument, whose meaning depends on the code, such as the table name.
+    #[inline]
+    pub fn first(&self) -> Option<&str> {
+        self.first
+    }
+
+    /// Return the second argument, whose meaning depends on the code, such as the column name.
+    #[inline]
+    pub fn second(&self) -> Option<&str> {
+        self.second
+    }
+
+    /// Return the schema name.
+    #[inline]
+    pub fn database(&self) -> Option<&str> {
+        self.database
+    }
+
+    /// Return the name of the innermost trigger or view responsible for the action.
+    #[inline]
+    pub fn accessor(&self) -> Option<&str> {
+        self.accessor
+    }
+}
+
+impl Operation {
+    fn from_raw(value: c_int) -> Option<Self> {
+        match value {
+            ffi::SQLITE_INSERT => Some(Operation::Insert),
+            ffi::SQLITE_UPDATE => Some(Operation::Update),
+            ffi::SQLITE_DELETE => Some(Operation::Delete),
+            _ => None,
+        }
+    }
+}
+
+pub fn new(connection: &Connection, kind: Kind, id: usize) -> Hook<'_> {
+    Hook {
+        connection,
+        kind,
+        id,
+    }
+}
+
+pub extern "C" fn authorizer_callback<F>(
+    callback: *mut c_void,
+    code: c_int,
+    first: *const c_char,
+    second: *const c_char,
+    database: *const c_char,
+    accessor: *const c_char,
+) -> c_int
+where
+    F: FnMut(&Action) -> Authorization,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let text = |value: *const c_char| {
+            if value.is_null() {
+                None
+            } else {
+                c_str_to_str!(value).ok()
+            }
+        };
+        let action = Action {
+            code: code as isize,
+            first: text(first),
+            second: text(second),
+            database: text(database),
+            accessor: text(accessor),
+        };
+        (*(callback as *mut F))(&action)
+    }));
+    match result {
+        Ok(Authorization::Allow) => ffi::SQLITE_OK,
+        Ok(Authorization::Ignore) => ffi::SQLITE_IGNORE,
+        _ => ffi::SQLITE_DENY,
+    }
+}
+
+pub extern "C" fn update_callback<F>(
+    callback: *mut c_void,
+    operation: c_int,
+    database: *const c_char,
+    table: *const c_char,
+    rowid: ffi::sqlite3_int64,
+) where
+    F: FnMut(Operation, &str, &str, i64),
+{
+    let _ = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let operation = match Operation::from_raw(operation) {
+            Some(operation) => operation,
+            _ => return,
+        };
+        let database = c_str_to_str!(database).unwrap_or_default();
+        let table = c_str_to_str!(table).unwrap_or_default();
+        (*(callback as *mut F))(operation, database, table, rowid);
+    }));
+}
+
+pub extern "C" fn commit_callback<F>(callback: *mut c_void) -> c_int
+where
+    F: FnMut() -> Result<()>,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe { (*(callback as *mut F))() }));
+    match result {
+        Ok(Ok(_)) => 0,
+        _ => 1,
+    }
+}
+
+pub extern "C" fn rollback_callback<F>(callback: *mut c_void)
+where
+    F: FnMut(),
+{
+    let _ = catch_unwind(AssertUnwindSafe(|| unsafe { (*(callback as *mut F))() }));
+}
+
+#[cfg(feature = "preupdate")]
+pub unsafe fn set_preupdate_hook<F>(raw: *mut ffi::sqlite3, callback: Option<&F>)
+where
+    F: FnMut(&Preupdate),
+{
+    match callback {
+        Some(callback) => sqlite3_preupdate_hook(
+            raw,
+            Some(preupdate_callback::<F>),
+            callback as *const F as *mut F as *mut _,
+        ),
+        _ => sqlite3_preupdate_hook(raw, None, std::ptr::null_mut()),
+    };
+}
+
+#[cfg(feature = "preupdate")]
+extern "C" fn preupdate_callback<F>(
+    callback: *mut c_void,
+    raw: *mut ffi::sqlite3,
+    operation: c_int,
+    database: *const c_char,
+    table: *const c_char,
+    old_rowid: ffi::sqlite3_int64,
+    new_rowid: ffi::sqlite3_int64,
+) where
+    F: FnMut(&Preupdate),
+{
+    let _ = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let operation = match Operation::from_raw(operation) {
+            Some(operation) => operation,
+            _ => return,
+        };
+        let change = Preupdate {
+            raw,
+            operation,
+            database: c_str_to_str!(database).unwrap_or_default(),
+            table: c_str_to_str!(table).unwrap_or_default(),
+            old_rowid,
+            new_rowid,
+        };
+        (*(callback as *mut F))(&change);
+    }));
+}
+
+pub extern "C" fn progress_callback<F>(callback: *mut c_void) -> c_int
+where
+    F: FnMut() -> bool,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| unsafe { (*(callback as *mut F))() }));
+    c_int::from(!result.unwrap_or(false))
+}
+
+pub extern "C" fn trace_callback<F>(
+    kind: c_uint,
+    callback: *mut c_void,
+    pointer: *mut c_void,
+    extra: *mut c_void,
+) -> c_int
+where
+    F: FnMut(&Trace),
+{
+    let _ = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let sql = || {
+            let sql = ffi::sqlite3_sql(pointer as *mut ffi::sqlite3_stmt);
+            if sql.is_null() {
+                ""
+            } else {
+                c_str_to_str!(sql).unwrap_or_default()
+            }
+        };
+        let trace = match kind as c_int {
+            ffi::SQLITE_TRACE_STMT => {
+                Trace::Statement(c_str_to_str!(extra as *const c_char).unwrap_or_default())
+            }
+            ffi::SQLITE_TRACE_PROFILE => {
+                Trace::Profile(sql(), Duration::from_nanos(*(extra as *const i64) as u64))
+            }
+            ffi::SQLITE_TRACE_ROW => Trace::Row(sql()),
+            ffi::SQLITE_TRACE_CLOSE => Trace::Close,
+            _ => return,
+        };
+        (*(callback as *mut F))(&trace);
+    }));
+    0
+}
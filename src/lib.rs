@@ -124,22 +124,80 @@ macro_rules! str_to_cstr(
     );
 );
 
+// https://sqlite.org/c3ref/c_static.html
+macro_rules! transient(
+    () => (
+        std::mem::transmute::<
+            *const std::ffi::c_void,
+            std::option::Option<unsafe extern "C" fn(*mut std::ffi::c_void)>
+        >(!0 as *const core::ffi::c_void)
+    );
+);
+
 #[macro_use]
 mod error;
 mod value;
 
+mod alter;
+mod backoff;
+mod backup;
+mod cache;
+mod capabilities;
+mod clock;
 mod connection;
 mod cursor;
+#[cfg(feature = "time")]
+mod datetime;
+mod function;
+mod header;
+mod hook;
+mod log;
+mod params;
+mod pragma;
+mod recorder;
+mod redaction;
+mod snapshot;
 mod statement;
+mod transaction;
+#[cfg(feature = "uuid")]
+mod uuid;
+mod writer;
+
+#[cfg(feature = "handles")]
+pub mod handles;
+pub mod vfs;
+pub mod vtab;
 
-pub use error::{Error, Result};
+pub use backoff::Backoff;
+pub use backup::{Backup, BackupOptions};
+pub use cache::CachedStatement;
+pub use capabilities::Capabilities;
+pub use error::{BatchError, Error, ErrorContext, Result, ResultCode};
+pub use function::{Aggregate, Context};
+pub use header::{Check, Encoding, Header, Source};
+#[cfg(feature = "preupdate")]
+pub use hook::Preupdate;
+pub use hook::{Action, Authorization, Hook, Operation, Trace, TraceFlags};
+pub use params::Params;
+pub use pragma::{IndexInfo, TableInfo, TableKind, TriggerInfo, ViewInfo};
+pub use recorder::{Record, Recorder, Session};
+pub use redaction::Redaction;
+#[cfg(feature = "derive")]
+pub use sqlite_derive::SqlNewtype;
 pub use value::{Type, Value};
 
-pub use connection::{Connection, ConnectionThreadSafe, OpenFlags};
-pub use cursor::{Cursor, CursorWithOwnership, Row, RowIndex};
+pub use connection::{
+    AttachOptions, BusyStatistics, Connection, ConnectionThreadSafe, CopyOptions, InterruptHandle,
+    OpenFlags, StatementInfo, TagHandle,
+};
+pub use cursor::{Cursor, CursorWithOwnership, MapInto, Prefetch, Row, RowIndex, Values};
+pub use snapshot::{SnapshotHandle, SnapshotScheduler};
 pub use statement::{
-    Bindable, BindableWithIndex, ColumnIndex, ParameterIndex, ReadableWithIndex, State, Statement,
+    Bindable, BindableWithIndex, ColumnIndex, ParameterIndex, PrepareFlags, RawValue,
+    ReadableWithIndex, State, Statement, Zeroblob,
 };
+pub use transaction::{MultiTx, PinnedReader, Savepoint, Transaction, TransactionBehavior};
+pub use writer::{Pending, Writer, WriterOptions};
 
 /// Open a read-write connection to a new or existing database.
 #[inline]
@@ -147,6 +205,60 @@ pub fn open<T: AsRef<std::path::Path>>(path: T) -> Result<Connection> {
     Connection::open(path)
 }
 
+/// Read the header of a database file without opening it.
+#[inline]
+pub fn inspect_header<T: AsRef<std::path::Path>>(path: T) -> Result<Header> {
+    header::inspect(path.as_ref())
+}
+
+/// Check if a file or a byte buffer contains a database.
+///
+/// The header is always validated, and depending on the check, the content of the database is
+/// validated as well. For files, the latter is done via a read-only connection.
+///
+/// # Examples
+///
+/// ```
+/// use sqlite::Check;
+///
+/// assert!(!sqlite::is_database(&b"SQLite format 2"[..], Check::Header));
+/// ```
+#[inline]
+pub fn is_database<'l, T: Into<Source<'l>>>(source: T, check: Check) -> bool {
+    header::is_database(source.into(), check)
+}
+
+/// Set a callback for receiving the messages logged by SQLite.
+///
+/// The callback receives the result code and the message, which are warnings about such issues
+/// as database corruption or misuse of the library. It is shared by all connections and can be
+/// triggered from any thread. The callback has to be set before any connection is opened;
+/// otherwise, the function fails with `SQLITE_MISUSE`.
+///
+/// # Examples
+///
+/// ```
+/// sqlite::config_log(|code, message| eprintln!("{message} (code {code})"))?;
+/// let connection = sqlite::open(":memory:")?;
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[inline]
+pub fn config_log<F>(callback: F) -> Result<()>
+where
+    F: FnMut(isize, &str) + Send + 'static,
+{
+    log::config(callback)
+}
+
+/// Suspend the current thread for at least a number of milliseconds.
+///
+/// The pause is done by the operating system interface of SQLite, and the number of milliseconds
+/// actually slept is returned, which is rounded up to the granularity supported by the system.
+#[inline]
+pub fn sleep(milliseconds: usize) -> usize {
+    unsafe { ffi::sqlite3_sleep(milliseconds as core::ffi::c_int) as usize }
+}
+
 /// Return the version number of SQLite.
 ///
 /// For instance, the version `3.8.11.1` corresponds to the integer `3008011`.
@@ -0,0 +1,105 @@
+use core::ffi::{c_char, c_int, c_void};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use crate::error::Result;
+
+type Logger = Box<dyn FnMut(isize, &str) + Send>;
+
+type Tag = Arc<Mutex<Option<String>>>;
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+// The labels of the connections keyed by their raw handles.
+static TAGS: Mutex<Option<HashMap<usize, Tag>>> = Mutex::new(None);
+
+thread_local! {
+    static LOGGING: Cell<bool> = const { Cell::new(false) };
+    // The raw handle of the connection being used on the thread if any.
+    static CONNECTION: Cell<*mut ffi::sqlite3> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+pub fn config<F>(callback: F) -> Result<()>
+where
+    F: FnMut(isize, &str) + Send + 'static,
+{
+    unsafe {
+        ok!(ffi::sqlite3_config(
+            ffi::SQLITE_CONFIG_LOG,
+            log_callback as extern "C" fn(*mut c_void, c_int, *const c_char),
+            std::ptr::null_mut::<c_void>(),
+        ));
+    }
+    let previous = match LOGGER.lock() {
+        Ok(mut logger) => logger.replace(Box::new(callback)),
+        Err(error) => error.into_inner().replace(Box::new(callback)),
+    };
+    drop(previous);
+    Ok(())
+}
+
+extern "C" fn log_callback(_: *mut c_void, code: c_int, message: *const c_char) {
+    // Messages logged from within the callback itself are skipped.
+    if LOGGING.with(|logging| logging.replace(true)) {
+        return;
+    }
+    let mut logger = match LOGGER.lock() {
+        Ok(logger) => logger,
+        Err(error) => error.into_inner(),
+    };
+    if let Some(callback) = logger.as_mut() {
+        let _ = catch_unwind(AssertUnwindSafe(|| unsafe {
+            let message = c_str_to_str!(message).unwrap_or_default();
+            let message = match tag(CONNECTION.with(Cell::get)) {
+                Some(tag) => Cow::Owned(format!("[{tag}] {message}")),
+                _ => Cow::Borrowed(message),
+            };
+            callback(code as isize, &message);
+        }));
+    }
+    drop(logger);
+    LOGGING.with(|logging| logging.set(false));
+}
+
+pub fn register_tag(raw: *mut ffi::sqlite3, tag: &Tag) {
+    let mut tags = TAGS.lock().unwrap();
+    tags.get_or_insert_with(HashMap::new)
+        .insert(raw as usize, tag.clone());
+}
+
+pub fn unregister_tag(raw: *mut ffi::sqlite3, tag: &Tag) {
+    let mut tags = TAGS.lock().unwrap();
+    if let Some(tags) = tags.as_mut() {
+        // A borrowed connection shares the handle and should not unregister the label of the
+        // owner.
+        if tags
+            .get(&(raw as usize))
+            .is_some_and(|other| Arc::ptr_eq(other, tag))
+        {
+            tags.remove(&(raw as usize));
+        }
+    }
+}
+
+pub fn tag(raw: *mut ffi::sqlite3) -> Option<String> {
+    if raw.is_null() {
+        return None;
+    }
+    let tag = TAGS.lock().unwrap().as_ref()?.get(&(raw as usize))?.clone();
+    let tag = tag.lock().unwrap().clone();
+    tag
+}
+
+// Attribute the messages logged while a callback runs to a connection.
+pub fn scope<F, T>(raw: *mut ffi::sqlite3, callback: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let previous = CONNECTION.with(|connection| connection.replace(raw));
+    let result = callback();
+    CONNECTION.with(|connection| connection.set(previous));
+    result
+}
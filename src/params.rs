@@ -0,0 +1,104 @@
+use crate::error::Result;
+use crate::statement::{Bindable, BindableWithIndex, Statement};
+use crate::value::Value;
+
+/// A set of values for named parameters.
+///
+/// The set can be reused across statements. Binding skips the parameters that the statement does
+/// not have, so that a single set can serve several statements that each use some of the values.
+///
+/// # Examples
+///
+/// ```
+/// use sqlite::Params;
+///
+/// let connection = sqlite::open(":memory:")?;
+/// connection.execute("CREATE TABLE users (id INTEGER, name TEXT)")?;
+/// let params = Params::new().set(":id", 1).set(":name", "Bob");
+/// let mut statement = connection.prepare("INSERT INTO users VALUES (:id, :name)")?;
+/// statement.bind(&params)?;
+/// statement.next()?;
+/// let mut statement = connection.prepare("SELECT name FROM users WHERE id = :id")?;
+/// statement.bind(&params)?;
+/// statement.next()?;
+/// assert_eq!(statement.read::<String, _>(0)?, "Bob");
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Params {
+    values: Vec<(String, Value)>,
+}
+
+impl Params {
+    /// Create an empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of a parameter, replacing the previous one if any.
+    ///
+    /// The name includes the prefix, as in `:id`.
+    pub fn set<T: Into<String>, U: Into<Value>>(mut self, name: T, value: U) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Set the value of a parameter in place, replacing the previous one if any.
+    pub fn insert<T: Into<String>, U: Into<Value>>(&mut self, name: T, value: U) {
+        let name = name.into();
+        let value = value.into();
+        match self.values.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, current)) => *current = value,
+            _ => self.values.push((name, value)),
+        }
+    }
+
+    /// Return the value of a parameter.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Iterate over the parameters and their values in the order of insertion.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl Bindable for &Params {
+    fn bind(self, statement: &mut Statement) -> Result<()> {
+        for (name, value) in self.values.iter() {
+            if let Some(index) = statement.parameter_index(name)? {
+                value.bind(statement, index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bindable for Params {
+    #[inline]
+    fn bind(self, statement: &mut Statement) -> Result<()> {
+        (&self).bind(statement)
+    }
+}
+
+impl<T, U> FromIterator<(T, U)> for Params
+where
+    T: Into<String>,
+    U: Into<Value>,
+{
+    fn from_iter<I: IntoIterator<Item = (T, U)>>(iterator: I) -> Self {
+        let mut params = Params::new();
+        for (name, value) in iterator {
+            params.insert(name, value);
+        }
+        params
+    }
+}
@@ -0,0 +1,344 @@
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::statement::{ReadableWithIndex, State};
+use crate::value::Value;
+
+/// Information about a table or view.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TableInfo {
+    schema: String,
+    name: String,
+    kind: TableKind,
+    column_count: usize,
+    without_rowid: bool,
+    strict: bool,
+}
+
+/// Information about an index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexInfo {
+    name: String,
+    table: String,
+    columns: Vec<Option<String>>,
+    unique: bool,
+    partial: bool,
+    sql: Option<String>,
+}
+
+/// Information about a view.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ViewInfo {
+    name: String,
+    sql: String,
+}
+
+/// Information about a trigger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TriggerInfo {
+    name: String,
+    table: String,
+    sql: String,
+}
+
+/// A kind of table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TableKind {
+    /// An ordinary table.
+    Table,
+    /// A view.
+    View,
+    /// A virtual table.
+    Virtual,
+    /// A shadow table of a virtual table.
+    Shadow,
+}
+
+impl TableInfo {
+    /// Return the schema name.
+    #[inline]
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Return the table name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the kind.
+    #[inline]
+    pub fn kind(&self) -> TableKind {
+        self.kind
+    }
+
+    /// Return the number of columns, including hidden and generated ones.
+    #[inline]
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    /// Check if the table is a `WITHOUT ROWID` table.
+    #[inline]
+    pub fn is_without_rowid(&self) -> bool {
+        self.without_rowid
+    }
+
+    /// Check if the table is a `STRICT` table.
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+}
+
+impl IndexInfo {
+    /// Return the index name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the name of the indexed table.
+    #[inline]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Return the indexed columns in order.
+    ///
+    /// Key parts that are expressions rather than columns are `None`; their definitions are
+    /// available via `sql`.
+    #[inline]
+    pub fn columns(&self) -> &[Option<String>] {
+        &self.columns
+    }
+
+    /// Check if the index is a `UNIQUE` one.
+    #[inline]
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Check if the index is a partial one, that is, one with a `WHERE` clause.
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Check if the index contains expressions.
+    #[inline]
+    pub fn has_expressions(&self) -> bool {
+        self.columns.iter().any(Option::is_none)
+    }
+
+    /// Return the `CREATE INDEX` statement.
+    ///
+    /// The statement is `None` for the indexes created implicitly by `UNIQUE` and `PRIMARY KEY`
+    /// constraints.
+    #[inline]
+    pub fn sql(&self) -> Option<&str> {
+        self.sql.as_deref()
+    }
+}
+
+impl ViewInfo {
+    /// Return the view name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the `CREATE VIEW` statement.
+    #[inline]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+}
+
+impl TriggerInfo {
+    /// Return the trigger name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the name of the table or view the trigger is attached to.
+    #[inline]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Return the `CREATE TRIGGER` statement.
+    #[inline]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+}
+
+impl TableKind {
+    fn parse(value: &str) -> Self {
+        match value {
+            "view" => TableKind::View,
+            "virtual" => TableKind::Virtual,
+            "shadow" => TableKind::Shadow,
+            _ => TableKind::Table,
+        }
+    }
+}
+
+pub fn table_list(connection: &Connection) -> Result<Vec<TableInfo>> {
+    if crate::version() >= 3037000 {
+        return table_list_native(connection);
+    }
+    let mut tables = Vec::new();
+    for schema in connection.schema_names() {
+        let quoted = crate::transaction::quote(&schema);
+        let mut statement = connection.prepare(format!(
+            "SELECT name, type, sql FROM {quoted}.sqlite_master \
+             WHERE type IN ('table', 'view') ORDER BY name"
+        ))?;
+        while let State::Row = statement.next()? {
+            let name = statement.read::<String, _>(0)?;
+            let sql = statement
+                .read::<Option<String>, _>(2)?
+                .unwrap_or_default()
+                .to_ascii_uppercase();
+            let kind = if statement.read::<String, _>(1)? == "view" {
+                TableKind::View
+            } else if sql.starts_with("CREATE VIRTUAL TABLE") {
+                TableKind::Virtual
+            } else {
+                TableKind::Table
+            };
+            let mut count = connection.prepare("SELECT count(*) FROM pragma_table_info(?, ?)")?;
+            count.bind((1, name.as_str()))?;
+            count.bind((2, schema.as_str()))?;
+            count.next()?;
+            tables.push(TableInfo {
+                schema: schema.clone(),
+                column_count: count.read::<i64, _>(0)? as usize,
+                name,
+                kind,
+                without_rowid: sql.contains("WITHOUT ROWID"),
+                strict: false,
+            });
+        }
+    }
+    Ok(tables)
+}
+
+fn table_list_native(connection: &Connection) -> Result<Vec<TableInfo>> {
+    let mut tables = Vec::new();
+    let mut statement = connection.prepare("PRAGMA table_list")?;
+    while let State::Row = statement.next()? {
+        tables.push(TableInfo {
+            schema: statement.read("schema")?,
+            name: statement.read("name")?,
+            kind: TableKind::parse(&statement.read::<String, _>("type")?),
+            column_count: statement.read::<i64, _>("ncol")? as usize,
+            without_rowid: statement.read::<i64, _>("wr")? != 0,
+            strict: statement.read::<i64, _>("strict")? != 0,
+        });
+    }
+    Ok(tables)
+}
+
+pub fn indexes(connection: &Connection, table: &str) -> Result<Vec<IndexInfo>> {
+    let mut indexes = Vec::new();
+    let mut statement = connection.prepare(
+        "SELECT list.name, list.\"unique\", list.partial, master.sql \
+         FROM pragma_index_list(?) AS list \
+         LEFT JOIN sqlite_master AS master ON master.type = 'index' AND master.name = list.name \
+         ORDER BY list.name",
+    )?;
+    statement.bind((1, table))?;
+    let mut columns = connection.prepare("SELECT name FROM pragma_index_info(?) ORDER BY seqno")?;
+    while let State::Row = statement.next()? {
+        let name = statement.read::<String, _>(0)?;
+        columns.reset()?;
+        columns.bind((1, name.as_str()))?;
+        let mut names = Vec::new();
+        while let State::Row = columns.next()? {
+            names.push(columns.read(0)?);
+        }
+        indexes.push(IndexInfo {
+            name,
+            table: table.to_string(),
+            columns: names,
+            unique: statement.read::<i64, _>(1)? != 0,
+            partial: statement.read::<i64, _>(2)? != 0,
+            sql: statement.read(3)?,
+        });
+    }
+    Ok(indexes)
+}
+
+pub fn views(connection: &Connection) -> Result<Vec<ViewInfo>> {
+    let mut views = Vec::new();
+    let mut statement = connection
+        .prepare("SELECT name, sql FROM sqlite_master WHERE type = 'view' ORDER BY name")?;
+    while let State::Row = statement.next()? {
+        views.push(ViewInfo {
+            name: statement.read(0)?,
+            sql: statement.read(1)?,
+        });
+    }
+    Ok(views)
+}
+
+pub fn triggers(connection: &Connection, table: &str) -> Result<Vec<TriggerInfo>> {
+    let mut triggers = Vec::new();
+    let mut statement = connection.prepare(
+        "SELECT name, tbl_name, sql FROM sqlite_master \
+         WHERE type = 'trigger' AND tbl_name = ? ORDER BY name",
+    )?;
+    statement.bind((1, table))?;
+    while let State::Row = statement.next()? {
+        triggers.push(TriggerInfo {
+            name: statement.read(0)?,
+            table: statement.read(1)?,
+            sql: statement.read(2)?,
+        });
+    }
+    Ok(triggers)
+}
+
+pub fn query<T: ReadableWithIndex>(connection: &Connection, name: &str) -> Result<T> {
+    let mut statement = connection.prepare(format!("PRAGMA {}", qualify(name)))?;
+    if let State::Done = statement.next()? {
+        raise!("the pragma has produced no value");
+    }
+    statement.read(0)
+}
+
+pub fn query_list<T: ReadableWithIndex>(connection: &Connection, name: &str) -> Result<Vec<T>> {
+    let mut values = Vec::new();
+    let mut statement = connection.prepare(format!("PRAGMA {}", qualify(name)))?;
+    while let State::Row = statement.next()? {
+        values.push(statement.read(0)?);
+    }
+    Ok(values)
+}
+
+pub fn set(connection: &Connection, name: &str, value: Value) -> Result<()> {
+    let value = match value {
+        Value::Integer(value) => value.to_string(),
+        Value::Float(value) if value.is_finite() => value.to_string(),
+        Value::String(value) => format!("'{}'", value.replace('\'', "''")),
+        _ => raise!("the pragma value should be an integer, a finite float, or a string"),
+    };
+    let mut statement = connection.prepare(format!("PRAGMA {} = {value}", qualify(name)))?;
+    while let State::Row = statement.next()? {}
+    Ok(())
+}
+
+fn qualify(name: &str) -> String {
+    match name.split_once('.') {
+        Some((schema, name)) => format!(
+            "{}.{}",
+            crate::transaction::quote(schema),
+            crate::transaction::quote(name),
+        ),
+        _ => crate::transaction::quote(name),
+    }
+}
@@ -0,0 +1,315 @@
+use core::ffi::{c_int, c_uint, c_void};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::hook::Hook;
+use crate::redaction::Redaction;
+use crate::statement::State;
+use crate::transaction::quote;
+
+/// A recorder of the statements executed by a connection.
+///
+/// The recorder stops when dropped or finished.
+pub struct Recorder<'l> {
+    hook: Hook<'l>,
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+/// A sequence of recorded statements.
+///
+/// A session can be written to and read from a file, saved to and loaded from a table, and
+/// replayed against another database, for instance, for catching performance regressions.
+///
+/// # Examples
+///
+/// ```
+/// let connection = sqlite::open(":memory:")?;
+/// let recorder = connection.record();
+/// connection.execute("CREATE TABLE users (name TEXT)")?;
+/// let mut statement = connection.prepare("INSERT INTO users VALUES (?)")?;
+/// statement.bind((1, "Alice"))?;
+/// statement.next()?;
+/// drop(statement);
+/// let session = recorder.finish();
+/// assert_eq!(session.records()[1].sql(), "INSERT INTO users VALUES ('Alice')");
+/// assert_eq!(session.records()[1].changes(), 1);
+///
+/// let other = sqlite::open(":memory:")?;
+/// let replayed = session.replay(&other)?;
+/// assert_eq!(replayed.records().len(), 2);
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    records: Vec<Record>,
+}
+
+/// A recorded statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    sql: String,
+    duration: Duration,
+    changes: usize,
+}
+
+pub struct Recording {
+    records: Arc<Mutex<Vec<Record>>>,
+    redaction: Option<Arc<Redaction>>,
+    // The total number of changes when each running statement started.
+    starts: Mutex<HashMap<usize, i64>>,
+}
+
+impl Recorder<'_> {
+    /// Return the statements recorded so far.
+    pub fn session(&self) -> Session {
+        Session {
+            records: self.records.lock().unwrap().clone(),
+        }
+    }
+
+    /// Stop recording and return the recorded statements.
+    pub fn finish(self) -> Session {
+        let Recorder { hook, records } = self;
+        drop(hook);
+        let records = std::mem::take(&mut *records.lock().unwrap());
+        Session { records }
+    }
+}
+
+impl Session {
+    /// Create an empty session.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the recorded statements.
+    #[inline]
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Execute the statements against a connection and record them anew.
+    ///
+    /// The execution stops at the first error.
+    pub fn replay(&self, connection: &Connection) -> Result<Session> {
+        let mut records = Vec::with_capacity(self.records.len());
+        for record in self.records.iter() {
+            let start = Instant::now();
+            let mut statement = connection.prepare(&record.sql)?;
+            let before = unsafe { ffi::sqlite3_total_changes64(connection.as_raw()) };
+            while let State::Row = statement.next()? {}
+            let duration = start.elapsed();
+            let changes = unsafe { changes(connection.as_raw(), before) };
+            records.push(Record {
+                sql: record.sql.clone(),
+                duration,
+                changes,
+            });
+        }
+        Ok(Session { records })
+    }
+
+    /// Write the statements, one per line.
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        for record in self.records.iter() {
+            let result = writeln!(
+                writer,
+                "{}\t{}\t{}",
+                record.duration.as_nanos(),
+                record.changes,
+                escape(&record.sql),
+            );
+            if let Err(error) = result {
+                raise!("failed to write a record ({error})");
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the statements written via `write`.
+    pub fn read<T: BufRead>(reader: T) -> Result<Session> {
+        let mut records = Vec::new();
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => raise!("failed to read a record ({error})"),
+            };
+            let mut fields = line.splitn(3, '\t');
+            let (Some(duration), Some(changes), Some(sql)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                raise!("the record is malformed ({line})");
+            };
+            let (Ok(duration), Ok(changes)) = (duration.parse(), changes.parse()) else {
+                raise!("the record is malformed ({line})");
+            };
+            records.push(Record {
+                sql: unescape(sql),
+                duration: Duration::from_nanos(duration),
+                changes,
+            });
+        }
+        Ok(Session { records })
+    }
+
+    /// Save the statements to a table, creating the table if needed.
+    ///
+    /// The table has columns `sql`, `duration` in nanoseconds, and `changes`.
+    pub fn save(&self, connection: &Connection, table: &str) -> Result<()> {
+        let table = quote(table);
+        let transaction = connection.transaction()?;
+        connection.execute(format!(
+            "CREATE TABLE IF NOT EXISTS {table} \
+             (sql TEXT NOT NULL, duration INTEGER NOT NULL, changes INTEGER NOT NULL)"
+        ))?;
+        let mut statement = connection.prepare(format!("INSERT INTO {table} VALUES (?, ?, ?)"))?;
+        for record in self.records.iter() {
+            statement.reset()?;
+            statement.bind((
+                record.sql.as_str(),
+                record.duration.as_nanos() as i64,
+                record.changes as i64,
+            ))?;
+            statement.next()?;
+        }
+        drop(statement);
+        transaction.commit()
+    }
+
+    /// Load the statements saved via `save`.
+    pub fn load(connection: &Connection, table: &str) -> Result<Session> {
+        let mut records = Vec::new();
+        let mut statement = connection.prepare(format!(
+            "SELECT sql, duration, changes FROM {} ORDER BY rowid",
+            quote(table),
+        ))?;
+        while let State::Row = statement.next()? {
+            records.push(Record {
+                sql: statement.read(0)?,
+                duration: Duration::from_nanos(statement.read::<i64, _>(1)? as u64),
+                changes: statement.read::<i64, _>(2)? as usize,
+            });
+        }
+        Ok(Session { records })
+    }
+}
+
+impl Record {
+    /// Return the SQL text with the bound parameters substituted.
+    #[inline]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Return the time the statement took.
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Return the number of rows modified by the statement, including those modified by triggers.
+    #[inline]
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+}
+
+pub fn new<'l>(hook: Hook<'l>, records: Arc<Mutex<Vec<Record>>>) -> Recorder<'l> {
+    Recorder { hook, records }
+}
+
+pub fn recording(redaction: Option<Arc<Redaction>>) -> (Recording, Arc<Mutex<Vec<Record>>>) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let recording = Recording {
+        records: records.clone(),
+        redaction,
+        starts: Mutex::new(HashMap::new()),
+    };
+    (recording, records)
+}
+
+pub extern "C" fn trace_callback(
+    event: c_uint,
+    recording: *mut c_void,
+    pointer: *mut c_void,
+    extra: *mut c_void,
+) -> c_int {
+    let _ = catch_unwind(AssertUnwindSafe(|| unsafe {
+        let recording = &*(recording as *const Recording);
+        let raw = pointer as *mut ffi::sqlite3_stmt;
+        let connection = ffi::sqlite3_db_handle(raw);
+        let mut starts = recording.starts.lock().unwrap();
+        if event == ffi::SQLITE_TRACE_STMT as c_uint {
+            // The event is also triggered for each trigger the statement fires.
+            starts
+                .entry(raw as usize)
+                .or_insert_with(|| ffi::sqlite3_total_changes64(connection));
+            return;
+        }
+        let Some(before) = starts.remove(&(raw as usize)) else {
+            return;
+        };
+        drop(starts);
+        let expanded = ffi::sqlite3_expanded_sql(raw);
+        if expanded.is_null() {
+            return;
+        }
+        let mut sql = c_str_to_string!(expanded);
+        ffi::sqlite3_free(expanded as *mut _);
+        if let Some(ref redaction) = recording.redaction {
+            let original = c_str_to_str!(ffi::sqlite3_sql(raw)).unwrap_or_default();
+            sql = crate::redaction::redact(redaction, original, &sql)
+                .unwrap_or_else(|| original.to_string());
+        }
+        let record = Record {
+            sql,
+            duration: Duration::from_nanos(*(extra as *const i64) as u64),
+            changes: changes(connection, before),
+        };
+        recording.records.lock().unwrap().push(record);
+    }));
+    0
+}
+
+unsafe fn changes(connection: *mut ffi::sqlite3, before: i64) -> usize {
+    (ffi::sqlite3_total_changes64(connection) - before) as usize
+}
+
+fn escape(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    for character in sql.chars() {
+        match character {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(character),
+        }
+    }
+    result
+}
+
+fn unescape(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    let mut characters = sql.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            result.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(character) => result.push(character),
+            _ => result.push('\\'),
+        }
+    }
+    result
+}
@@ -0,0 +1,196 @@
+/// A policy for redacting bound values in SQL text meant for logging.
+///
+/// Values are matched by the names of the parameters they are bound to, with the prefix
+/// character removed, against column name patterns, so that `:email` and `@email` both match the
+/// pattern `email`. Patterns are case insensitive and may contain `*` to match any sequence of
+/// characters. Parameters without names, such as `?` and `?1`, are matched only if enabled via
+/// `with_positional`. Redacted values are replaced with the parameters themselves.
+///
+/// # Examples
+///
+/// ```
+/// use sqlite::{ErrorContext, Redaction, Value};
+///
+/// let mut connection = sqlite::open(":memory:")?;
+/// connection.set_error_context(ErrorContext::ExpandedSql);
+/// connection.set_redaction(Redaction::new().with_column("email").with_column("*_token"));
+/// connection.execute("CREATE TABLE users (name TEXT, email TEXT NOT NULL)")?;
+/// let mut statement = connection.prepare("INSERT INTO users VALUES (:name, :email)")?;
+/// statement.bind::<&[(_, Value)]>(&[(":name", "Alice".into()), (":email", Value::Null)][..])?;
+/// let error = statement.next().unwrap_err();
+/// assert_eq!(error.sql().unwrap(), "INSERT INTO users VALUES ('Alice', :email)");
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    patterns: Vec<String>,
+    positional: bool,
+}
+
+impl Redaction {
+    /// Create a policy redacting nothing.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact the values bound to parameters named after columns matching a pattern.
+    pub fn with_column<T: Into<String>>(mut self, pattern: T) -> Self {
+        self.patterns.push(pattern.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Redact the values bound to parameters without names.
+    pub fn with_positional(mut self) -> Self {
+        self.positional = true;
+        self
+    }
+
+    /// Check if the value bound to a parameter is redacted.
+    ///
+    /// The parameter is given as it appears in the SQL text, such as `:email` or `?1`.
+    pub fn is_redacted(&self, parameter: &str) -> bool {
+        match parameter.as_bytes().first() {
+            None | Some(b'?') => self.positional,
+            _ => {
+                let name = parameter[1..].to_ascii_lowercase();
+                self.patterns
+                    .iter()
+                    .any(|pattern| glob(pattern.as_bytes(), name.as_bytes()))
+            }
+        }
+    }
+}
+
+pub fn redact(redaction: &Redaction, sql: &str, expanded: &str) -> Option<String> {
+    let mut result = String::with_capacity(expanded.len());
+    let mut expanded = expanded;
+    let mut sql = sql;
+    while let Some((start, end)) = next_parameter(sql) {
+        expanded = expanded.strip_prefix(&sql[..start])?;
+        result.push_str(&sql[..start]);
+        let length = literal_length(expanded)?;
+        let parameter = &sql[start..end];
+        if redaction.is_redacted(parameter) {
+            result.push_str(parameter);
+        } else {
+            result.push_str(&expanded[..length]);
+        }
+        expanded = &expanded[length..];
+        sql = &sql[end..];
+    }
+    if expanded != sql {
+        return None;
+    }
+    result.push_str(sql);
+    Some(result)
+}
+
+fn glob(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|start| glob(rest, &name[start..])),
+        Some((first, rest)) => name.first() == Some(first) && glob(rest, &name[1..]),
+    }
+}
+
+fn next_parameter(sql: &str) -> Option<(usize, usize)> {
+    let bytes = sql.as_bytes();
+    let mut position = 0;
+    while position < bytes.len() {
+        let start = position;
+        position += 1;
+        match bytes[start] {
+            quote @ (b'\'' | b'"' | b'`') => loop {
+                match bytes.get(position) {
+                    None => return None,
+                    Some(&byte) if byte == quote => {
+                        position += 1;
+                        if bytes.get(position) != Some(&quote) {
+                            break;
+                        }
+                        position += 1;
+                    }
+                    _ => position += 1,
+                }
+            },
+            b'[' => position += sql[position..].find(']')? + 1,
+            b'-' if bytes.get(position) == Some(&b'-') => {
+                position += sql[position..].find('\n').unwrap_or(sql.len() - position);
+            }
+            b'/' if bytes.get(position) == Some(&b'*') => {
+                position += sql[position + 1..].find("*/")? + 3;
+            }
+            b'?' => {
+                position += count(&bytes[position..], |byte| byte.is_ascii_digit());
+                return Some((start, position));
+            }
+            b':' | b'@' | b'$' => {
+                let length = count(&bytes[position..], is_identifier);
+                if length > 0 {
+                    return Some((start, position + length));
+                }
+            }
+            byte if is_identifier(byte) => {
+                position += count(&bytes[position..], is_identifier);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn literal_length(expanded: &str) -> Option<usize> {
+    let bytes = expanded.as_bytes();
+    if expanded.starts_with("NULL") {
+        return Some(4);
+    }
+    if let Some(rest) = expanded.strip_prefix("zeroblob(") {
+        return Some(9 + rest.find(')')? + 1);
+    }
+    let (prefix, quoted) = match bytes.first()? {
+        b'x' | b'X' => (1, &bytes[1..]),
+        _ => (0, bytes),
+    };
+    if quoted.first() == Some(&b'\'') {
+        let mut position = 1;
+        loop {
+            match quoted.get(position)? {
+                b'\'' if quoted.get(position + 1) == Some(&b'\'') => position += 2,
+                b'\'' => return Some(prefix + position + 1),
+                _ => position += 1,
+            }
+        }
+    }
+    let mut position = usize::from(bytes[0] == b'-');
+    for special in ["Inf", "NaN"] {
+        if expanded[position..].starts_with(special) {
+            return Some(position + special.len());
+        }
+    }
+    let digits = count(&bytes[position..], |byte| {
+        byte.is_ascii_digit() || byte == b'.'
+    });
+    if digits == 0 {
+        return None;
+    }
+    position += digits;
+    if matches!(bytes.get(position), Some(b'e' | b'E')) {
+        position += 1;
+        if matches!(bytes.get(position), Some(b'+' | b'-')) {
+            position += 1;
+        }
+        position += count(&bytes[position..], |byte| byte.is_ascii_digit());
+    }
+    Some(position)
+}
+
+#[inline]
+fn is_identifier(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+#[inline]
+fn count<F: Fn(u8) -> bool>(bytes: &[u8], predicate: F) -> usize {
+    bytes.iter().take_while(|&&byte| predicate(byte)).count()
+}
@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::connection::{Connection, OpenFlags};
+use crate::error::{Error, Result};
+
+/// A scheduler of database snapshots.
+///
+/// Each snapshot is a standalone copy of the main database produced by `VACUUM INTO`, which
+/// yields a compacted file and does not block writers of other connections for the duration of
+/// the copy. Only the most recent snapshots are kept.
+#[derive(Clone, Debug)]
+pub struct SnapshotScheduler {
+    directory: PathBuf,
+    prefix: String,
+    keep: usize,
+    #[cfg(feature = "zstd")]
+    level: Option<i32>,
+}
+
+/// A handle to snapshots taken in the background.
+///
+/// The background thread is stopped when the handle is dropped.
+pub struct SnapshotHandle {
+    state: Arc<(Mutex<State>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct State {
+    stopped: bool,
+    error: Option<Error>,
+}
+
+impl SnapshotScheduler {
+    /// Create a scheduler placing snapshots into a directory.
+    ///
+    /// By default, the snapshots are named `snapshot-<timestamp>.sqlite3`, and the seven most
+    /// recent ones are kept.
+    pub fn new<T: AsRef<Path>>(directory: T) -> Self {
+        SnapshotScheduler {
+            directory: directory.as_ref().to_path_buf(),
+            prefix: "snapshot".into(),
+            keep: 7,
+            #[cfg(feature = "zstd")]
+            level: None,
+        }
+    }
+
+    /// Set the number of snapshots to keep.
+    pub fn with_keep(mut self, value: usize) -> Self {
+        self.keep = value.max(1);
+        self
+    }
+
+    /// Set the prefix of file names.
+    pub fn with_prefix<T: Into<String>>(mut self, value: T) -> Self {
+        self.prefix = value.into();
+        self
+    }
+
+    /// Compress snapshots using Zstandard at a given level.
+    #[cfg(feature = "zstd")]
+    pub fn with_compression(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Take a snapshot of the main database and remove the snapshots that are no longer kept.
+    ///
+    /// The function returns the path to the new snapshot.
+    pub fn snapshot(&self, connection: &Connection) -> Result<PathBuf> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0);
+        let counter = COUNTER.fetch_add(1, Ordering::Relaxed) % 1000;
+        let name = format!("{}-{timestamp:016}{counter:03}.sqlite3", self.prefix);
+        let path = self.directory.join(&name);
+        let mut statement = connection.prepare("VACUUM INTO ?")?;
+        match path.to_str() {
+            Some(value) => statement.bind((1, value))?,
+            _ => raise!("failed to process a path"),
+        }
+        statement.next()?;
+        #[cfg(feature = "zstd")]
+        let path = match self.level {
+            Some(level) => compress(path, level)?,
+            _ => path,
+        };
+        let snapshots = self.snapshots()?;
+        if snapshots.len() > self.keep {
+            for path in &snapshots[..snapshots.len() - self.keep] {
+                std::fs::remove_file(path).map_err(from_io)?;
+            }
+        }
+        Ok(path)
+    }
+
+    /// Return the paths to the existing snapshots ordered from oldest to newest.
+    pub fn snapshots(&self) -> Result<Vec<PathBuf>> {
+        let prefix = format!("{}-", self.prefix);
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.directory).map_err(from_io)? {
+            let path = entry.map_err(from_io)?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                _ => continue,
+            };
+            let suffix = match name.strip_prefix(&prefix) {
+                Some(suffix) => suffix,
+                _ => continue,
+            };
+            let stem = suffix
+                .strip_suffix(".sqlite3")
+                .or_else(|| suffix.strip_suffix(".sqlite3.zst"));
+            if stem.is_some_and(|stem| stem.bytes().all(|byte| byte.is_ascii_digit())) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Take snapshots of a database periodically on a background thread.
+    ///
+    /// The thread opens its own read-only connection to the database and takes the first
+    /// snapshot after one interval has elapsed.
+    pub fn spawn<T: AsRef<Path>>(self, path: T, interval: Duration) -> Result<SnapshotHandle> {
+        let connection =
+            Connection::open_with_flags(path, OpenFlags::new().with_read_only().with_no_mutex())?;
+        let state = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        let thread = {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                let (lock, condvar) = &*state;
+                let mut guard = lock.lock().unwrap();
+                loop {
+                    guard = condvar.wait_timeout(guard, interval).unwrap().0;
+                    if guard.stopped {
+                        break;
+                    }
+                    if let Err(error) = self.snapshot(&connection) {
+                        guard.error = Some(error);
+                    }
+                }
+            })
+        };
+        Ok(SnapshotHandle {
+            state,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl SnapshotHandle {
+    /// Stop taking snapshots and return the last error if any.
+    pub fn stop(mut self) -> Result<()> {
+        self.shutdown();
+        match self.state.0.lock().unwrap().error.take() {
+            Some(error) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.state.0.lock().unwrap().stopped = true;
+            self.state.1.notify_all();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for SnapshotHandle {
+    #[inline]
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn compress(path: PathBuf, level: i32) -> Result<PathBuf> {
+    let mut other = path.clone().into_os_string();
+    other.push(".zst");
+    let other = PathBuf::from(other);
+    let source = std::fs::File::open(&path).map_err(from_io)?;
+    let destination = std::fs::File::create(&other).map_err(from_io)?;
+    zstd::stream::copy_encode(source, destination, level).map_err(from_io)?;
+    std::fs::remove_file(&path).map_err(from_io)?;
+    Ok(other)
+}
+
+fn from_io(error: std::io::Error) -> Error {
+    Error::new(None, Some(error.to_string()))
+}
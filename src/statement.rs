@@ -1,27 +1,24 @@
 use core::ffi::{c_double, c_int};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::connection::BusyStatistics;
 use crate::cursor::{Cursor, CursorWithOwnership, Row};
-use crate::error::Result;
+use crate::error::{Error, ErrorContext, Result};
+use crate::redaction::Redaction;
 use crate::value::{Type, Value};
 
-// https://sqlite.org/c3ref/c_static.html
-macro_rules! transient(
-    () => (
-        std::mem::transmute::<
-            *const std::ffi::c_void,
-            std::option::Option<unsafe extern "C" fn(*mut std::ffi::c_void)>
-        >(!0 as *const core::ffi::c_void)
-    );
-);
-
 /// A prepared statement.
 pub struct Statement<'l> {
     raw: (*mut ffi::sqlite3_stmt, *mut ffi::sqlite3),
     column_names: Vec<String>,
     column_mapping: Rc<HashMap<String, usize>>,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+    busy: BusyStatistics,
     phantom: PhantomData<(ffi::sqlite3_stmt, &'l ffi::sqlite3)>,
 }
 
@@ -63,6 +60,10 @@ pub trait ReadableWithIndex: Sized {
     fn read<T: ColumnIndex>(_: &Statement, _: T) -> Result<Self>;
 }
 
+/// Flags for preparing a statement.
+#[derive(Clone, Copy, Debug)]
+pub struct PrepareFlags(c_int);
+
 /// The state of a prepared statement.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum State {
@@ -72,10 +73,28 @@ pub enum State {
     Done,
 }
 
+/// A blob filled with zeros.
+///
+/// Binding the type allocates a blob of a given size in the database without materializing it in
+/// memory, so that it can be filled in later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zeroblob(pub usize);
+
+/// A value in its original representation.
+///
+/// The value is a protected copy of what SQLite holds internally, and binding it preserves the
+/// exact type and subtype without any conversion on the Rust side.
+pub struct RawValue {
+    raw: *mut ffi::sqlite3_value,
+}
+
 impl<'l> Statement<'l> {
     /// Bind values to parameters.
     ///
-    /// In case of integer indices, the first parameter has index 1.
+    /// In case of integer indices, the first parameter has index 1. A pair is interpreted as an
+    /// index and a value, and other tuples of up to 16 values are bound to the parameters at
+    /// indices starting from 1. Names can be given as `&str`, `String`, `Box<str>`, `Cow<str>`,
+    /// or references to them.
     ///
     /// # Examples
     ///
@@ -99,6 +118,15 @@ impl<'l> Statement<'l> {
     ///
     /// ```
     /// # let connection = sqlite::open(":memory:").unwrap();
+    /// # connection.execute("CREATE TABLE users (id INTEGER, name STRING, age REAL)");
+    /// let query = "SELECT * FROM users WHERE name = ? AND id = ? AND age > ?";
+    /// let mut statement = connection.prepare(query)?;
+    /// statement.bind(("Bob", 42, 3.14))?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    ///
+    /// ```
+    /// # let connection = sqlite::open(":memory:").unwrap();
     /// # connection.execute("CREATE TABLE users (id INTEGER, name STRING)");
     /// let query = "SELECT * FROM users WHERE name = :name";
     /// let mut statement = connection.prepare(query)?;
@@ -160,25 +188,125 @@ impl<'l> Statement<'l> {
         Ok(())
     }
 
+    /// Evaluate the statement entirely for each set of parameters.
+    ///
+    /// The statement is reset and its bindings are cleared before each set is bound. The function
+    /// returns the number of evaluations and stops at the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sqlite::Value;
+    /// # let connection = sqlite::open(":memory:").unwrap();
+    /// # connection.execute("CREATE TABLE users (id INTEGER, name STRING)");
+    /// let query = "INSERT INTO users VALUES (?, ?)";
+    /// let mut statement = connection.prepare(query)?;
+    /// let users: [[Value; 2]; 2] = [[1.into(), "Alice".into()], [2.into(), "Bob".into()]];
+    /// let count = statement.execute_batch(users.iter().map(|user| &user[..]))?;
+    /// assert_eq!(count, 2);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn execute_batch<T, U>(&mut self, values: T) -> Result<usize>
+    where
+        T: IntoIterator<Item = U>,
+        U: Bindable,
+    {
+        let mut count = 0;
+        let result = (|| {
+            for value in values {
+                self.reset()?;
+                unsafe { ffi::sqlite3_clear_bindings(self.raw.0) };
+                self.bind(value)?;
+                while self.next()? == State::Row {}
+                count += 1;
+            }
+            Ok(count)
+        })();
+        self.reset()?;
+        result
+    }
+
+    /// Evaluate the statement entirely for each set of parameters within a savepoint.
+    ///
+    /// The changes are rolled back if any of the evaluations fails. Other than that, the function
+    /// behaves as `execute_batch`, and wrapping many evaluations in a single transaction is
+    /// considerably faster than committing each of them separately.
+    pub fn execute_batch_in_transaction<T, U>(&mut self, values: T) -> Result<usize>
+    where
+        T: IntoIterator<Item = U>,
+        U: Bindable,
+    {
+        execute(self.raw.1, "SAVEPOINT execute_batch")?;
+        match self.execute_batch(values) {
+            Ok(count) => {
+                execute(self.raw.1, "RELEASE execute_batch")?;
+                Ok(count)
+            }
+            Err(error) => {
+                let _ = execute(
+                    self.raw.1,
+                    "ROLLBACK TO execute_batch; RELEASE execute_batch",
+                );
+                Err(error)
+            }
+        }
+    }
+
     /// Create a cursor.
     #[inline]
     pub fn iter(&mut self) -> Cursor<'l, '_> {
         self.into()
     }
 
+    /// Create a cursor.
+    #[deprecated(note = "use `iter` instead")]
+    #[inline]
+    pub fn cursor(&mut self) -> Cursor<'l, '_> {
+        self.iter()
+    }
+
+    /// Convert into a cursor taking ownership of the statement.
+    #[deprecated(note = "use `into_iter` instead")]
+    #[inline]
+    pub fn into_cursor(self) -> CursorWithOwnership<'l> {
+        self.into_iter()
+    }
+
+    /// Bind a value to a named parameter.
+    #[deprecated(note = "use `bind((name, value))` instead")]
+    #[inline]
+    pub fn bind_by_name<T: BindableWithIndex>(&mut self, name: &str, value: T) -> Result<()> {
+        self.bind((name, value))
+    }
+
     /// Advance to the next state.
     ///
     /// The function should be called multiple times until `State::Done` is reached in order to
     /// evaluate the statement entirely.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<State> {
-        Ok(match unsafe { ffi::sqlite3_step(self.raw.0) } {
+        let raw = self.raw;
+        let busy = &mut self.busy;
+        let code = crate::log::scope(raw.1, || {
+            crate::connection::track_busy(busy, || unsafe { ffi::sqlite3_step(raw.0) })
+        });
+        Ok(match code {
             ffi::SQLITE_ROW => State::Row,
             ffi::SQLITE_DONE => State::Done,
-            code => error!(self.raw.1, code),
+            code => return Err(self.contextualize(crate::error::from_code(self.raw.1, code))),
         })
     }
 
+    /// Return the statistics about busy events that occurred while evaluating the statement.
+    ///
+    /// See `Connection::busy_statistics` for further details.
+    #[inline]
+    pub fn busy_statistics(&self) -> BusyStatistics {
+        let mut statistics = self.busy.clone();
+        statistics.tag = crate::log::tag(self.raw.1);
+        statistics
+    }
+
     /// Read a value from a column.
     ///
     /// In case of integer indices, the first column has index 0.
@@ -191,6 +319,20 @@ impl<'l> Statement<'l> {
         ReadableWithIndex::read(self, index)
     }
 
+    /// Read a value from a column in its original representation.
+    ///
+    /// In case of integer indices, the first column has index 0.
+    pub fn read_raw_value<T: ColumnIndex>(&self, index: T) -> Result<RawValue> {
+        unsafe {
+            let value = ffi::sqlite3_column_value(self.raw.0, index.index(self)? as c_int);
+            let raw = ffi::sqlite3_value_dup(value);
+            if raw.is_null() {
+                raise!("failed to allocate memory");
+            }
+            Ok(RawValue { raw })
+        }
+    }
+
     /// Return the number of columns.
     #[inline]
     pub fn column_count(&self) -> usize {
@@ -205,18 +347,115 @@ impl<'l> Statement<'l> {
 
     /// Return the name of a column.
     ///
-    /// In case of integer indices, the first column has index 0.
+    /// The names are cached when the statement is prepared. See `column_name_live` for
+    /// retrieving the current name. In case of integer indices, the first column has index 0.
     #[inline]
     pub fn column_name<T: ColumnIndex>(&self, index: T) -> Result<&str> {
         Ok(&self.column_names[index.index(self)?])
     }
 
+    /// Return the name of a column as currently reported by SQLite.
+    ///
+    /// Unlike `column_name`, the name reflects changes made after the statement was prepared,
+    /// such as the statement being prepared again due to a schema change or the setting of
+    /// `PRAGMA full_column_names`. The first column has index 0.
+    pub fn column_name_live(&self, index: usize) -> Result<String> {
+        unsafe {
+            if index >= ffi::sqlite3_column_count(self.raw.0) as usize {
+                raise!("the index is out of range ({})", index);
+            }
+            let name = ffi::sqlite3_column_name(self.raw.0, index as c_int);
+            if name.is_null() {
+                raise!("failed to allocate memory");
+            }
+            Ok(c_str_to_string!(name))
+        }
+    }
+
     /// Return column names.
+    ///
+    /// The names are cached when the statement is prepared.
     #[inline]
     pub fn column_names(&self) -> &[String] {
         &self.column_names
     }
 
+    /// Return the name of the database a column originates from.
+    ///
+    /// The name is a schema name, such as `main`, and it is available before taking a step. If
+    /// the column is an expression, `None` is returned. The linked library has to be compiled
+    /// with `SQLITE_ENABLE_COLUMN_METADATA`. In case of integer indices, the first column has
+    /// index 0.
+    #[cfg(feature = "column-metadata")]
+    pub fn column_database_name<T: ColumnIndex>(&self, index: T) -> Result<Option<String>> {
+        let index = index.index(self)? as c_int;
+        Ok(unsafe { c_str_to_option(ffi::sqlite3_column_database_name(self.raw.0, index)) })
+    }
+
+    /// Return the name of the table a column originates from.
+    ///
+    /// The name is the one of the underlying table even if the column is selected via a view or
+    /// a subquery. See `column_database_name` for further details.
+    #[cfg(feature = "column-metadata")]
+    pub fn column_table_name<T: ColumnIndex>(&self, index: T) -> Result<Option<String>> {
+        let index = index.index(self)? as c_int;
+        Ok(unsafe { c_str_to_option(ffi::sqlite3_column_table_name(self.raw.0, index)) })
+    }
+
+    /// Return the name a column has in the table it originates from.
+    ///
+    /// Unlike `column_name`, the name is not affected by `AS` clauses. See
+    /// `column_database_name` for further details.
+    #[cfg(feature = "column-metadata")]
+    pub fn column_origin_name<T: ColumnIndex>(&self, index: T) -> Result<Option<String>> {
+        let index = index.index(self)? as c_int;
+        Ok(unsafe { c_str_to_option(ffi::sqlite3_column_origin_name(self.raw.0, index)) })
+    }
+
+    /// Return the declared type of a column.
+    ///
+    /// The type is the one given in the definition of the table the column originates from, such
+    /// as `VARCHAR(255)`, and it is available before taking a step. If the column is an
+    /// expression or has no declared type, `None` is returned. In case of integer indices, the
+    /// first column has index 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// connection.execute("CREATE TABLE users (name VARCHAR(255), age)")?;
+    /// let statement = connection.prepare("SELECT name, age, 42 FROM users")?;
+    /// assert_eq!(statement.column_decltype(0)?.as_deref(), Some("VARCHAR(255)"));
+    /// assert_eq!(statement.column_decltype(1)?, None);
+    /// assert_eq!(statement.column_decltype(2)?, None);
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn column_decltype<T: ColumnIndex>(&self, index: T) -> Result<Option<String>> {
+        unsafe {
+            let value = ffi::sqlite3_column_decltype(self.raw.0, index.index(self)? as c_int);
+            if value.is_null() {
+                return Ok(None);
+            }
+            Ok(Some(c_str_to_string!(value)))
+        }
+    }
+
+    /// Return the type corresponding to the affinity of a column.
+    ///
+    /// The affinity is determined by the declared type of the column via `Type::from_decltype`,
+    /// and it is available before taking a step. If the column is an expression and has no
+    /// declared type, `None` is returned. In case of integer indices, the first column has index
+    /// 0.
+    pub fn column_affinity<T: ColumnIndex>(&self, index: T) -> Result<Option<Type>> {
+        unsafe {
+            let value = ffi::sqlite3_column_decltype(self.raw.0, index.index(self)? as c_int);
+            if value.is_null() {
+                return Ok(None);
+            }
+            Ok(c_str_to_str!(value).ok().map(Type::from_decltype))
+        }
+    }
+
     /// Return the type of a column.
     ///
     /// The type becomes available after taking a step. In case of integer indices, the first
@@ -257,6 +496,68 @@ impl<'l> Statement<'l> {
         }
     }
 
+    /// Return the SQL text used to prepare the statement.
+    #[inline]
+    pub fn sql(&self) -> &str {
+        unsafe {
+            let sql = ffi::sqlite3_sql(self.raw.0);
+            if sql.is_null() {
+                return "";
+            }
+            c_str_to_str!(sql).unwrap_or_default()
+        }
+    }
+
+    /// Return the SQL text with the bound parameters substituted.
+    ///
+    /// The values are redacted according to the policy set via `Connection::set_redaction` if
+    /// any; if the text cannot be redacted reliably, the unexpanded one is returned instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let connection = sqlite::open(":memory:")?;
+    /// let mut statement = connection.prepare("SELECT ?, ?, ?")?;
+    /// statement.bind((42, "Alice", 4.2))?;
+    /// assert_eq!(statement.sql(), "SELECT ?, ?, ?");
+    /// assert_eq!(statement.expanded_sql()?, "SELECT 42, 'Alice', 4.2");
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    pub fn expanded_sql(&self) -> Result<String> {
+        let expanded = unsafe {
+            let sql = ffi::sqlite3_expanded_sql(self.raw.0);
+            if sql.is_null() {
+                raise!("failed to allocate memory");
+            }
+            let expanded = c_str_to_string!(sql);
+            ffi::sqlite3_free(sql as *mut _);
+            expanded
+        };
+        Ok(match self.redaction {
+            Some(ref redaction) => {
+                let sql = self.sql();
+                crate::redaction::redact(redaction, sql, &expanded)
+                    .unwrap_or_else(|| sql.to_string())
+            }
+            _ => expanded,
+        })
+    }
+
+    /// Return the SQL text with literals replaced by parameters and whitespace normalized.
+    ///
+    /// The text is suitable for grouping statements that differ only in their values. The linked
+    /// library has to be compiled with `SQLITE_ENABLE_NORMALIZE`.
+    #[cfg(feature = "normalize")]
+    pub fn normalized_sql(&self) -> Result<String> {
+        unsafe {
+            let sql = sqlite3_normalized_sql(self.raw.0);
+            if sql.is_null() {
+                raise!("failed to allocate memory");
+            }
+            Ok(c_str_to_string!(sql))
+        }
+    }
+
     /// Reset the internal state.
     #[inline]
     pub fn reset(&mut self) -> Result<()> {
@@ -264,11 +565,77 @@ impl<'l> Statement<'l> {
         Ok(())
     }
 
-    #[doc(hidden)]
+    /// Create a prepared statement from a raw handle.
+    ///
+    /// The statement takes ownership of the handle and finalizes it when dropped.
+    ///
+    /// # Safety
+    ///
+    /// The handle has to be valid, it should not be used or finalized by anything else while the
+    /// statement is alive, and the connection it belongs to has to outlive the statement.
+    pub unsafe fn from_raw(raw: *mut ffi::sqlite3_stmt) -> Statement<'l> {
+        adopt(
+            raw,
+            ffi::sqlite3_db_handle(raw),
+            ErrorContext::Disabled,
+            None,
+        )
+    }
+
+    /// Return the raw handle.
+    ///
+    /// The handle stays owned by the statement and is valid as long as the statement is alive.
     #[inline]
     pub fn as_raw(&self) -> *mut ffi::sqlite3_stmt {
         self.raw.0
     }
+
+    /// Release the raw handle without finalizing it.
+    ///
+    /// The caller becomes responsible for finalizing the handle via `sqlite3_finalize`.
+    #[inline]
+    pub fn into_raw(mut self) -> *mut ffi::sqlite3_stmt {
+        std::mem::replace(&mut self.raw.0, std::ptr::null_mut())
+    }
+
+    fn contextualize(&self, mut error: Error) -> Error {
+        match self.error_context {
+            ErrorContext::Disabled => {}
+            ErrorContext::Sql => error.sql = Some(self.sql().to_string()),
+            ErrorContext::ExpandedSql => error.sql = self.expanded_sql().ok(),
+        }
+        error
+    }
+}
+
+impl PrepareFlags {
+    /// Create flags for preparing a statement.
+    #[inline]
+    pub fn new() -> Self {
+        PrepareFlags(0)
+    }
+
+    /// Hint that the statement will be retained for a long time and probably reused many times.
+    ///
+    /// The statement then avoids the lookaside memory allocator, which is meant for short-lived
+    /// objects and would otherwise be exhausted.
+    pub fn with_persistent(mut self) -> Self {
+        self.0 |= ffi::SQLITE_PREPARE_PERSISTENT;
+        self
+    }
+
+    /// Fail to prepare the statement if it uses virtual tables.
+    pub fn with_no_vtab(mut self) -> Self {
+        self.0 |= ffi::SQLITE_PREPARE_NO_VTAB;
+        self
+    }
+}
+
+impl Default for PrepareFlags {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'l> Drop for Statement<'l> {
@@ -278,6 +645,64 @@ impl<'l> Drop for Statement<'l> {
     }
 }
 
+impl RawValue {
+    /// Return the type.
+    pub fn kind(&self) -> Type {
+        match unsafe { ffi::sqlite3_value_type(self.raw) } {
+            ffi::SQLITE_BLOB => Type::Binary,
+            ffi::SQLITE_FLOAT => Type::Float,
+            ffi::SQLITE_INTEGER => Type::Integer,
+            ffi::SQLITE_TEXT => Type::String,
+            _ => Type::Null,
+        }
+    }
+
+    /// Return the subtype.
+    #[inline]
+    pub fn subtype(&self) -> u32 {
+        unsafe { ffi::sqlite3_value_subtype(self.raw) as u32 }
+    }
+
+    /// Convert into a value.
+    #[inline]
+    pub fn to_value(&self) -> Value {
+        unsafe { crate::function::read(self.raw) }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn as_raw(&self) -> *mut ffi::sqlite3_value {
+        self.raw
+    }
+}
+
+impl Clone for RawValue {
+    fn clone(&self) -> Self {
+        let raw = unsafe { ffi::sqlite3_value_dup(self.raw) };
+        if raw.is_null() {
+            panic!("failed to allocate memory");
+        }
+        RawValue { raw }
+    }
+}
+
+impl std::fmt::Debug for RawValue {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("RawValue")
+            .field("value", &self.to_value())
+            .field("subtype", &self.subtype())
+            .finish()
+    }
+}
+
+impl Drop for RawValue {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_value_free(self.raw) };
+    }
+}
+
 impl<'l, 'm> From<&'m mut Statement<'l>> for Cursor<'l, 'm> {
     #[inline]
     fn from(statement: &'m mut Statement<'l>) -> Self {
@@ -306,6 +731,37 @@ where
     }
 }
 
+macro_rules! tuple(
+    ($($type:ident: $index:tt),+) => (
+        impl<$($type),+> Bindable for ($($type,)+)
+        where
+            $($type: BindableWithIndex,)+
+        {
+            #[inline]
+            fn bind(self, statement: &mut Statement) -> Result<()> {
+                $(self.$index.bind(statement, $index + 1)?;)+
+                Ok(())
+            }
+        }
+    );
+);
+
+tuple!(A: 0);
+tuple!(A: 0, B: 1, C: 2);
+tuple!(A: 0, B: 1, C: 2, D: 3);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11, M: 12);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11, M: 12, N: 13);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11, M: 12, N: 13, O: 14);
+tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11, M: 12, N: 13, O: 14, P: 15);
+
 impl<T> Bindable for &[T]
 where
     T: BindableWithIndex + Clone,
@@ -331,6 +787,34 @@ where
     }
 }
 
+macro_rules! named(
+    ($($type:ty),+) => ($(
+        impl<U> Bindable for ($type, U)
+        where
+            U: BindableWithIndex,
+        {
+            #[inline]
+            fn bind(self, statement: &mut Statement) -> Result<()> {
+                self.1.bind(statement, &self.0)
+            }
+        }
+
+        impl<U> Bindable for &[($type, U)]
+        where
+            U: BindableWithIndex + Clone,
+        {
+            fn bind(self, statement: &mut Statement) -> Result<()> {
+                for (name, value) in self.iter() {
+                    value.clone().bind(statement, name)?;
+                }
+                Ok(())
+            }
+        }
+    )+);
+);
+
+named!(String, Box<str>, Cow<'_, str>);
+
 impl BindableWithIndex for &[u8] {
     fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
         unsafe {
@@ -349,6 +833,48 @@ impl BindableWithIndex for &[u8] {
     }
 }
 
+impl BindableWithIndex for Box<[u8]> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for &Box<[u8]> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for Cow<'_, [u8]> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for &Cow<'_, [u8]> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for bool {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        i64::from(self).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for f32 {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        f64::from(self).bind(statement, index)
+    }
+}
+
 impl BindableWithIndex for f64 {
     fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
         unsafe {
@@ -399,6 +925,34 @@ impl BindableWithIndex for &str {
     }
 }
 
+impl BindableWithIndex for Box<str> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&*self).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for &Box<str> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&**self).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for Cow<'_, str> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&*self).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for &Cow<'_, str> {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&**self).bind(statement, index)
+    }
+}
+
 impl BindableWithIndex for () {
     fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
         unsafe {
@@ -411,6 +965,45 @@ impl BindableWithIndex for () {
     }
 }
 
+impl BindableWithIndex for Zeroblob {
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        unsafe {
+            ok!(
+                statement.raw.1,
+                ffi::sqlite3_bind_zeroblob64(
+                    statement.raw.0,
+                    index.index(statement)? as c_int,
+                    self.0 as u64,
+                )
+            );
+        }
+        Ok(())
+    }
+}
+
+impl BindableWithIndex for &RawValue {
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        unsafe {
+            ok!(
+                statement.raw.1,
+                ffi::sqlite3_bind_value(
+                    statement.raw.0,
+                    index.index(statement)? as c_int,
+                    self.raw,
+                )
+            );
+        }
+        Ok(())
+    }
+}
+
+impl BindableWithIndex for RawValue {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self).bind(statement, index)
+    }
+}
+
 impl BindableWithIndex for Value {
     #[inline]
     fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
@@ -456,6 +1049,33 @@ where
     }
 }
 
+macro_rules! integer(
+    ($($type:ty),+) => ($(
+        impl BindableWithIndex for $type {
+            #[inline]
+            fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+                match i64::try_from(self) {
+                    Ok(value) => value.bind(statement, index),
+                    _ => raise!("the value is out of range ({})", self),
+                }
+            }
+        }
+
+        impl ReadableWithIndex for $type {
+            #[inline]
+            fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+                let value = i64::read(statement, index)?;
+                match <$type>::try_from(value) {
+                    Ok(value) => Ok(value),
+                    _ => raise!("the value is out of range ({})", value),
+                }
+            }
+        }
+    )+);
+);
+
+integer!(i8, i16, i32, u16, u32, u64, usize);
+
 impl ColumnIndex for &str {
     #[inline]
     fn index(self, statement: &Statement) -> Result<usize> {
@@ -478,12 +1098,16 @@ impl ColumnIndex for usize {
     }
 }
 
-impl ParameterIndex for &str {
+impl<T> ParameterIndex for &T
+where
+    T: AsRef<str> + std::fmt::Debug + ?Sized,
+{
     #[inline]
     fn index(self, statement: &Statement) -> Result<usize> {
-        match statement.parameter_index(self)? {
+        let name = self.as_ref();
+        match statement.parameter_index(name)? {
             Some(index) => Ok(index),
-            _ => raise!("the index is out of range ({})", self),
+            _ => raise!("the index is out of range ({})", name),
         }
     }
 }
@@ -518,6 +1142,20 @@ impl ReadableWithIndex for Vec<u8> {
     }
 }
 
+impl ReadableWithIndex for bool {
+    #[inline]
+    fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+        i64::read(statement, index).map(|value| value != 0)
+    }
+}
+
+impl ReadableWithIndex for f32 {
+    #[inline]
+    fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+        crate::value::narrow(f64::read(statement, index)?)
+    }
+}
+
 impl ReadableWithIndex for f64 {
     #[allow(clippy::unnecessary_cast)]
     fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
@@ -561,6 +1199,15 @@ impl ReadableWithIndex for Value {
     }
 }
 
+impl ReadableWithIndex for () {
+    fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+        if statement.column_type(index)? != Type::Null {
+            raise!("failed to convert");
+        }
+        Ok(())
+    }
+}
+
 impl<T: ReadableWithIndex> ReadableWithIndex for Option<T> {
     fn read<U: ColumnIndex>(statement: &Statement, index: U) -> Result<Self> {
         if statement.column_type(index)? == Type::Null {
@@ -571,26 +1218,130 @@ impl<T: ReadableWithIndex> ReadableWithIndex for Option<T> {
     }
 }
 
-pub fn new<'l, T>(raw_connection: *mut ffi::sqlite3, statement: T) -> Result<Statement<'l>>
+fn execute(raw_connection: *mut ffi::sqlite3, statement: &str) -> Result<()> {
+    unsafe {
+        ok!(
+            raw_connection,
+            ffi::sqlite3_exec(
+                raw_connection,
+                str_to_cstr!(statement).as_ptr(),
+                None,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn new<'l, T>(
+    raw_connection: *mut ffi::sqlite3,
+    statement: T,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+) -> Result<Statement<'l>>
+where
+    T: AsRef<str>,
+{
+    new_with_flags(
+        raw_connection,
+        statement,
+        PrepareFlags::new(),
+        error_context,
+        redaction,
+    )
+}
+
+pub fn new_with_flags<'l, T>(
+    raw_connection: *mut ffi::sqlite3,
+    statement: T,
+    flags: PrepareFlags,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+) -> Result<Statement<'l>>
 where
     T: AsRef<str>,
 {
     let mut raw_statement = std::ptr::null_mut();
     unsafe {
-        ok!(
-            raw_connection,
-            ffi::sqlite3_prepare_v2(
+        let sql = str_to_cstr!(statement.as_ref());
+        let code = crate::log::scope(raw_connection, || {
+            ffi::sqlite3_prepare_v3(
                 raw_connection,
-                str_to_cstr!(statement.as_ref()).as_ptr(),
+                sql.as_ptr(),
                 -1,
+                flags.0 as _,
                 &mut raw_statement,
                 std::ptr::null_mut(),
             )
-        );
+        });
+        if code != ffi::SQLITE_OK {
+            let mut error = crate::error::from_code(raw_connection, code);
+            if error_context != ErrorContext::Disabled {
+                error.sql = Some(statement.as_ref().to_string());
+            }
+            return Err(error);
+        }
     }
-    let column_count = unsafe { ffi::sqlite3_column_count(raw_statement) as usize };
+    Ok(unsafe { adopt(raw_statement, raw_connection, error_context, redaction) })
+}
+
+pub fn new_next<'l>(
+    raw_connection: *mut ffi::sqlite3,
+    statement: &mut &str,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+) -> Result<Option<Statement<'l>>> {
+    while !statement.trim().is_empty() {
+        let current = str_to_cstr!(*statement);
+        let mut raw_statement = std::ptr::null_mut();
+        let mut tail = std::ptr::null();
+        let offset = unsafe {
+            let code = crate::log::scope(raw_connection, || {
+                ffi::sqlite3_prepare_v2(
+                    raw_connection,
+                    current.as_ptr(),
+                    -1,
+                    &mut raw_statement,
+                    &mut tail,
+                )
+            });
+            if code != ffi::SQLITE_OK {
+                let mut error = crate::error::from_code(raw_connection, code);
+                if error_context != ErrorContext::Disabled {
+                    error.sql = Some(statement.to_string());
+                }
+                return Err(error);
+            }
+            if tail.is_null() {
+                statement.len()
+            } else {
+                tail.offset_from(current.as_ptr()) as usize
+            }
+        };
+        *statement = &statement[offset..];
+        if !raw_statement.is_null() {
+            return Ok(Some(unsafe {
+                adopt(raw_statement, raw_connection, error_context, redaction)
+            }));
+        }
+        if offset == 0 {
+            break;
+        }
+    }
+    *statement = "";
+    Ok(None)
+}
+
+pub unsafe fn adopt<'l>(
+    raw_statement: *mut ffi::sqlite3_stmt,
+    raw_connection: *mut ffi::sqlite3,
+    error_context: ErrorContext,
+    redaction: Option<Arc<Redaction>>,
+) -> Statement<'l> {
+    let column_count = ffi::sqlite3_column_count(raw_statement) as usize;
     let column_names = (0..column_count)
-        .map(|index| unsafe {
+        .map(|index| {
             let raw = ffi::sqlite3_column_name(raw_statement, index as c_int);
             debug_assert!(!raw.is_null());
             c_str_to_str!(raw).unwrap().to_string()
@@ -601,10 +1352,51 @@ where
         .enumerate()
         .map(|(index, name)| (name.to_string(), index))
         .collect();
-    Ok(Statement {
+    Statement {
         raw: (raw_statement, raw_connection),
         column_names,
         column_mapping: Rc::new(column_mapping),
+        error_context,
+        redaction,
+        busy: BusyStatistics::default(),
         phantom: PhantomData,
-    })
+    }
+}
+
+#[cfg(feature = "normalize")]
+extern "C" {
+    fn sqlite3_normalized_sql(statement: *mut ffi::sqlite3_stmt) -> *const core::ffi::c_char;
+}
+
+#[cfg(feature = "column-metadata")]
+unsafe fn c_str_to_option(value: *const core::ffi::c_char) -> Option<String> {
+    if value.is_null() {
+        None
+    } else {
+        Some(c_str_to_string!(value))
+    }
+}
+
+pub fn read_blob_into(statement: &Statement, index: usize, buffer: &mut Vec<u8>) {
+    buffer.clear();
+    unsafe {
+        let pointer = ffi::sqlite3_column_blob(statement.raw.0, index as c_int);
+        if !pointer.is_null() {
+            let count = ffi::sqlite3_column_bytes(statement.raw.0, index as c_int) as usize;
+            buffer.extend_from_slice(std::slice::from_raw_parts(pointer as *const u8, count));
+        }
+    }
+}
+
+pub fn read_text_into(statement: &Statement, index: usize, buffer: &mut String) -> Result<()> {
+    buffer.clear();
+    unsafe {
+        let pointer = ffi::sqlite3_column_text(statement.raw.0, index as c_int);
+        if pointer.is_null() {
+            raise!("cannot read a text column");
+        }
+        let bytes = std::ffi::CStr::from_ptr(pointer as *const _).to_bytes();
+        buffer.push_str(&String::from_utf8_lossy(bytes));
+    }
+    Ok(())
 }
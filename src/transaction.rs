@@ -0,0 +1,341 @@
+use std::ops::Deref;
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::statement::State;
+
+/// A transaction.
+///
+/// The transaction is rolled back when dropped without an explicit commit.
+pub struct Transaction<'l> {
+    connection: &'l Connection,
+    active: bool,
+}
+
+/// A transaction spanning the main database and all attached databases.
+///
+/// SQLite commits a transaction that touches several attached databases atomically across all of
+/// them only if the main database is an ordinary file and none of the touched databases is in the
+/// WAL journal mode. Otherwise, the transaction is atomic for each database individually, but a
+/// crash in the middle of a commit might leave some databases updated and others not; see
+/// `is_atomic`.
+///
+/// The transaction is rolled back when dropped without an explicit commit.
+pub struct MultiTx<'l> {
+    connection: &'l Connection,
+    schemas: Vec<String>,
+    active: bool,
+}
+
+/// A read transaction pinning the data seen by queries.
+///
+/// All queries made via the guard see the database as it was when the guard was created, even if
+/// other connections commit changes in the meantime. In the WAL journal mode, writers are not
+/// blocked; in the other modes, they cannot commit until the guard is dropped.
+///
+/// The transaction is rolled back when finished or dropped, so any changes made via the guard are
+/// discarded.
+pub struct PinnedReader<'l> {
+    connection: &'l Connection,
+    active: bool,
+}
+
+/// The behavior of a transaction.
+///
+/// See the [documentation][1] of SQLite for further details.
+///
+/// [1]: https://www.sqlite.org/lang_transaction.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TransactionBehavior {
+    /// Acquire locks lazily when the database is first accessed.
+    #[default]
+    Deferred,
+    /// Start writing immediately.
+    Immediate,
+    /// Start writing immediately and prevent other connections from reading.
+    Exclusive,
+    /// Allow concurrent writers with page-level conflict detection at commit time.
+    ///
+    /// The behavior is available only in SQLite builds from the `begin-concurrent` branch; see
+    /// `is_supported`. Starting such a transaction otherwise fails with `ResultCode::Misuse`.
+    Concurrent,
+}
+
+/// A savepoint within a transaction.
+///
+/// The savepoint is rolled back when dropped without an explicit release.
+pub struct Savepoint<'l> {
+    connection: &'l Connection,
+    name: String,
+    active: bool,
+}
+
+impl<'l> Transaction<'l> {
+    /// Create a savepoint.
+    #[inline]
+    pub fn savepoint<T: AsRef<str>>(&self, name: T) -> Result<Savepoint<'_>> {
+        Savepoint::new(self.connection, name.as_ref())
+    }
+
+    /// Commit the transaction.
+    ///
+    /// If the commit fails, the transaction is rolled back.
+    pub fn commit(mut self) -> Result<()> {
+        self.connection.execute("COMMIT")?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Roll back the transaction.
+    pub fn rollback(mut self) -> Result<()> {
+        self.connection.execute("ROLLBACK")?;
+        self.active = false;
+        Ok(())
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Connection;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.connection
+    }
+}
+
+impl Drop for Transaction<'_> {
+    #[inline]
+    #[allow(unused_must_use)]
+    fn drop(&mut self) {
+        if self.active {
+            self.connection.execute("ROLLBACK");
+        }
+    }
+}
+
+impl<'l> MultiTx<'l> {
+    /// Begin a transaction.
+    #[inline]
+    pub fn begin(connection: &'l Connection) -> Result<Self> {
+        MultiTx::begin_with_behavior(connection, TransactionBehavior::Deferred)
+    }
+
+    /// Begin a transaction with a specific behavior.
+    pub fn begin_with_behavior(
+        connection: &'l Connection,
+        behavior: TransactionBehavior,
+    ) -> Result<Self> {
+        let schemas = schema_names(connection);
+        begin(connection, behavior)?;
+        Ok(MultiTx {
+            connection,
+            schemas,
+            active: true,
+        })
+    }
+
+    /// Return the names of the schemas covered by the transaction.
+    ///
+    /// The list includes `main` and the aliases of all attached databases.
+    #[inline]
+    pub fn schemas(&self) -> &[String] {
+        &self.schemas
+    }
+
+    /// Check if a commit is atomic across all schemas.
+    pub fn is_atomic(&self) -> Result<bool> {
+        let mut statement = self.connection.prepare("PRAGMA database_list")?;
+        while let State::Row = statement.next()? {
+            let name = statement.read::<String, _>(1)?;
+            let path = statement.read::<Option<String>, _>(2)?;
+            if name == "temp" {
+                continue;
+            }
+            if name == "main" && path.map(|path| path.is_empty()).unwrap_or(true) {
+                return Ok(false);
+            }
+            let mut mode = self
+                .connection
+                .prepare(format!("PRAGMA {}.journal_mode", quote(&name)))?;
+            if let State::Row = mode.next()? {
+                if mode.read::<String, _>(0)?.eq_ignore_ascii_case("wal") {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Create a savepoint.
+    ///
+    /// Savepoints in SQLite are not bound to a particular schema; a savepoint covers the changes
+    /// made to all databases since it was created, which makes it possible to undo the work done
+    /// for one shard without abandoning the whole transaction.
+    pub fn savepoint<T: AsRef<str>>(&self, name: T) -> Result<Savepoint<'_>> {
+        Savepoint::new(self.connection, name.as_ref())
+    }
+
+    /// Commit the transaction.
+    ///
+    /// If the commit fails, the transaction is rolled back.
+    pub fn commit(mut self) -> Result<()> {
+        self.connection.execute("COMMIT")?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Roll back the transaction.
+    pub fn rollback(mut self) -> Result<()> {
+        self.connection.execute("ROLLBACK")?;
+        self.active = false;
+        Ok(())
+    }
+}
+
+impl Drop for MultiTx<'_> {
+    #[inline]
+    #[allow(unused_must_use)]
+    fn drop(&mut self) {
+        if self.active {
+            self.connection.execute("ROLLBACK");
+        }
+    }
+}
+
+impl PinnedReader<'_> {
+    /// End the transaction.
+    pub fn finish(mut self) -> Result<()> {
+        self.connection.execute("ROLLBACK")?;
+        self.active = false;
+        Ok(())
+    }
+}
+
+impl Deref for PinnedReader<'_> {
+    type Target = Connection;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.connection
+    }
+}
+
+impl Drop for PinnedReader<'_> {
+    #[inline]
+    #[allow(unused_must_use)]
+    fn drop(&mut self) {
+        if self.active {
+            self.connection.execute("ROLLBACK");
+        }
+    }
+}
+
+impl TransactionBehavior {
+    /// Check if the behavior is supported by the linked library.
+    pub fn is_supported(self, connection: &Connection) -> bool {
+        match self {
+            TransactionBehavior::Concurrent => connection.prepare(self.statement()).is_ok(),
+            _ => true,
+        }
+    }
+
+    fn statement(self) -> &'static str {
+        match self {
+            TransactionBehavior::Deferred => "BEGIN DEFERRED",
+            TransactionBehavior::Immediate => "BEGIN IMMEDIATE",
+            TransactionBehavior::Exclusive => "BEGIN EXCLUSIVE",
+            TransactionBehavior::Concurrent => "BEGIN CONCURRENT",
+        }
+    }
+}
+
+impl<'l> Savepoint<'l> {
+    fn new(connection: &'l Connection, name: &str) -> Result<Self> {
+        connection.execute(format!("SAVEPOINT {}", quote(name)))?;
+        Ok(Savepoint {
+            connection,
+            name: name.to_string(),
+            active: true,
+        })
+    }
+
+    /// Return the name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Release the savepoint, keeping the changes made since it was created.
+    ///
+    /// If the release fails, the savepoint is rolled back.
+    pub fn release(mut self) -> Result<()> {
+        self.connection
+            .execute(format!("RELEASE {}", quote(&self.name)))?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Roll back the changes made since the savepoint was created and release it.
+    pub fn rollback(mut self) -> Result<()> {
+        let name = quote(&self.name);
+        self.connection
+            .execute(format!("ROLLBACK TO {name}; RELEASE {name}"))?;
+        self.active = false;
+        Ok(())
+    }
+}
+
+impl Drop for Savepoint<'_> {
+    #[inline]
+    #[allow(unused_must_use)]
+    fn drop(&mut self) {
+        if self.active {
+            let name = quote(&self.name);
+            self.connection
+                .execute(format!("ROLLBACK TO {name}; RELEASE {name}"));
+        }
+    }
+}
+
+pub fn new(connection: &Connection, behavior: TransactionBehavior) -> Result<Transaction<'_>> {
+    begin(connection, behavior)?;
+    Ok(Transaction {
+        connection,
+        active: true,
+    })
+}
+
+pub fn pin(connection: &Connection) -> Result<PinnedReader<'_>> {
+    connection.execute("BEGIN DEFERRED")?;
+    let reader = PinnedReader {
+        connection,
+        active: true,
+    };
+    // A deferred transaction takes its snapshot only on the first read.
+    for schema in schema_names(connection) {
+        connection.execute(format!("SELECT 1 FROM {}.sqlite_master", quote(&schema)))?;
+    }
+    Ok(reader)
+}
+
+fn begin(connection: &Connection, behavior: TransactionBehavior) -> Result<()> {
+    if !behavior.is_supported(connection) {
+        return Err(Error::new(
+            Some(ffi::SQLITE_MISUSE as isize),
+            Some(format!(
+                "the transaction behavior is not supported ({behavior:?})"
+            )),
+        ));
+    }
+    connection.execute(behavior.statement())
+}
+
+pub fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn schema_names(connection: &Connection) -> Vec<String> {
+    let mut names = connection.schema_names();
+    names.retain(|name| name != "temp");
+    names
+}
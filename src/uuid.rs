@@ -0,0 +1,98 @@
+use uuid::fmt::Hyphenated;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::statement::{
+    BindableWithIndex, ColumnIndex, ParameterIndex, ReadableWithIndex, Statement,
+};
+use crate::value::{Type, Value};
+
+impl BindableWithIndex for Uuid {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self.as_bytes()[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for &Uuid {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        (&self.as_bytes()[..]).bind(statement, index)
+    }
+}
+
+impl BindableWithIndex for Hyphenated {
+    #[inline]
+    fn bind<T: ParameterIndex>(self, statement: &mut Statement, index: T) -> Result<()> {
+        self.encode_lower(&mut Uuid::encode_buffer())
+            .bind(statement, index)
+    }
+}
+
+impl ReadableWithIndex for Uuid {
+    fn read<T: ColumnIndex>(statement: &Statement, index: T) -> Result<Self> {
+        match statement.column_type(index)? {
+            Type::Binary => parse(&Value::Binary(statement.read(index)?)),
+            Type::String => parse(&Value::String(statement.read(index)?)),
+            _ => raise!("failed to convert"),
+        }
+    }
+}
+
+impl From<Uuid> for Value {
+    #[inline]
+    fn from(value: Uuid) -> Self {
+        Value::Binary(value.as_bytes().to_vec())
+    }
+}
+
+impl From<Hyphenated> for Value {
+    #[inline]
+    fn from(value: Hyphenated) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl TryFrom<&Value> for Uuid {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        parse(value)
+    }
+}
+
+impl TryFrom<&Value> for Option<Uuid> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        if let Value::Null = value {
+            return Ok(None);
+        }
+        parse(value).map(Some)
+    }
+}
+
+impl TryFrom<Value> for Uuid {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: Value) -> Result<Self> {
+        parse(&value)
+    }
+}
+
+fn parse(value: &Value) -> Result<Uuid> {
+    match value {
+        Value::Binary(value) => match Uuid::from_slice(value) {
+            Ok(value) => Ok(value),
+            Err(error) => raise!("failed to convert a UUID ({error})"),
+        },
+        Value::String(value) => match Uuid::parse_str(value) {
+            Ok(value) => Ok(value),
+            Err(error) => raise!("failed to parse a UUID ({error})"),
+        },
+        _ => raise!("failed to convert"),
+    }
+}
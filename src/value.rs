@@ -1,8 +1,13 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
 
 use crate::error::{Error, Result};
 
 /// A value.
+///
+/// `NULL` corresponds to `Value::Null` and `()`. When a value is read into `Option<T>`, be it via
+/// `Statement::read`, `Row::read`, or `Value::try_into`, `NULL` becomes `None`, and any other
+/// value is converted into `T`. When a value is bound, `None` and `()` become `NULL`.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub enum Value {
     /// Binary data.
@@ -33,6 +38,39 @@ pub enum Type {
     Null,
 }
 
+impl Type {
+    /// Determine the type corresponding to the affinity of a declared column type.
+    ///
+    /// The [rules][1] of SQLite are applied in order: a declared type containing `INT` has the
+    /// integer affinity; one containing `CHAR`, `CLOB`, or `TEXT` has the text affinity; one
+    /// containing `BLOB` or an empty one has the blob affinity; and one containing `REAL`,
+    /// `FLOA`, or `DOUB` has the real affinity. Any other declared type has the numeric affinity,
+    /// which is reported as `Type::Float` since values can be either integers or floats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::Type;
+    ///
+    /// assert_eq!(Type::from_decltype("VARCHAR(255)"), Type::String);
+    /// assert_eq!(Type::from_decltype("FLOATING POINT"), Type::Integer);
+    /// ```
+    ///
+    /// [1]: https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+    pub fn from_decltype(value: &str) -> Type {
+        let value = value.to_ascii_uppercase();
+        if value.contains("INT") {
+            Type::Integer
+        } else if value.contains("CHAR") || value.contains("CLOB") || value.contains("TEXT") {
+            Type::String
+        } else if value.contains("BLOB") || value.trim().is_empty() {
+            Type::Binary
+        } else {
+            Type::Float
+        }
+    }
+}
+
 impl Value {
     /// Return the type.
     pub fn kind(&self) -> Type {
@@ -76,13 +114,36 @@ macro_rules! implement(
 
 implement!(Vec<u8>, Binary);
 implement!(&[u8], Binary);
+implement!(Box<[u8]>, Binary);
+implement!(Cow<'_, [u8]>, Binary);
 implement!(f64, Float);
 implement!(i64, Integer);
 implement!(String, String);
 implement!(&str, String);
+implement!(Box<str>, String);
+implement!(Cow<'_, str>, String);
 implement!((), Null);
 
 macro_rules! implement(
+    (@value (), Null) => {
+        impl TryFrom<Value> for () {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: Value) -> Result<Self> {
+                <()>::try_from(&value)
+            }
+        }
+
+        impl TryFrom<Value> for Option<()> {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: Value) -> Result<Self> {
+                <Option<()>>::try_from(&value)
+            }
+        }
+    };
     (@value $type:ty, $value:ident) => {
         impl TryFrom<Value> for $type {
             type Error = Error;
@@ -120,6 +181,18 @@ macro_rules! implement(
                 raise!("failed to convert");
             }
         }
+
+        impl TryFrom<&Value> for Option<()> {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let &Value::Null = value {
+                    return Ok(None);
+                }
+                raise!("failed to convert");
+            }
+        }
     };
     (@reference $type:ty, $value:ident) => {
         impl TryFrom<&Value> for $type {
@@ -146,6 +219,31 @@ macro_rules! implement(
             }
         }
     };
+    (@reference-clone $type:ty, $value:ident) => {
+        impl TryFrom<&Value> for $type {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let &Value::$value(ref value) = value {
+                    return Ok(value.clone());
+                }
+                raise!("failed to convert");
+            }
+        }
+
+        impl TryFrom<&Value> for Option<$type> {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let Value::Null = value {
+                    return Ok(None);
+                }
+                <$type>::try_from(value).and_then(|value| Ok(Some(value)))
+            }
+        }
+    };
     (@reference-lifetime $type:ty, $value:ident) => {
         impl<'l> TryFrom<&'l Value> for $type {
             type Error = Error;
@@ -174,13 +272,152 @@ macro_rules! implement(
 );
 
 implement!(@value Vec<u8>, Binary);
+implement!(@reference-clone Vec<u8>, Binary);
 implement!(@reference-lifetime &'l [u8], Binary);
+implement!(@value f64, Float);
 implement!(@reference f64, Float);
+implement!(@value i64, Integer);
 implement!(@reference i64, Integer);
 implement!(@value String, String);
+implement!(@reference-clone String, String);
 implement!(@reference-lifetime &'l str, String);
+implement!(@value (), Null);
 implement!(@reference (), Null);
 
+macro_rules! integer(
+    ($($type:ty),+) => ($(
+        impl TryFrom<&Value> for $type {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let &Value::Integer(value) = value {
+                    if let Ok(value) = <$type>::try_from(value) {
+                        return Ok(value);
+                    }
+                    raise!("the value is out of range ({})", value);
+                }
+                raise!("failed to convert");
+            }
+        }
+
+        impl TryFrom<&Value> for Option<$type> {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: &Value) -> Result<Self> {
+                if let Value::Null = value {
+                    return Ok(None);
+                }
+                <$type>::try_from(value).map(Some)
+            }
+        }
+    )+);
+);
+
+integer!(i8, i16, i32, u16, u32, u64, usize);
+
+impl From<bool> for Value {
+    #[inline]
+    fn from(value: bool) -> Self {
+        Value::Integer(value.into())
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: Value) -> Result<Self> {
+        bool::try_from(&value)
+    }
+}
+
+impl TryFrom<Value> for Option<bool> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: Value) -> Result<Self> {
+        <Option<bool>>::try_from(&value)
+    }
+}
+
+impl TryFrom<&Value> for bool {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        if let &Value::Integer(value) = value {
+            return Ok(value != 0);
+        }
+        raise!("failed to convert");
+    }
+}
+
+impl TryFrom<&Value> for Option<bool> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        if let Value::Null = value {
+            return Ok(None);
+        }
+        bool::try_from(value).map(Some)
+    }
+}
+
+impl From<f32> for Value {
+    #[inline]
+    fn from(value: f32) -> Self {
+        Value::Float(value.into())
+    }
+}
+
+impl TryFrom<&Value> for f32 {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        if let &Value::Float(value) = value {
+            return narrow(value);
+        }
+        raise!("failed to convert");
+    }
+}
+
+impl TryFrom<&Value> for Option<f32> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        if let Value::Null = value {
+            return Ok(None);
+        }
+        f32::try_from(value).map(Some)
+    }
+}
+
+impl TryFrom<&Value> for Value {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl TryFrom<&Value> for Option<Value> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &Value) -> Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            value => Ok(Some(value.clone())),
+        }
+    }
+}
+
 impl<T> From<Option<T>> for Value
 where
     T: Into<Value>,
@@ -194,3 +431,26 @@ where
         }
     }
 }
+
+#[cfg(feature = "serde_json")]
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Binary(value) => value.into(),
+            Value::Float(value) => serde_json::Number::from_f64(value)
+                .map(serde_json::Value::Number)
+                .unwrap_or_default(),
+            Value::Integer(value) => value.into(),
+            Value::String(value) => value.into(),
+            Value::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+pub fn narrow(value: f64) -> Result<f32> {
+    let narrowed = value as f32;
+    if value.is_finite() && !narrowed.is_finite() {
+        raise!("the value is out of range ({})", value);
+    }
+    Ok(narrowed)
+}
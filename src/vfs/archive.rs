@@ -0,0 +1,178 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::error::{Error, Result};
+use crate::vfs::{read_buffer, write_buffer, File, FileFlags, FileKind, Vfs};
+
+/// A read-only virtual file system serving databases stored in zip archives.
+///
+/// The path of a main database is the path to an archive followed by the name of an entry within
+/// it, such as `data/lookup.zip/lookup.sqlite3`. Stored entries are read directly from the
+/// archive, while deflated entries are decompressed into memory when opened. Temporary files are
+/// kept in memory. The databases should be opened for reading only.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite::{Connection, OpenFlags};
+/// use sqlite::vfs::ZipVfs;
+///
+/// sqlite::vfs::register("zip", ZipVfs::new(), false)?;
+/// let connection = Connection::open_with_flags_and_vfs(
+///     "data/lookup.zip/lookup.sqlite3",
+///     OpenFlags::new().with_read_only(),
+///     "zip",
+/// )?;
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZipVfs;
+
+#[doc(hidden)]
+pub enum ZipFile {
+    Stored {
+        file: std::fs::File,
+        start: u64,
+        size: u64,
+    },
+    Deflated(Vec<u8>),
+    Temporary(Vec<u8>),
+}
+
+impl ZipVfs {
+    /// Create a virtual file system.
+    #[inline]
+    pub fn new() -> Self {
+        ZipVfs
+    }
+}
+
+impl Vfs for ZipVfs {
+    type File = ZipFile;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        match (path, flags.kind()) {
+            (Some(path), FileKind::MainDatabase) => {
+                let (archive, name) = split(path)?;
+                let file = std::fs::File::open(archive).map_err(from_io)?;
+                let mut archive = ZipArchive::new(file).map_err(from_zip)?;
+                let mut entry = archive.by_name(&name).map_err(from_zip)?;
+                match entry.compression() {
+                    CompressionMethod::Stored => {
+                        let start = entry.data_start();
+                        let size = entry.size();
+                        drop(entry);
+                        Ok(ZipFile::Stored {
+                            file: archive.into_inner(),
+                            start,
+                            size,
+                        })
+                    }
+                    _ => {
+                        let mut data = Vec::with_capacity(entry.size() as usize);
+                        entry.read_to_end(&mut data).map_err(from_io)?;
+                        Ok(ZipFile::Deflated(data))
+                    }
+                }
+            }
+            (None, _) => Ok(ZipFile::Temporary(Vec::new())),
+            _ => Err(Error::new(
+                Some(ffi::SQLITE_CANTOPEN as isize),
+                Some("only main databases can be served from archives".into()),
+            )),
+        }
+    }
+
+    #[inline]
+    fn delete(&self, _: &str) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn exists(&self, _: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl File for ZipFile {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        match self {
+            ZipFile::Stored { file, start, size } => {
+                let count = (buffer.len() as u64).min(size.saturating_sub(offset)) as usize;
+                file.seek(SeekFrom::Start(*start + offset))
+                    .map_err(from_io)?;
+                file.read_exact(&mut buffer[..count]).map_err(from_io)?;
+                Ok(count)
+            }
+            ZipFile::Deflated(data) | ZipFile::Temporary(data) => {
+                Ok(read_buffer(data, offset, buffer))
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        match self {
+            ZipFile::Temporary(data) => {
+                write_buffer(data, offset, buffer);
+                Ok(())
+            }
+            _ => Err(Error::new(Some(ffi::SQLITE_READONLY as isize), None)),
+        }
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        match self {
+            ZipFile::Temporary(data) => {
+                data.truncate(size as usize);
+                Ok(())
+            }
+            _ => Err(Error::new(Some(ffi::SQLITE_READONLY as isize), None)),
+        }
+    }
+
+    #[inline]
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        match self {
+            ZipFile::Stored { size, .. } => Ok(*size),
+            ZipFile::Deflated(data) | ZipFile::Temporary(data) => Ok(data.len() as u64),
+        }
+    }
+
+    fn characteristics(&mut self) -> i32 {
+        match self {
+            ZipFile::Temporary(_) => 0,
+            _ => ffi::SQLITE_IOCAP_IMMUTABLE,
+        }
+    }
+}
+
+fn split(path: &str) -> Result<(PathBuf, String)> {
+    let path = Path::new(path);
+    for archive in path.ancestors().skip(1) {
+        if archive.is_file() {
+            let name = path
+                .strip_prefix(archive)
+                .unwrap()
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            return Ok((archive.to_path_buf(), name));
+        }
+    }
+    raise!("failed to find an archive ({})", path.display());
+}
+
+fn from_io(error: std::io::Error) -> Error {
+    Error::new(Some(ffi::SQLITE_IOERR as isize), Some(error.to_string()))
+}
+
+fn from_zip(error: zip::result::ZipError) -> Error {
+    Error::new(Some(ffi::SQLITE_CANTOPEN as isize), Some(error.to_string()))
+}
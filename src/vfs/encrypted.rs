@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::vfs::{File, FileFlags, LockLevel, Vfs};
+
+/// A cipher encrypting blocks of files.
+pub trait Cipher: Send + Sync + 'static {
+    /// Return the number of bytes added to each block by encryption.
+    fn overhead(&self) -> usize;
+
+    /// Encrypt a block.
+    ///
+    /// The index is the position of the block within the file and should be authenticated along
+    /// with the data. The result is expected to be longer than the block by exactly `overhead`
+    /// bytes.
+    fn encrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decrypt a block.
+    fn decrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// A virtual file system encrypting the files of another one.
+///
+/// Files are split into blocks, which are encrypted independently and stored one after another
+/// together with their overhead, such as nonces and authentication tags. All files are
+/// encrypted, including journals and temporary files. The block size should be equal to the page
+/// size of the databases, as partial writes require decrypting and encrypting entire blocks.
+///
+/// Blocks are bound to their position within a file but not to the file itself, and the
+/// encryption does not hide the size of files.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "chacha20poly1305")]
+/// # {
+/// use sqlite::vfs::{ChaChaCipher, EncryptedVfs, OsVfs};
+///
+/// let key = [42; 32];
+/// let vfs = EncryptedVfs::new(OsVfs::new()?, ChaChaCipher::new(&key));
+/// sqlite::vfs::register("encrypted", vfs, false)?;
+/// # }
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+pub struct EncryptedVfs<T, U> {
+    inner: T,
+    cipher: Arc<U>,
+    block_size: usize,
+}
+
+/// A file opened by a virtual file system encrypting the files of another one.
+pub struct EncryptedFile<T, U> {
+    inner: T,
+    cipher: Arc<U>,
+    block_size: usize,
+}
+
+/// A cipher based on XChaCha20-Poly1305 with random nonces.
+#[cfg(feature = "chacha20poly1305")]
+pub struct ChaChaCipher {
+    inner: chacha20poly1305::XChaCha20Poly1305,
+}
+
+impl<T: Vfs, U: Cipher> EncryptedVfs<T, U> {
+    /// Wrap a virtual file system.
+    ///
+    /// By default, the block size is 4096 bytes.
+    pub fn new(inner: T, cipher: U) -> Self {
+        EncryptedVfs {
+            inner,
+            cipher: Arc::new(cipher),
+            block_size: 4096,
+        }
+    }
+
+    /// Set the block size.
+    pub fn with_block_size(mut self, value: usize) -> Self {
+        self.block_size = value.max(1);
+        self
+    }
+}
+
+impl<T: Vfs, U: Cipher> Vfs for EncryptedVfs<T, U> {
+    type File = EncryptedFile<T::File, U>;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        Ok(EncryptedFile {
+            inner: self.inner.open(path, flags)?,
+            cipher: self.cipher.clone(),
+            block_size: self.block_size,
+        })
+    }
+
+    #[inline]
+    fn delete(&self, path: &str) -> Result<()> {
+        self.inner.delete(path)
+    }
+
+    #[inline]
+    fn exists(&self, path: &str) -> Result<bool> {
+        self.inner.exists(path)
+    }
+
+    #[inline]
+    fn full_path(&self, path: &str) -> Result<String> {
+        self.inner.full_path(path)
+    }
+}
+
+impl<T: File, U: Cipher> EncryptedFile<T, U> {
+    fn stride(&self) -> u64 {
+        (self.block_size + self.cipher.overhead()) as u64
+    }
+
+    fn read_block(&mut self, index: u64) -> Result<Vec<u8>> {
+        let mut block = vec![0; self.stride() as usize];
+        let count = self.inner.read(index * self.stride(), &mut block)?;
+        if count <= self.cipher.overhead() {
+            return Ok(Vec::new());
+        }
+        block.truncate(count);
+        self.cipher.decrypt(index, &block)
+    }
+
+    fn write_block(&mut self, index: u64, block: &[u8]) -> Result<()> {
+        let block = self.cipher.encrypt(index, block)?;
+        if block.len() > self.stride() as usize {
+            raise!("the cipher has produced a block of a wrong size");
+        }
+        self.inner.write(index * self.stride(), &block)
+    }
+
+    fn write_blocks(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        let size = self.block_size as u64;
+        let mut count = 0;
+        while count < buffer.len() {
+            let position = offset + count as u64;
+            let index = position / size;
+            let start = (position % size) as usize;
+            let length = (buffer.len() - count).min(self.block_size - start);
+            let mut block = if start == 0 && length == self.block_size {
+                Vec::new()
+            } else {
+                self.read_block(index)?
+            };
+            if block.len() < start + length {
+                block.resize(start + length, 0);
+            }
+            block[start..(start + length)].copy_from_slice(&buffer[count..(count + length)]);
+            self.write_block(index, &block)?;
+            count += length;
+        }
+        Ok(())
+    }
+}
+
+impl<T: File, U: Cipher> File for EncryptedFile<T, U> {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let size = self.size()?;
+        let mut count = 0;
+        while count < buffer.len() {
+            let position = offset + count as u64;
+            if position >= size {
+                break;
+            }
+            let block = self.read_block(position / self.block_size as u64)?;
+            let start = (position % self.block_size as u64) as usize;
+            if start >= block.len() {
+                break;
+            }
+            let length = (buffer.len() - count).min(block.len() - start);
+            buffer[count..(count + length)].copy_from_slice(&block[start..(start + length)]);
+            count += length;
+        }
+        Ok(count)
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        // Gaps are filled with zeros so that every block but the last one is complete.
+        let size = self.size()?;
+        if offset > size {
+            self.write_blocks(size, &vec![0; (offset - size) as usize])?;
+        }
+        self.write_blocks(offset, buffer)
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        let current = self.size()?;
+        if size > current {
+            return self.write_blocks(current, &vec![0; (size - current) as usize]);
+        }
+        let index = size / self.block_size as u64;
+        let remainder = (size % self.block_size as u64) as usize;
+        if remainder == 0 {
+            return self.inner.truncate(index * self.stride());
+        }
+        let mut block = self.read_block(index)?;
+        block.truncate(remainder);
+        self.inner.truncate(index * self.stride())?;
+        self.write_block(index, &block)
+    }
+
+    #[inline]
+    fn sync(&mut self) -> Result<()> {
+        self.inner.sync()
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        let size = self.inner.size()?;
+        let stride = self.stride();
+        let remainder = (size % stride).saturating_sub(self.cipher.overhead() as u64);
+        Ok(size / stride * self.block_size as u64 + remainder)
+    }
+
+    #[inline]
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    #[inline]
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    #[inline]
+    fn is_reserved(&mut self) -> Result<bool> {
+        self.inner.is_reserved()
+    }
+
+    #[inline]
+    fn sector_size(&mut self) -> usize {
+        self.block_size
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl ChaChaCipher {
+    /// Create a cipher with a 256-bit key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        use chacha20poly1305::KeyInit;
+
+        ChaChaCipher {
+            inner: chacha20poly1305::XChaCha20Poly1305::new(key.into()),
+        }
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl Cipher for ChaChaCipher {
+    #[inline]
+    fn overhead(&self) -> usize {
+        24 + 16
+    }
+
+    fn encrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>> {
+        use chacha20poly1305::aead::{Aead, AeadCore, OsRng, Payload};
+
+        let nonce = chacha20poly1305::XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: block,
+            aad: &index.to_le_bytes(),
+        };
+        let data = match self.inner.encrypt(&nonce, payload) {
+            Ok(data) => data,
+            _ => raise!("failed to encrypt a block"),
+        };
+        let mut value = Vec::with_capacity(nonce.len() + data.len());
+        value.extend_from_slice(&nonce);
+        value.extend_from_slice(&data);
+        Ok(value)
+    }
+
+    fn decrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>> {
+        use chacha20poly1305::aead::{Aead, Payload};
+
+        if block.len() < self.overhead() {
+            return Err(corrupt());
+        }
+        let (nonce, data) = block.split_at(24);
+        let payload = Payload {
+            msg: data,
+            aad: &index.to_le_bytes(),
+        };
+        self.inner
+            .decrypt(nonce.into(), payload)
+            .map_err(|_| corrupt())
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+fn corrupt() -> crate::error::Error {
+    crate::error::Error::new(
+        Some(ffi::SQLITE_NOTADB as isize),
+        Some("failed to decrypt a block".into()),
+    )
+}
@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::vfs::{File, FileFlags, LockLevel, Vfs};
+
+/// A virtual file system injecting faults into the files of another one.
+///
+/// Faults are scheduled for specific occurrences of operations, which are counted across all
+/// files, and each one is triggered once. The type is meant for testing how applications handle
+/// errors and crashes. Clones share the schedule.
+#[derive(Clone)]
+pub struct FaultVfs<T> {
+    inner: T,
+    state: Arc<Mutex<State>>,
+}
+
+/// A file opened by a virtual file system injecting faults into the files of another one.
+pub struct FaultFile<T> {
+    inner: T,
+    state: Arc<Mutex<State>>,
+}
+
+/// An operation on files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// Reading.
+    Read,
+    /// Writing.
+    Write,
+    /// Truncating.
+    Truncate,
+    /// Syncing.
+    Sync,
+}
+
+/// A fault.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fault {
+    /// Fail with an error code.
+    Error(i32),
+    /// Write only a number of bytes and fail with `SQLITE_IOERR_WRITE`.
+    ///
+    /// For operations other than writing, the fault is the same as failing with the corresponding
+    /// input-output error.
+    PartialWrite(usize),
+    /// Wait before performing the operation.
+    Delay(Duration),
+}
+
+#[derive(Default)]
+struct State {
+    counts: [usize; 4],
+    faults: Vec<(Operation, usize, Fault)>,
+}
+
+impl<T: Vfs> FaultVfs<T> {
+    /// Wrap a virtual file system.
+    pub fn new(inner: T) -> Self {
+        FaultVfs {
+            inner,
+            state: Default::default(),
+        }
+    }
+
+    /// Schedule a fault.
+    ///
+    /// The fault is triggered by the operation after the operation has been performed a number
+    /// of further times.
+    pub fn inject(&self, operation: Operation, skip: usize, fault: Fault) {
+        let mut state = self.state.lock().unwrap();
+        let occurrence = state.counts[operation as usize] + skip;
+        state.faults.push((operation, occurrence, fault));
+    }
+
+    /// Remove all scheduled faults.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().faults.clear();
+    }
+
+    /// Return the number of times an operation has been performed.
+    pub fn count(&self, operation: Operation) -> usize {
+        self.state.lock().unwrap().counts[operation as usize]
+    }
+}
+
+impl<T: Vfs> Vfs for FaultVfs<T> {
+    type File = FaultFile<T::File>;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        Ok(FaultFile {
+            inner: self.inner.open(path, flags)?,
+            state: self.state.clone(),
+        })
+    }
+
+    #[inline]
+    fn delete(&self, path: &str) -> Result<()> {
+        self.inner.delete(path)
+    }
+
+    #[inline]
+    fn exists(&self, path: &str) -> Result<bool> {
+        self.inner.exists(path)
+    }
+
+    #[inline]
+    fn full_path(&self, path: &str) -> Result<String> {
+        self.inner.full_path(path)
+    }
+}
+
+impl<T: File> FaultFile<T> {
+    fn fault(&self, operation: Operation) -> Option<Fault> {
+        let mut state = self.state.lock().unwrap();
+        let occurrence = state.counts[operation as usize];
+        state.counts[operation as usize] += 1;
+        let position = state
+            .faults
+            .iter()
+            .position(|(other, value, _)| *other == operation && *value == occurrence)?;
+        let fault = state.faults.remove(position).2;
+        drop(state);
+        if let Fault::Delay(duration) = fault {
+            std::thread::sleep(duration);
+        }
+        Some(fault)
+    }
+}
+
+impl<T: File> File for FaultFile<T> {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        match self.fault(Operation::Read) {
+            Some(Fault::Error(code)) => fail(code),
+            Some(Fault::PartialWrite(_)) => fail(ffi::SQLITE_IOERR_READ),
+            _ => self.inner.read(offset, buffer),
+        }
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        match self.fault(Operation::Write) {
+            Some(Fault::Error(code)) => fail(code),
+            Some(Fault::PartialWrite(size)) => {
+                self.inner
+                    .write(offset, &buffer[..size.min(buffer.len())])?;
+                fail(ffi::SQLITE_IOERR_WRITE)
+            }
+            _ => self.inner.write(offset, buffer),
+        }
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        match self.fault(Operation::Truncate) {
+            Some(Fault::Error(code)) => fail(code),
+            Some(Fault::PartialWrite(_)) => fail(ffi::SQLITE_IOERR_TRUNCATE),
+            _ => self.inner.truncate(size),
+        }
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        match self.fault(Operation::Sync) {
+            Some(Fault::Error(code)) => fail(code),
+            Some(Fault::PartialWrite(_)) => fail(ffi::SQLITE_IOERR_FSYNC),
+            _ => self.inner.sync(),
+        }
+    }
+
+    #[inline]
+    fn size(&mut self) -> Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    #[inline]
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    #[inline]
+    fn is_reserved(&mut self) -> Result<bool> {
+        self.inner.is_reserved()
+    }
+
+    #[inline]
+    fn sector_size(&mut self) -> usize {
+        self.inner.sector_size()
+    }
+
+    #[inline]
+    fn characteristics(&mut self) -> i32 {
+        self.inner.characteristics()
+    }
+}
+
+fn fail<T>(code: i32) -> Result<T> {
+    Err(Error::new(Some(code as isize), None))
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::io::Read;
+
+use crate::error::{Error, Result};
+use crate::vfs::{read_buffer, write_buffer, File, FileFlags, FileKind, Vfs};
+
+/// A read-only virtual file system serving databases over HTTP.
+///
+/// The paths of main databases are interpreted as URLs, and the content is fetched on demand via
+/// range requests, which makes it possible to query large remote databases without downloading
+/// them entirely, so the server has to support range requests. Reads are aligned to chunks, and recently fetched chunks are cached. Temporary
+/// files are kept in memory. The databases should be opened for reading only.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite::{Connection, OpenFlags};
+/// use sqlite::vfs::HttpVfs;
+///
+/// sqlite::vfs::register("http", HttpVfs::new(), false)?;
+/// let connection = Connection::open_with_flags_and_vfs(
+///     "https://example.com/database.sqlite3",
+///     OpenFlags::new().with_read_only(),
+///     "http",
+/// )?;
+/// # Ok::<(), sqlite::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct HttpVfs {
+    agent: ureq::Agent,
+    chunk_size: usize,
+    cache_size: usize,
+}
+
+#[doc(hidden)]
+pub enum HttpFile {
+    Remote(Remote),
+    Temporary(Vec<u8>),
+}
+
+#[doc(hidden)]
+pub struct Remote {
+    agent: ureq::Agent,
+    url: String,
+    size: u64,
+    chunk_size: usize,
+    cache_size: usize,
+    cache: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl HttpVfs {
+    /// Create a virtual file system.
+    ///
+    /// By default, data are fetched in chunks of 64 KiB, and up to 64 chunks are cached per file.
+    pub fn new() -> Self {
+        HttpVfs {
+            agent: ureq::AgentBuilder::new().build(),
+            chunk_size: 64 * 1024,
+            cache_size: 64,
+        }
+    }
+
+    /// Set the size of chunks.
+    ///
+    /// The size should be a multiple of the page size of the databases being served.
+    pub fn with_chunk_size(mut self, value: usize) -> Self {
+        self.chunk_size = value.max(1);
+        self
+    }
+
+    /// Set the number of chunks cached per file.
+    pub fn with_cache_size(mut self, value: usize) -> Self {
+        self.cache_size = value;
+        self
+    }
+}
+
+impl Default for HttpVfs {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vfs for HttpVfs {
+    type File = HttpFile;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        match (path, flags.kind()) {
+            (Some(url), FileKind::MainDatabase) => {
+                let mut remote = Remote {
+                    agent: self.agent.clone(),
+                    url: url.to_string(),
+                    size: 0,
+                    chunk_size: self.chunk_size,
+                    cache_size: self.cache_size,
+                    cache: VecDeque::new(),
+                };
+                remote.size = remote.fetch_size()?;
+                Ok(HttpFile::Remote(remote))
+            }
+            (None, _) => Ok(HttpFile::Temporary(Vec::new())),
+            _ => Err(Error::new(
+                Some(ffi::SQLITE_CANTOPEN as isize),
+                Some("only main databases can be served over HTTP".into()),
+            )),
+        }
+    }
+
+    #[inline]
+    fn delete(&self, _: &str) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn exists(&self, _: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl File for HttpFile {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        match self {
+            HttpFile::Remote(remote) => remote.read(offset, buffer),
+            HttpFile::Temporary(data) => Ok(read_buffer(data, offset, buffer)),
+        }
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        match self {
+            HttpFile::Remote(_) => Err(Error::new(Some(ffi::SQLITE_READONLY as isize), None)),
+            HttpFile::Temporary(data) => {
+                write_buffer(data, offset, buffer);
+                Ok(())
+            }
+        }
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        match self {
+            HttpFile::Remote(_) => Err(Error::new(Some(ffi::SQLITE_READONLY as isize), None)),
+            HttpFile::Temporary(data) => {
+                data.truncate(size as usize);
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        match self {
+            HttpFile::Remote(remote) => Ok(remote.size),
+            HttpFile::Temporary(data) => Ok(data.len() as u64),
+        }
+    }
+
+    fn characteristics(&mut self) -> i32 {
+        match self {
+            HttpFile::Remote(_) => ffi::SQLITE_IOCAP_IMMUTABLE,
+            HttpFile::Temporary(_) => 0,
+        }
+    }
+}
+
+impl Remote {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let size = self.chunk_size as u64;
+        let mut count = 0;
+        while count < buffer.len() {
+            let position = offset + count as u64;
+            if position >= self.size {
+                break;
+            }
+            let chunk = self.chunk(position / size)?;
+            let start = (position % size) as usize;
+            if start >= chunk.len() {
+                break;
+            }
+            let length = (buffer.len() - count).min(chunk.len() - start);
+            buffer[count..(count + length)].copy_from_slice(&chunk[start..(start + length)]);
+            count += length;
+        }
+        Ok(count)
+    }
+
+    fn chunk(&mut self, index: u64) -> Result<&[u8]> {
+        if let Some(position) = self.cache.iter().position(|(other, _)| *other == index) {
+            let entry = self.cache.remove(position).unwrap();
+            self.cache.push_front(entry);
+        } else {
+            let start = index * self.chunk_size as u64;
+            let end = (start + self.chunk_size as u64).min(self.size);
+            let data = self.fetch(start, end)?;
+            if self.cache.len() >= self.cache_size.max(1) {
+                self.cache.pop_back();
+            }
+            self.cache.push_front((index, data));
+        }
+        Ok(&self.cache[0].1)
+    }
+
+    fn fetch(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let response = self
+            .agent
+            .get(&self.url)
+            .set(
+                "Range",
+                &format!("bytes={}-{}", start, end.max(start + 1) - 1),
+            )
+            .call()
+            .map_err(from_http)?;
+        if response.status() != 206 {
+            return Err(Error::new(
+                Some(ffi::SQLITE_IOERR as isize),
+                Some(format!(
+                    "the server does not support range requests ({})",
+                    self.url
+                )),
+            ));
+        }
+        let mut data = Vec::with_capacity((end - start) as usize);
+        response
+            .into_reader()
+            .take(end - start)
+            .read_to_end(&mut data)
+            .map_err(from_io)?;
+        Ok(data)
+    }
+
+    fn fetch_size(&self) -> Result<u64> {
+        let response = self
+            .agent
+            .get(&self.url)
+            .set("Range", "bytes=0-0")
+            .call()
+            .map_err(from_http)?;
+        let size = match response.status() {
+            206 => response
+                .header("Content-Range")
+                .and_then(|value| value.rsplit('/').next())
+                .and_then(|value| value.trim().parse().ok()),
+            _ => response
+                .header("Content-Length")
+                .and_then(|value| value.trim().parse().ok()),
+        };
+        match size {
+            Some(size) => Ok(size),
+            _ => raise!(
+                "failed to determine the size of a remote database ({})",
+                self.url
+            ),
+        }
+    }
+}
+
+fn from_http(error: ureq::Error) -> Error {
+    Error::new(Some(ffi::SQLITE_IOERR as isize), Some(error.to_string()))
+}
+
+fn from_io(error: std::io::Error) -> Error {
+    Error::new(Some(ffi::SQLITE_IOERR as isize), Some(error.to_string()))
+}
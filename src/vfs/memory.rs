@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::error::{Error, Result};
+use crate::vfs::{read_buffer, write_buffer, File, FileFlags, LockLevel, Vfs};
+
+/// A virtual file system keeping files in memory.
+///
+/// The content of each file is a byte buffer that can be inspected and modified from Rust via
+/// `buffer`, which makes it possible to inject or extract raw databases without touching the
+/// file system. Clones share the same files, so a clone can be kept after registration.
+#[derive(Clone, Default)]
+pub struct MemoryVfs {
+    files: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+/// A file opened by a virtual file system keeping files in memory.
+pub struct MemoryFile {
+    path: Option<String>,
+    entry: Entry,
+    level: LockLevel,
+    delete: Option<MemoryVfs>,
+}
+
+#[derive(Clone, Default)]
+struct Entry {
+    data: Arc<RwLock<Vec<u8>>>,
+    locks: Arc<Mutex<Locks>>,
+}
+
+#[derive(Default)]
+struct Locks {
+    shared: usize,
+    reserved: bool,
+    pending: bool,
+    exclusive: bool,
+}
+
+impl MemoryVfs {
+    /// Create a virtual file system.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the buffer of a file, creating an empty one if the file does not exist.
+    ///
+    /// The buffer should not be modified while the file is used by a connection.
+    pub fn buffer<T: AsRef<str>>(&self, path: T) -> Arc<RwLock<Vec<u8>>> {
+        self.entry(path.as_ref()).data
+    }
+
+    /// Return the paths of the existing files.
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    fn entry(&self, path: &str) -> Entry {
+        self.files
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default()
+            .clone()
+    }
+}
+
+impl Vfs for MemoryVfs {
+    type File = MemoryFile;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        let entry = match path {
+            Some(path) => {
+                let entry = self.files.lock().unwrap().get(path).cloned();
+                match entry {
+                    Some(entry) => entry,
+                    _ if flags.is_create() => self.entry(path),
+                    _ => return Err(Error::new(Some(ffi::SQLITE_CANTOPEN as isize), None)),
+                }
+            }
+            _ => Entry::default(),
+        };
+        Ok(MemoryFile {
+            path: path.map(String::from),
+            entry,
+            level: LockLevel::None,
+            delete: if flags.is_delete_on_close() {
+                Some(self.clone())
+            } else {
+                None
+            },
+        })
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(self.files.lock().unwrap().contains_key(path))
+    }
+}
+
+impl File for MemoryFile {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        Ok(read_buffer(
+            &self.entry.data.read().unwrap(),
+            offset,
+            buffer,
+        ))
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        write_buffer(&mut self.entry.data.write().unwrap(), offset, buffer);
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.entry.data.write().unwrap().truncate(size as usize);
+        Ok(())
+    }
+
+    #[inline]
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        Ok(self.entry.data.read().unwrap().len() as u64)
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        if level <= self.level {
+            return Ok(());
+        }
+        let mut locks = self.entry.locks.lock().unwrap();
+        match level {
+            LockLevel::None => {}
+            LockLevel::Shared => {
+                if locks.pending || locks.exclusive {
+                    return busy();
+                }
+                locks.shared += 1;
+            }
+            LockLevel::Reserved => {
+                if locks.reserved {
+                    return busy();
+                }
+                locks.reserved = true;
+            }
+            LockLevel::Pending | LockLevel::Exclusive => {
+                if self.level < LockLevel::Pending {
+                    if locks.pending {
+                        return busy();
+                    }
+                    locks.pending = true;
+                    self.level = LockLevel::Pending;
+                }
+                if level == LockLevel::Exclusive {
+                    if locks.shared > 1 {
+                        return busy();
+                    }
+                    locks.exclusive = true;
+                }
+            }
+        }
+        self.level = level;
+        Ok(())
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        if level >= self.level {
+            return Ok(());
+        }
+        let mut locks = self.entry.locks.lock().unwrap();
+        if self.level >= LockLevel::Reserved {
+            locks.reserved = false;
+        }
+        if self.level >= LockLevel::Pending {
+            locks.pending = false;
+        }
+        if self.level == LockLevel::Exclusive {
+            locks.exclusive = false;
+        }
+        if level == LockLevel::None {
+            locks.shared -= 1;
+        }
+        self.level = level;
+        Ok(())
+    }
+
+    fn is_reserved(&mut self) -> Result<bool> {
+        let locks = self.entry.locks.lock().unwrap();
+        Ok(locks.reserved || locks.pending || locks.exclusive)
+    }
+
+    #[inline]
+    fn sector_size(&mut self) -> usize {
+        512
+    }
+
+    #[inline]
+    fn characteristics(&mut self) -> i32 {
+        ffi::SQLITE_IOCAP_SAFE_APPEND | ffi::SQLITE_IOCAP_SEQUENTIAL
+    }
+}
+
+impl Drop for MemoryFile {
+    fn drop(&mut self) {
+        let _ = self.unlock(LockLevel::None);
+        if let (Some(vfs), Some(path)) = (&self.delete, &self.path) {
+            let _ = vfs.delete(path);
+        }
+    }
+}
+
+fn busy() -> Result<()> {
+    Err(Error::new(Some(ffi::SQLITE_BUSY as isize), None))
+}
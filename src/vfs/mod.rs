@@ -0,0 +1,871 @@
+//! Virtual file systems.
+//!
+//! A [virtual file system][1] is the layer through which SQLite accesses storage. Implementing
+//! the `Vfs` and `File` traits and registering the implementation via `register` makes it
+//! possible to serve databases from places other than the local file system or to intercept the
+//! input and output of the default implementation, which is available as `OsVfs`. A connection
+//! selects a virtual file system by name via `Connection::open_with_flags_and_vfs`.
+//!
+//! The files exposed through this module do not support shared memory, which means that the WAL
+//! journal mode is available only with `PRAGMA locking_mode = EXCLUSIVE`.
+//!
+//! [1]: https://www.sqlite.org/vfs.html
+
+use core::ffi::{c_char, c_int, c_void};
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::error::{Error, Result};
+
+#[cfg(feature = "zip")]
+mod archive;
+mod encrypted;
+mod fault;
+#[cfg(feature = "http")]
+mod http;
+mod memory;
+mod quota;
+
+#[cfg(feature = "zip")]
+pub use archive::ZipVfs;
+#[cfg(feature = "chacha20poly1305")]
+pub use encrypted::ChaChaCipher;
+pub use encrypted::{Cipher, EncryptedFile, EncryptedVfs};
+pub use fault::{Fault, FaultFile, FaultVfs, Operation};
+#[cfg(feature = "http")]
+pub use http::HttpVfs;
+pub use memory::{MemoryFile, MemoryVfs};
+pub use quota::{QuotaFile, QuotaVfs};
+
+/// A virtual file system.
+pub trait Vfs: Send + Sync + 'static {
+    /// The type of files.
+    type File: File;
+
+    /// Open a file.
+    ///
+    /// The path is absent for temporary files, which are expected to be deleted on close.
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File>;
+
+    /// Delete a file.
+    fn delete(&self, path: &str) -> Result<()>;
+
+    /// Check if a file exists.
+    fn exists(&self, path: &str) -> Result<bool>;
+
+    /// Convert a path into its canonical form.
+    #[inline]
+    fn full_path(&self, path: &str) -> Result<String> {
+        Ok(path.to_string())
+    }
+}
+
+/// A file opened by a virtual file system.
+pub trait File: Send + 'static {
+    /// Read data at an offset.
+    ///
+    /// The function returns the number of bytes read, which is less than the size of the buffer
+    /// only if the end of the file has been reached.
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize>;
+
+    /// Write data at an offset.
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()>;
+
+    /// Truncate the file to a size.
+    fn truncate(&mut self, size: u64) -> Result<()>;
+
+    /// Flush the data to persistent storage.
+    fn sync(&mut self) -> Result<()>;
+
+    /// Return the size.
+    fn size(&mut self) -> Result<u64>;
+
+    /// Raise the lock to a level.
+    #[inline]
+    fn lock(&mut self, _: LockLevel) -> Result<()> {
+        Ok(())
+    }
+
+    /// Lower the lock to a level.
+    #[inline]
+    fn unlock(&mut self, _: LockLevel) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check if any connection holds a reserved or higher lock.
+    #[inline]
+    fn is_reserved(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Return the sector size.
+    #[inline]
+    fn sector_size(&mut self) -> usize {
+        4096
+    }
+
+    /// Return the device characteristics as a combination of `SQLITE_IOCAP_*` flags.
+    #[inline]
+    fn characteristics(&mut self) -> i32 {
+        0
+    }
+}
+
+/// Flags describing how a file is to be opened.
+#[derive(Clone, Copy, Debug)]
+pub struct FileFlags(c_int);
+
+/// The kind of a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileKind {
+    /// A main database.
+    MainDatabase,
+    /// A rollback journal of a main database.
+    MainJournal,
+    /// A temporary database.
+    TempDatabase,
+    /// A rollback journal of a temporary database.
+    TempJournal,
+    /// A transient database.
+    TransientDatabase,
+    /// A statement journal.
+    Subjournal,
+    /// A super journal of a multi-database transaction.
+    SuperJournal,
+    /// A write-ahead log.
+    Wal,
+    /// Another kind.
+    Other,
+}
+
+/// A lock level.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum LockLevel {
+    /// No lock.
+    None,
+    /// A shared lock.
+    Shared,
+    /// A reserved lock.
+    Reserved,
+    /// A pending lock.
+    Pending,
+    /// An exclusive lock.
+    Exclusive,
+}
+
+/// The default virtual file system of the platform.
+///
+/// The type can be used to wrap the files of the platform in order to intercept their input and
+/// output.
+pub struct OsVfs {
+    raw: *mut ffi::sqlite3_vfs,
+}
+
+/// A file opened by the default virtual file system of the platform.
+pub struct OsFile {
+    raw: Box<[u64]>,
+    name: ffi::sqlite3_filename,
+}
+
+thread_local! {
+    // The name of the file being opened on the thread as given by SQLite.
+    static OPENING: Cell<ffi::sqlite3_filename> = const { Cell::new(std::ptr::null()) };
+}
+
+#[repr(C)]
+struct Handle<T> {
+    base: ffi::sqlite3_file,
+    file: Option<T>,
+}
+
+#[repr(C)]
+struct Application<T: Vfs> {
+    parent: *mut ffi::sqlite3_vfs,
+    methods: ffi::sqlite3_io_methods,
+    vfs: T,
+}
+
+/// Register a virtual file system under a name.
+///
+/// If `default` is `true`, the virtual file system is used by all connections that do not
+/// specify one explicitly. A virtual file system stays registered until the end of the program.
+pub fn register<T: Vfs>(name: &str, vfs: T, default: bool) -> Result<()> {
+    let parent = unsafe { ffi::sqlite3_vfs_find(std::ptr::null()) };
+    if parent.is_null() {
+        raise!("failed to find the default virtual file system");
+    }
+    let name = str_to_cstr!(name);
+    let application = Box::new(Application {
+        vfs,
+        methods: ffi::sqlite3_io_methods {
+            iVersion: 1,
+            xClose: Some(close::<T::File>),
+            xRead: Some(read::<T::File>),
+            xWrite: Some(write::<T::File>),
+            xTruncate: Some(truncate::<T::File>),
+            xSync: Some(sync::<T::File>),
+            xFileSize: Some(file_size::<T::File>),
+            xLock: Some(lock::<T::File>),
+            xUnlock: Some(unlock::<T::File>),
+            xCheckReservedLock: Some(check_reserved_lock::<T::File>),
+            xFileControl: Some(file_control),
+            xSectorSize: Some(sector_size::<T::File>),
+            xDeviceCharacteristics: Some(device_characteristics::<T::File>),
+            xShmMap: None,
+            xShmLock: None,
+            xShmBarrier: None,
+            xShmUnmap: None,
+            xFetch: None,
+            xUnfetch: None,
+        },
+        parent,
+    });
+    let raw = Box::new(ffi::sqlite3_vfs {
+        iVersion: 2,
+        szOsFile: std::mem::size_of::<Handle<T::File>>() as c_int,
+        mxPathname: 1024,
+        pNext: std::ptr::null_mut(),
+        zName: name.into_raw(),
+        pAppData: Box::into_raw(application) as *mut c_void,
+        xOpen: Some(open::<T>),
+        xDelete: Some(delete::<T>),
+        xAccess: Some(access::<T>),
+        xFullPathname: Some(full_pathname::<T>),
+        xDlOpen: Some(dl_open),
+        xDlError: Some(dl_error),
+        xDlSym: Some(dl_sym),
+        xDlClose: Some(dl_close),
+        xRandomness: Some(randomness),
+        xSleep: Some(sleep),
+        xCurrentTime: Some(current_time),
+        xGetLastError: Some(get_last_error),
+        xCurrentTimeInt64: Some(current_time_int64),
+        xSetSystemCall: None,
+        xGetSystemCall: None,
+        xNextSystemCall: None,
+    });
+    let raw = Box::into_raw(raw);
+    unsafe {
+        ok!(ffi::sqlite3_vfs_register(raw, c_int::from(default)));
+    }
+    Ok(())
+}
+
+impl FileFlags {
+    /// Return the kind of the file.
+    pub fn kind(&self) -> FileKind {
+        match self.0 & 0x000fff00 {
+            ffi::SQLITE_OPEN_MAIN_DB => FileKind::MainDatabase,
+            ffi::SQLITE_OPEN_MAIN_JOURNAL => FileKind::MainJournal,
+            ffi::SQLITE_OPEN_TEMP_DB => FileKind::TempDatabase,
+            ffi::SQLITE_OPEN_TEMP_JOURNAL => FileKind::TempJournal,
+            ffi::SQLITE_OPEN_TRANSIENT_DB => FileKind::TransientDatabase,
+            ffi::SQLITE_OPEN_SUBJOURNAL => FileKind::Subjournal,
+            ffi::SQLITE_OPEN_SUPER_JOURNAL => FileKind::SuperJournal,
+            ffi::SQLITE_OPEN_WAL => FileKind::Wal,
+            _ => FileKind::Other,
+        }
+    }
+
+    /// Check if the file is to be created if it does not exist.
+    #[inline]
+    pub fn is_create(&self) -> bool {
+        self.0 & ffi::SQLITE_OPEN_CREATE != 0
+    }
+
+    /// Check if the file is to be deleted when closed.
+    #[inline]
+    pub fn is_delete_on_close(&self) -> bool {
+        self.0 & ffi::SQLITE_OPEN_DELETEONCLOSE != 0
+    }
+
+    /// Check if the file is to be opened for reading only.
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.0 & ffi::SQLITE_OPEN_READONLY != 0
+    }
+}
+
+impl LockLevel {
+    fn from_raw(level: c_int) -> Self {
+        match level {
+            ffi::SQLITE_LOCK_SHARED => LockLevel::Shared,
+            ffi::SQLITE_LOCK_RESERVED => LockLevel::Reserved,
+            ffi::SQLITE_LOCK_PENDING => LockLevel::Pending,
+            ffi::SQLITE_LOCK_EXCLUSIVE => LockLevel::Exclusive,
+            _ => LockLevel::None,
+        }
+    }
+
+    fn into_raw(self) -> c_int {
+        match self {
+            LockLevel::None => ffi::SQLITE_LOCK_NONE,
+            LockLevel::Shared => ffi::SQLITE_LOCK_SHARED,
+            LockLevel::Reserved => ffi::SQLITE_LOCK_RESERVED,
+            LockLevel::Pending => ffi::SQLITE_LOCK_PENDING,
+            LockLevel::Exclusive => ffi::SQLITE_LOCK_EXCLUSIVE,
+        }
+    }
+}
+
+impl OsVfs {
+    /// Find the current default virtual file system.
+    ///
+    /// The function should be called before registering another virtual file system as the
+    /// default one.
+    pub fn new() -> Result<Self> {
+        let raw = unsafe { ffi::sqlite3_vfs_find(std::ptr::null()) };
+        if raw.is_null() {
+            raise!("failed to find the default virtual file system");
+        }
+        Ok(OsVfs { raw })
+    }
+}
+
+impl Vfs for OsVfs {
+    type File = OsFile;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        // The name given by SQLite is passed on as is, since it carries the URI parameters and
+        // stays valid until the file is closed. Other paths are laid out the same way, and the
+        // resulting name is owned by the file.
+        let (name, owned) = match path {
+            Some(path) if OPENING.with(Cell::get) == path.as_ptr() as ffi::sqlite3_filename => {
+                (path.as_ptr() as ffi::sqlite3_filename, std::ptr::null())
+            }
+            Some(path) => {
+                let name = unsafe {
+                    ffi::sqlite3_create_filename(
+                        str_to_cstr!(path).as_ptr(),
+                        str_to_cstr!(format!("{path}-journal")).as_ptr(),
+                        str_to_cstr!(format!("{path}-wal")).as_ptr(),
+                        0,
+                        std::ptr::null_mut(),
+                    )
+                };
+                if name.is_null() {
+                    raise!("failed to allocate memory");
+                }
+                (name, name)
+            }
+            _ => (std::ptr::null(), std::ptr::null()),
+        };
+        let size = unsafe { (*self.raw).szOsFile as usize };
+        let mut raw = vec![0u64; size.div_ceil(8).max(1)].into_boxed_slice();
+        let mut output = 0;
+        let code = unsafe {
+            (*self.raw).xOpen.unwrap()(
+                self.raw,
+                name,
+                raw.as_mut_ptr() as *mut ffi::sqlite3_file,
+                flags.0,
+                &mut output,
+            )
+        };
+        // The file is closed and the name is released when the file is dropped.
+        let file = OsFile { raw, name: owned };
+        ok!(code);
+        Ok(file)
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        unsafe {
+            ok!((*self.raw).xDelete.unwrap()(
+                self.raw,
+                str_to_cstr!(path).as_ptr(),
+                0
+            ));
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        let mut output = 0;
+        unsafe {
+            ok!((*self.raw).xAccess.unwrap()(
+                self.raw,
+                str_to_cstr!(path).as_ptr(),
+                ffi::SQLITE_ACCESS_EXISTS,
+                &mut output,
+            ));
+        }
+        Ok(output != 0)
+    }
+
+    fn full_path(&self, path: &str) -> Result<String> {
+        let size = unsafe { (*self.raw).mxPathname as usize + 1 };
+        let mut buffer = vec![0 as c_char; size];
+        unsafe {
+            // The result might be SQLITE_OK_SYMLINK, which is a success too.
+            ok!((*self.raw).xFullPathname.unwrap()(
+                self.raw,
+                str_to_cstr!(path).as_ptr(),
+                size as c_int,
+                buffer.as_mut_ptr(),
+            ) & 0xff);
+            Ok(c_str_to_string!(buffer.as_ptr()))
+        }
+    }
+}
+
+unsafe impl Send for OsVfs {}
+
+unsafe impl Sync for OsVfs {}
+
+macro_rules! methods(
+    ($file:expr) => ({
+        let raw = $file.raw.as_mut_ptr() as *mut ffi::sqlite3_file;
+        (raw, &*(*raw).pMethods)
+    });
+);
+
+impl File for OsFile {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            match methods.xRead.unwrap()(
+                raw,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len() as c_int,
+                offset as ffi::sqlite3_int64,
+            ) {
+                ffi::SQLITE_OK => Ok(buffer.len()),
+                ffi::SQLITE_IOERR_SHORT_READ => {
+                    let size = self.size()?;
+                    Ok(size.saturating_sub(offset).min(buffer.len() as u64) as usize)
+                }
+                code => Err(Error::new(Some(code as isize), None)),
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xWrite.unwrap()(
+                raw,
+                buffer.as_ptr() as *const c_void,
+                buffer.len() as c_int,
+                offset as ffi::sqlite3_int64,
+            ));
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xTruncate.unwrap()(raw, size as ffi::sqlite3_int64));
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xSync.unwrap()(raw, ffi::SQLITE_SYNC_NORMAL));
+        }
+        Ok(())
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        let mut size = 0;
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xFileSize.unwrap()(raw, &mut size));
+        }
+        Ok(size as u64)
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xLock.unwrap()(raw, level.into_raw()));
+        }
+        Ok(())
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xUnlock.unwrap()(raw, level.into_raw()));
+        }
+        Ok(())
+    }
+
+    fn is_reserved(&mut self) -> Result<bool> {
+        let mut output = 0;
+        unsafe {
+            let (raw, methods) = methods!(self);
+            ok!(methods.xCheckReservedLock.unwrap()(raw, &mut output));
+        }
+        Ok(output != 0)
+    }
+
+    fn sector_size(&mut self) -> usize {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            methods.xSectorSize.unwrap()(raw) as usize
+        }
+    }
+
+    fn characteristics(&mut self) -> i32 {
+        unsafe {
+            let (raw, methods) = methods!(self);
+            methods.xDeviceCharacteristics.unwrap()(raw)
+        }
+    }
+}
+
+impl Drop for OsFile {
+    fn drop(&mut self) {
+        unsafe {
+            let raw = self.raw.as_mut_ptr() as *mut ffi::sqlite3_file;
+            if !(*raw).pMethods.is_null() {
+                (*(*raw).pMethods).xClose.unwrap()(raw);
+            }
+            ffi::sqlite3_free_filename(self.name);
+        }
+    }
+}
+
+unsafe impl Send for OsFile {}
+
+fn read_buffer(data: &[u8], offset: u64, buffer: &mut [u8]) -> usize {
+    let offset = (offset as usize).min(data.len());
+    let count = buffer.len().min(data.len() - offset);
+    buffer[..count].copy_from_slice(&data[offset..(offset + count)]);
+    count
+}
+
+fn write_buffer(data: &mut Vec<u8>, offset: u64, buffer: &[u8]) {
+    let end = offset as usize + buffer.len();
+    if data.len() < end {
+        data.resize(end, 0);
+    }
+    data[(offset as usize)..end].copy_from_slice(buffer);
+}
+
+fn code(error: Error, default: c_int) -> c_int {
+    error.code.map(|code| code as c_int).unwrap_or(default)
+}
+
+fn guard<F: FnOnce() -> c_int>(default: c_int, callback: F) -> c_int {
+    catch_unwind(AssertUnwindSafe(callback)).unwrap_or(default)
+}
+
+unsafe fn application<'l, T: Vfs>(vfs: *mut ffi::sqlite3_vfs) -> &'l Application<T> {
+    &*((*vfs).pAppData as *const Application<T>)
+}
+
+unsafe fn parent(vfs: *mut ffi::sqlite3_vfs) -> *mut ffi::sqlite3_vfs {
+    // The parent is the first field of every application regardless of the type.
+    *((*vfs).pAppData as *const *mut ffi::sqlite3_vfs)
+}
+
+unsafe fn file<'l, T: File>(file: *mut ffi::sqlite3_file) -> &'l mut T {
+    (*(file as *mut Handle<T>)).file.as_mut().unwrap()
+}
+
+unsafe fn path<'l>(path: *const c_char) -> Option<&'l str> {
+    if path.is_null() {
+        None
+    } else {
+        CStr::from_ptr(path).to_str().ok()
+    }
+}
+
+extern "C" fn open<T: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: ffi::sqlite3_filename,
+    raw: *mut ffi::sqlite3_file,
+    flags: c_int,
+    output: *mut c_int,
+) -> c_int {
+    guard(ffi::SQLITE_CANTOPEN, || unsafe {
+        let application = application::<T>(vfs);
+        let handle = raw as *mut Handle<T::File>;
+        (*handle).base.pMethods = std::ptr::null();
+        let previous = OPENING.with(|opening| opening.replace(name));
+        let result = application.vfs.open(path(name), FileFlags(flags));
+        OPENING.with(|opening| opening.set(previous));
+        match result {
+            Ok(file) => {
+                std::ptr::write(
+                    handle,
+                    Handle {
+                        base: ffi::sqlite3_file {
+                            pMethods: &application.methods,
+                        },
+                        file: Some(file),
+                    },
+                );
+                if !output.is_null() {
+                    *output = flags;
+                }
+                ffi::SQLITE_OK
+            }
+            Err(error) => code(error, ffi::SQLITE_CANTOPEN),
+        }
+    })
+}
+
+extern "C" fn delete<T: Vfs>(vfs: *mut ffi::sqlite3_vfs, name: *const c_char, _: c_int) -> c_int {
+    guard(ffi::SQLITE_IOERR_DELETE, || unsafe {
+        let path = match path(name) {
+            Some(path) => path,
+            _ => return ffi::SQLITE_IOERR_DELETE,
+        };
+        match application::<T>(vfs).vfs.delete(path) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_DELETE),
+        }
+    })
+}
+
+extern "C" fn access<T: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    _: c_int,
+    output: *mut c_int,
+) -> c_int {
+    guard(ffi::SQLITE_IOERR_ACCESS, || unsafe {
+        let path = match path(name) {
+            Some(path) => path,
+            _ => return ffi::SQLITE_IOERR_ACCESS,
+        };
+        match application::<T>(vfs).vfs.exists(path) {
+            Ok(value) => {
+                *output = c_int::from(value);
+                ffi::SQLITE_OK
+            }
+            Err(error) => code(error, ffi::SQLITE_IOERR_ACCESS),
+        }
+    })
+}
+
+extern "C" fn full_pathname<T: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    size: c_int,
+    output: *mut c_char,
+) -> c_int {
+    guard(ffi::SQLITE_CANTOPEN, || unsafe {
+        let path = match path(name) {
+            Some(path) => path,
+            _ => return ffi::SQLITE_CANTOPEN,
+        };
+        match application::<T>(vfs).vfs.full_path(path) {
+            Ok(path) if path.len() < size as usize => {
+                std::ptr::copy_nonoverlapping(path.as_ptr() as *const c_char, output, path.len());
+                *output.add(path.len()) = 0;
+                ffi::SQLITE_OK
+            }
+            Ok(_) => ffi::SQLITE_CANTOPEN,
+            Err(error) => code(error, ffi::SQLITE_CANTOPEN),
+        }
+    })
+}
+
+extern "C" fn dl_open(vfs: *mut ffi::sqlite3_vfs, name: *const c_char) -> *mut c_void {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xDlOpen.unwrap()(parent, name)
+    }
+}
+
+extern "C" fn dl_error(vfs: *mut ffi::sqlite3_vfs, size: c_int, output: *mut c_char) {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xDlError.unwrap()(parent, size, output)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+extern "C" fn dl_sym(
+    vfs: *mut ffi::sqlite3_vfs,
+    handle: *mut c_void,
+    symbol: *const c_char,
+) -> Option<unsafe extern "C" fn(*mut ffi::sqlite3_vfs, *mut c_void, *const c_char)> {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xDlSym.unwrap()(parent, handle, symbol)
+    }
+}
+
+extern "C" fn dl_close(vfs: *mut ffi::sqlite3_vfs, handle: *mut c_void) {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xDlClose.unwrap()(parent, handle)
+    }
+}
+
+extern "C" fn randomness(vfs: *mut ffi::sqlite3_vfs, size: c_int, output: *mut c_char) -> c_int {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xRandomness.unwrap()(parent, size, output)
+    }
+}
+
+extern "C" fn sleep(vfs: *mut ffi::sqlite3_vfs, microseconds: c_int) -> c_int {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xSleep.unwrap()(parent, microseconds)
+    }
+}
+
+extern "C" fn current_time(vfs: *mut ffi::sqlite3_vfs, output: *mut f64) -> c_int {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xCurrentTime.unwrap()(parent, output)
+    }
+}
+
+extern "C" fn get_last_error(
+    vfs: *mut ffi::sqlite3_vfs,
+    size: c_int,
+    output: *mut c_char,
+) -> c_int {
+    unsafe {
+        let parent = parent(vfs);
+        (*parent).xGetLastError.unwrap()(parent, size, output)
+    }
+}
+
+extern "C" fn current_time_int64(
+    vfs: *mut ffi::sqlite3_vfs,
+    output: *mut ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let parent = parent(vfs);
+        match (*parent).xCurrentTimeInt64 {
+            Some(function) if (*parent).iVersion >= 2 => function(parent, output),
+            _ => {
+                let mut value = 0.0;
+                let code = (*parent).xCurrentTime.unwrap()(parent, &mut value);
+                *output = (value * 86400000.0) as ffi::sqlite3_int64;
+                code
+            }
+        }
+    }
+}
+
+extern "C" fn close<T: File>(raw: *mut ffi::sqlite3_file) -> c_int {
+    guard(ffi::SQLITE_IOERR_CLOSE, || unsafe {
+        (*(raw as *mut Handle<T>)).file.take();
+        ffi::SQLITE_OK
+    })
+}
+
+extern "C" fn read<T: File>(
+    raw: *mut ffi::sqlite3_file,
+    buffer: *mut c_void,
+    size: c_int,
+    offset: ffi::sqlite3_int64,
+) -> c_int {
+    guard(ffi::SQLITE_IOERR_READ, || unsafe {
+        let buffer = std::slice::from_raw_parts_mut(buffer as *mut u8, size as usize);
+        match file::<T>(raw).read(offset as u64, buffer) {
+            Ok(count) if count < buffer.len() => {
+                buffer[count..].fill(0);
+                ffi::SQLITE_IOERR_SHORT_READ
+            }
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_READ),
+        }
+    })
+}
+
+extern "C" fn write<T: File>(
+    raw: *mut ffi::sqlite3_file,
+    buffer: *const c_void,
+    size: c_int,
+    offset: ffi::sqlite3_int64,
+) -> c_int {
+    guard(ffi::SQLITE_IOERR_WRITE, || unsafe {
+        let buffer = std::slice::from_raw_parts(buffer as *const u8, size as usize);
+        match file::<T>(raw).write(offset as u64, buffer) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_WRITE),
+        }
+    })
+}
+
+extern "C" fn truncate<T: File>(raw: *mut ffi::sqlite3_file, size: ffi::sqlite3_int64) -> c_int {
+    guard(ffi::SQLITE_IOERR_TRUNCATE, || unsafe {
+        match file::<T>(raw).truncate(size as u64) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_TRUNCATE),
+        }
+    })
+}
+
+extern "C" fn sync<T: File>(raw: *mut ffi::sqlite3_file, _: c_int) -> c_int {
+    guard(ffi::SQLITE_IOERR_FSYNC, || unsafe {
+        match file::<T>(raw).sync() {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_FSYNC),
+        }
+    })
+}
+
+extern "C" fn file_size<T: File>(
+    raw: *mut ffi::sqlite3_file,
+    output: *mut ffi::sqlite3_int64,
+) -> c_int {
+    guard(ffi::SQLITE_IOERR_FSTAT, || unsafe {
+        match file::<T>(raw).size() {
+            Ok(size) => {
+                *output = size as ffi::sqlite3_int64;
+                ffi::SQLITE_OK
+            }
+            Err(error) => code(error, ffi::SQLITE_IOERR_FSTAT),
+        }
+    })
+}
+
+extern "C" fn lock<T: File>(raw: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+    guard(ffi::SQLITE_IOERR_LOCK, || unsafe {
+        match file::<T>(raw).lock(LockLevel::from_raw(level)) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_LOCK),
+        }
+    })
+}
+
+extern "C" fn unlock<T: File>(raw: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+    guard(ffi::SQLITE_IOERR_UNLOCK, || unsafe {
+        match file::<T>(raw).unlock(LockLevel::from_raw(level)) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => code(error, ffi::SQLITE_IOERR_UNLOCK),
+        }
+    })
+}
+
+extern "C" fn check_reserved_lock<T: File>(
+    raw: *mut ffi::sqlite3_file,
+    output: *mut c_int,
+) -> c_int {
+    guard(ffi::SQLITE_IOERR_CHECKRESERVEDLOCK, || unsafe {
+        match file::<T>(raw).is_reserved() {
+            Ok(value) => {
+                *output = c_int::from(value);
+                ffi::SQLITE_OK
+            }
+            Err(error) => code(error, ffi::SQLITE_IOERR_CHECKRESERVEDLOCK),
+        }
+    })
+}
+
+extern "C" fn file_control(_: *mut ffi::sqlite3_file, _: c_int, _: *mut c_void) -> c_int {
+    ffi::SQLITE_NOTFOUND
+}
+
+extern "C" fn sector_size<T: File>(raw: *mut ffi::sqlite3_file) -> c_int {
+    guard(4096, || unsafe { file::<T>(raw).sector_size() as c_int })
+}
+
+extern "C" fn device_characteristics<T: File>(raw: *mut ffi::sqlite3_file) -> c_int {
+    guard(0, || unsafe { file::<T>(raw).characteristics() })
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{Error, Result};
+use crate::vfs::{File, FileFlags, FileKind, LockLevel, Vfs};
+
+/// A virtual file system limiting the size of the databases of another one.
+///
+/// The limit applies to the total size of a database together with its rollback journal and
+/// write-ahead log. Writes that would exceed the limit fail with `SQLITE_FULL`. Temporary files
+/// are not limited. Clones share the accounting.
+#[derive(Clone)]
+pub struct QuotaVfs<T> {
+    inner: T,
+    limit: u64,
+    groups: Arc<Mutex<HashMap<String, Group>>>,
+}
+
+/// A file opened by a virtual file system limiting the size of the databases of another one.
+pub struct QuotaFile<T> {
+    inner: T,
+    limit: u64,
+    key: Option<(String, String)>,
+    groups: Arc<Mutex<HashMap<String, Group>>>,
+}
+
+#[derive(Default)]
+struct Group {
+    files: HashMap<String, (u64, usize)>,
+}
+
+impl<T: Vfs> QuotaVfs<T> {
+    /// Wrap a virtual file system with a limit in bytes.
+    pub fn new(inner: T, limit: u64) -> Self {
+        QuotaVfs {
+            inner,
+            limit,
+            groups: Default::default(),
+        }
+    }
+
+    /// Return the number of bytes used by a database and its journals.
+    ///
+    /// Only the files that are currently open are taken into account.
+    pub fn usage<U: AsRef<str>>(&self, path: U) -> u64 {
+        self.groups
+            .lock()
+            .unwrap()
+            .get(path.as_ref())
+            .map(Group::size)
+            .unwrap_or(0)
+    }
+}
+
+impl<T: Vfs> Vfs for QuotaVfs<T> {
+    type File = QuotaFile<T::File>;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        let mut inner = self.inner.open(path, flags)?;
+        let key = match (path, flags.kind()) {
+            (Some(path), FileKind::MainDatabase) => Some((path.to_string(), path.to_string())),
+            (Some(path), FileKind::MainJournal) => path
+                .strip_suffix("-journal")
+                .map(|group| (group.to_string(), path.to_string())),
+            (Some(path), FileKind::Wal) => path
+                .strip_suffix("-wal")
+                .map(|group| (group.to_string(), path.to_string())),
+            _ => None,
+        };
+        if let Some((group, path)) = &key {
+            let size = inner.size()?;
+            let mut groups = self.groups.lock().unwrap();
+            let entry = groups
+                .entry(group.clone())
+                .or_default()
+                .files
+                .entry(path.clone())
+                .or_insert((0, 0));
+            entry.0 = size;
+            entry.1 += 1;
+        }
+        Ok(QuotaFile {
+            inner,
+            limit: self.limit,
+            key,
+            groups: self.groups.clone(),
+        })
+    }
+
+    #[inline]
+    fn delete(&self, path: &str) -> Result<()> {
+        self.inner.delete(path)
+    }
+
+    #[inline]
+    fn exists(&self, path: &str) -> Result<bool> {
+        self.inner.exists(path)
+    }
+
+    #[inline]
+    fn full_path(&self, path: &str) -> Result<String> {
+        self.inner.full_path(path)
+    }
+}
+
+impl<T: File> QuotaFile<T> {
+    fn resize(&mut self, size: u64) -> Result<()> {
+        let (group, path) = match &self.key {
+            Some(key) => key,
+            _ => return Ok(()),
+        };
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups.get_mut(group).unwrap();
+        let current = group.files.get(path).map(|(size, _)| *size).unwrap_or(0);
+        if size > current && group.size() - current + size > self.limit {
+            return Err(Error::new(Some(ffi::SQLITE_FULL as isize), None));
+        }
+        group.files.entry(path.clone()).or_insert((0, 1)).0 = size;
+        Ok(())
+    }
+}
+
+impl<T: File> File for QuotaFile<T> {
+    #[inline]
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        self.inner.read(offset, buffer)
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        let size = self.inner.size()?;
+        let end = offset + buffer.len() as u64;
+        if end > size {
+            self.resize(end)?;
+        }
+        self.inner.write(offset, buffer)
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.resize(size)?;
+        self.inner.truncate(size)
+    }
+
+    #[inline]
+    fn sync(&mut self) -> Result<()> {
+        self.inner.sync()
+    }
+
+    #[inline]
+    fn size(&mut self) -> Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    #[inline]
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    #[inline]
+    fn is_reserved(&mut self) -> Result<bool> {
+        self.inner.is_reserved()
+    }
+
+    #[inline]
+    fn sector_size(&mut self) -> usize {
+        self.inner.sector_size()
+    }
+
+    #[inline]
+    fn characteristics(&mut self) -> i32 {
+        self.inner.characteristics()
+    }
+}
+
+impl<T> Drop for QuotaFile<T> {
+    fn drop(&mut self) {
+        if let Some((group, path)) = &self.key {
+            let mut groups = self.groups.lock().unwrap();
+            let mut empty = false;
+            if let Some(entry) = groups.get_mut(group) {
+                if let Some((_, count)) = entry.files.get_mut(path) {
+                    *count -= 1;
+                    if *count == 0 {
+                        entry.files.remove(path);
+                    }
+                }
+                empty = entry.files.is_empty();
+            }
+            if empty {
+                groups.remove(group);
+            }
+        }
+    }
+}
+
+impl Group {
+    fn size(&self) -> u64 {
+        self.files.values().map(|(size, _)| size).sum()
+    }
+}
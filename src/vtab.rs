@@ -0,0 +1,578 @@
+//! Virtual tables.
+//!
+//! A [virtual table][1] is a table whose content is provided by the application instead of
+//! being stored in a database. Implementing the `Module`, `Table`, and `Cursor` traits and
+//! registering the implementation via `Connection::create_module` makes it possible to query
+//! arbitrary data sources with SQL, including joins and predicates.
+//!
+//! A registered module is eponymous, meaning that it can be used directly as a table under its
+//! own name, which is how table-valued functions work, and it can also back tables created via
+//! `CREATE VIRTUAL TABLE`. The arguments of a table-valued function are passed to the columns
+//! declared as `HIDDEN` in the schema returned by `Module::connect`; they are available in
+//! `Table::best_index` as equality constraints on those columns.
+//!
+//! [1]: https://www.sqlite.org/vtab.html
+
+use core::ffi::{c_char, c_int, c_void};
+use std::ffi::CStr;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+/// A module of virtual tables.
+pub trait Module: Send + 'static {
+    /// The type of tables.
+    type Table: Table;
+
+    /// Connect to a table.
+    ///
+    /// The arguments are the ones given in `CREATE VIRTUAL TABLE` after the module name, and they
+    /// are absent when the module is used directly. The function returns the schema of the table
+    /// as a `CREATE TABLE` statement, in which the name of the table is ignored, along with the
+    /// table itself.
+    fn connect(&self, arguments: &[&str]) -> Result<(String, Self::Table)>;
+}
+
+/// A virtual table.
+pub trait Table: Send + 'static {
+    /// The type of cursors.
+    type Cursor: Cursor;
+
+    /// Choose a strategy for a query.
+    ///
+    /// The default implementation leaves all constraints to SQLite, which results in a full
+    /// scan.
+    #[inline]
+    fn best_index(&self, _: &mut IndexInfo) -> Result<()> {
+        Ok(())
+    }
+
+    /// Open a cursor.
+    fn open(&self) -> Result<Self::Cursor>;
+
+    /// Insert a row and return its rowid.
+    ///
+    /// The rowid is absent if it has not been specified explicitly.
+    #[inline]
+    fn insert(&mut self, _: Option<i64>, _: &[Value]) -> Result<i64> {
+        raise!("the table is read-only");
+    }
+
+    /// Update a row given its old and new rowids.
+    #[inline]
+    fn update(&mut self, _: i64, _: i64, _: &[Value]) -> Result<()> {
+        raise!("the table is read-only");
+    }
+
+    /// Delete a row.
+    #[inline]
+    fn delete(&mut self, _: i64) -> Result<()> {
+        raise!("the table is read-only");
+    }
+}
+
+/// A cursor of a virtual table.
+pub trait Cursor: Send + 'static {
+    /// Start a scan.
+    ///
+    /// The index number and name are the ones chosen in `Table::best_index`, and the arguments
+    /// are the values of the constraints marked there as used.
+    fn filter(&mut self, index: i32, name: Option<&str>, arguments: &[Value]) -> Result<()>;
+
+    /// Advance to the next row.
+    fn next(&mut self) -> Result<()>;
+
+    /// Check if the scan has been completed.
+    fn eof(&self) -> bool;
+
+    /// Read a column of the current row.
+    ///
+    /// The first column has index 0.
+    fn column(&self, index: usize) -> Result<Value>;
+
+    /// Return the rowid of the current row.
+    fn rowid(&self) -> Result<i64>;
+}
+
+/// Information about a query used for choosing a strategy.
+pub struct IndexInfo<'l> {
+    raw: &'l mut ffi::sqlite3_index_info,
+}
+
+/// A constraint of a query.
+#[derive(Clone, Copy, Debug)]
+pub struct Constraint {
+    /// The column, which is absent for the rowid.
+    pub column: Option<usize>,
+    /// The operator.
+    pub operator: Operator,
+    /// The flag indicating whether the constraint can be used.
+    pub usable: bool,
+}
+
+/// An ordering of a query.
+#[derive(Clone, Copy, Debug)]
+pub struct OrderBy {
+    /// The column, which is absent for the rowid.
+    pub column: Option<usize>,
+    /// The flag indicating whether the order is descending.
+    pub descending: bool,
+}
+
+/// An operator of a constraint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operator {
+    /// `=`
+    Equal,
+    /// `>`
+    GreaterThan,
+    /// `<=`
+    LessOrEqual,
+    /// `<`
+    LessThan,
+    /// `>=`
+    GreaterOrEqual,
+    /// `MATCH`
+    Match,
+    /// `LIKE`
+    Like,
+    /// `GLOB`
+    Glob,
+    /// `REGEXP`
+    Regexp,
+    /// `!=`
+    NotEqual,
+    /// `IS NOT`
+    IsNot,
+    /// `IS NOT NULL`
+    IsNotNull,
+    /// `IS NULL`
+    IsNull,
+    /// `IS`
+    Is,
+    /// `LIMIT`
+    Limit,
+    /// `OFFSET`
+    Offset,
+    /// Another operator.
+    Other(u8),
+}
+
+#[repr(C)]
+struct Application<T: Module> {
+    methods: ffi::sqlite3_module,
+    module: T,
+}
+
+#[repr(C)]
+struct TableHandle<T> {
+    base: ffi::sqlite3_vtab,
+    table: T,
+}
+
+#[repr(C)]
+struct CursorHandle<T> {
+    base: ffi::sqlite3_vtab_cursor,
+    cursor: T,
+}
+
+impl<'l> IndexInfo<'l> {
+    /// Return the constraints.
+    pub fn constraints(&self) -> Vec<Constraint> {
+        (0..self.raw.nConstraint as usize)
+            .map(|index| unsafe {
+                let constraint = &*self.raw.aConstraint.add(index);
+                Constraint {
+                    column: column(constraint.iColumn),
+                    operator: Operator::from_raw(constraint.op),
+                    usable: constraint.usable != 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Return the orderings.
+    pub fn order_by(&self) -> Vec<OrderBy> {
+        (0..self.raw.nOrderBy as usize)
+            .map(|index| unsafe {
+                let order_by = &*self.raw.aOrderBy.add(index);
+                OrderBy {
+                    column: column(order_by.iColumn),
+                    descending: order_by.desc != 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Pass the value of a constraint to `Cursor::filter` as an argument.
+    ///
+    /// The first constraint and the first argument have index 0. If `omit` is `true`, SQLite does
+    /// not double-check the constraint.
+    pub fn use_constraint(&mut self, constraint: usize, argument: usize, omit: bool) {
+        assert!(constraint < self.raw.nConstraint as usize);
+        unsafe {
+            let usage = &mut *self.raw.aConstraintUsage.add(constraint);
+            usage.argvIndex = argument as c_int + 1;
+            usage.omit = u8::from(omit);
+        }
+    }
+
+    /// Set the index number passed to `Cursor::filter`.
+    #[inline]
+    pub fn set_index_number(&mut self, value: i32) {
+        self.raw.idxNum = value;
+    }
+
+    /// Set the index name passed to `Cursor::filter`.
+    pub fn set_index_name(&mut self, value: &str) -> Result<()> {
+        let value = str_to_cstr!(value);
+        unsafe {
+            if self.raw.needToFreeIdxStr != 0 {
+                ffi::sqlite3_free(self.raw.idxStr as *mut c_void);
+            }
+            self.raw.idxStr = ffi::sqlite3_mprintf(c"%s".as_ptr(), value.as_ptr());
+        }
+        self.raw.needToFreeIdxStr = 1;
+        Ok(())
+    }
+
+    /// Set the estimated cost of the strategy.
+    #[inline]
+    pub fn set_estimated_cost(&mut self, value: f64) {
+        self.raw.estimatedCost = value;
+    }
+
+    /// Set the estimated number of rows returned by the strategy.
+    #[inline]
+    pub fn set_estimated_rows(&mut self, value: i64) {
+        self.raw.estimatedRows = value as ffi::sqlite3_int64;
+    }
+
+    /// Indicate that the rows are returned in the requested order.
+    #[inline]
+    pub fn set_order_by_consumed(&mut self, value: bool) {
+        self.raw.orderByConsumed = c_int::from(value);
+    }
+}
+
+impl Operator {
+    fn from_raw(value: u8) -> Self {
+        match value as c_int {
+            ffi::SQLITE_INDEX_CONSTRAINT_EQ => Operator::Equal,
+            ffi::SQLITE_INDEX_CONSTRAINT_GT => Operator::GreaterThan,
+            ffi::SQLITE_INDEX_CONSTRAINT_LE => Operator::LessOrEqual,
+            ffi::SQLITE_INDEX_CONSTRAINT_LT => Operator::LessThan,
+            ffi::SQLITE_INDEX_CONSTRAINT_GE => Operator::GreaterOrEqual,
+            ffi::SQLITE_INDEX_CONSTRAINT_MATCH => Operator::Match,
+            ffi::SQLITE_INDEX_CONSTRAINT_LIKE => Operator::Like,
+            ffi::SQLITE_INDEX_CONSTRAINT_GLOB => Operator::Glob,
+            ffi::SQLITE_INDEX_CONSTRAINT_REGEXP => Operator::Regexp,
+            ffi::SQLITE_INDEX_CONSTRAINT_NE => Operator::NotEqual,
+            ffi::SQLITE_INDEX_CONSTRAINT_ISNOT => Operator::IsNot,
+            ffi::SQLITE_INDEX_CONSTRAINT_ISNOTNULL => Operator::IsNotNull,
+            ffi::SQLITE_INDEX_CONSTRAINT_ISNULL => Operator::IsNull,
+            ffi::SQLITE_INDEX_CONSTRAINT_IS => Operator::Is,
+            ffi::SQLITE_INDEX_CONSTRAINT_LIMIT => Operator::Limit,
+            ffi::SQLITE_INDEX_CONSTRAINT_OFFSET => Operator::Offset,
+            _ => Operator::Other(value),
+        }
+    }
+}
+
+pub fn create_module<T: Module>(connection: &Connection, name: &str, module: T) -> Result<()> {
+    let application = Box::new(Application {
+        methods: ffi::sqlite3_module {
+            iVersion: 1,
+            xCreate: Some(connect::<T>),
+            xConnect: Some(connect::<T>),
+            xBestIndex: Some(best_index::<T::Table>),
+            xDisconnect: Some(disconnect::<T::Table>),
+            xDestroy: Some(disconnect::<T::Table>),
+            xOpen: Some(open::<T::Table>),
+            xClose: Some(close::<<T::Table as Table>::Cursor>),
+            xFilter: Some(filter::<<T::Table as Table>::Cursor>),
+            xNext: Some(next::<<T::Table as Table>::Cursor>),
+            xEof: Some(eof::<<T::Table as Table>::Cursor>),
+            xColumn: Some(column_value::<<T::Table as Table>::Cursor>),
+            xRowid: Some(rowid::<<T::Table as Table>::Cursor>),
+            xUpdate: Some(update::<T::Table>),
+            xBegin: None,
+            xSync: None,
+            xCommit: None,
+            xRollback: None,
+            xFindFunction: None,
+            xRename: None,
+            xSavepoint: None,
+            xRelease: None,
+            xRollbackTo: None,
+            xShadowName: None,
+            xIntegrity: None,
+        },
+        module,
+    });
+    let application = Box::into_raw(application);
+    unsafe {
+        ok!(
+            connection.as_raw(),
+            ffi::sqlite3_create_module_v2(
+                connection.as_raw(),
+                str_to_cstr!(name).as_ptr(),
+                &(*application).methods,
+                application as *mut c_void,
+                Some(destroy::<T>),
+            )
+        );
+    }
+    Ok(())
+}
+
+fn column(value: c_int) -> Option<usize> {
+    if value < 0 {
+        None
+    } else {
+        Some(value as usize)
+    }
+}
+
+fn code(error: &Error) -> c_int {
+    error
+        .code
+        .map(|code| code as c_int)
+        .unwrap_or(ffi::SQLITE_ERROR)
+}
+
+fn guard<F: FnOnce() -> c_int>(callback: F) -> c_int {
+    catch_unwind(AssertUnwindSafe(callback)).unwrap_or(ffi::SQLITE_ERROR)
+}
+
+unsafe fn message(error: &Error) -> *mut c_char {
+    let message = error
+        .message
+        .as_deref()
+        .unwrap_or("an error in a virtual table");
+    match std::ffi::CString::new(message) {
+        Ok(message) => ffi::sqlite3_mprintf(c"%s".as_ptr(), message.as_ptr()),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+unsafe fn fail(vtab: *mut ffi::sqlite3_vtab, error: &Error) -> c_int {
+    if !(*vtab).zErrMsg.is_null() {
+        ffi::sqlite3_free((*vtab).zErrMsg as *mut c_void);
+    }
+    (*vtab).zErrMsg = message(error);
+    code(error)
+}
+
+unsafe fn arguments(count: c_int, values: *mut *mut ffi::sqlite3_value) -> Vec<Value> {
+    (0..count as usize)
+        .map(|index| crate::function::read(*values.add(index)))
+        .collect()
+}
+
+unsafe fn table<'l, T: Table>(vtab: *mut ffi::sqlite3_vtab) -> &'l mut T {
+    &mut (*(vtab as *mut TableHandle<T>)).table
+}
+
+unsafe fn cursor<'l, T: Cursor>(raw: *mut ffi::sqlite3_vtab_cursor) -> &'l mut T {
+    &mut (*(raw as *mut CursorHandle<T>)).cursor
+}
+
+extern "C" fn connect<T: Module>(
+    db: *mut ffi::sqlite3,
+    aux: *mut c_void,
+    count: c_int,
+    values: *const *const c_char,
+    output: *mut *mut ffi::sqlite3_vtab,
+    error: *mut *mut c_char,
+) -> c_int {
+    guard(|| unsafe {
+        let application = &*(aux as *const Application<T>);
+        let mut arguments = Vec::with_capacity((count as usize).saturating_sub(3));
+        for index in 3..count as usize {
+            match CStr::from_ptr(*values.add(index)).to_str() {
+                Ok(value) => arguments.push(value),
+                _ => return ffi::SQLITE_ERROR,
+            }
+        }
+        let result = application
+            .module
+            .connect(&arguments)
+            .and_then(|(schema, table)| {
+                let schema = str_to_cstr!(schema);
+                ok!(db, ffi::sqlite3_declare_vtab(db, schema.as_ptr()));
+                Ok(table)
+            });
+        match result {
+            Ok(table) => {
+                let handle = Box::new(TableHandle {
+                    base: ffi::sqlite3_vtab {
+                        pModule: std::ptr::null(),
+                        nRef: 0,
+                        zErrMsg: std::ptr::null_mut(),
+                    },
+                    table,
+                });
+                *output = Box::into_raw(handle) as *mut ffi::sqlite3_vtab;
+                ffi::SQLITE_OK
+            }
+            Err(value) => {
+                *error = message(&value);
+                code(&value)
+            }
+        }
+    })
+}
+
+extern "C" fn best_index<T: Table>(
+    vtab: *mut ffi::sqlite3_vtab,
+    raw: *mut ffi::sqlite3_index_info,
+) -> c_int {
+    guard(|| unsafe {
+        let mut info = IndexInfo { raw: &mut *raw };
+        match table::<T>(vtab).best_index(&mut info) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => fail(vtab, &error),
+        }
+    })
+}
+
+extern "C" fn disconnect<T: Table>(vtab: *mut ffi::sqlite3_vtab) -> c_int {
+    guard(|| unsafe {
+        drop(Box::from_raw(vtab as *mut TableHandle<T>));
+        ffi::SQLITE_OK
+    })
+}
+
+extern "C" fn open<T: Table>(
+    vtab: *mut ffi::sqlite3_vtab,
+    output: *mut *mut ffi::sqlite3_vtab_cursor,
+) -> c_int {
+    guard(|| unsafe {
+        match table::<T>(vtab).open() {
+            Ok(cursor) => {
+                let handle = Box::new(CursorHandle {
+                    base: ffi::sqlite3_vtab_cursor {
+                        pVtab: std::ptr::null_mut(),
+                    },
+                    cursor,
+                });
+                *output = Box::into_raw(handle) as *mut ffi::sqlite3_vtab_cursor;
+                ffi::SQLITE_OK
+            }
+            Err(error) => fail(vtab, &error),
+        }
+    })
+}
+
+extern "C" fn close<T: Cursor>(raw: *mut ffi::sqlite3_vtab_cursor) -> c_int {
+    guard(|| unsafe {
+        drop(Box::from_raw(raw as *mut CursorHandle<T>));
+        ffi::SQLITE_OK
+    })
+}
+
+extern "C" fn filter<T: Cursor>(
+    raw: *mut ffi::sqlite3_vtab_cursor,
+    index: c_int,
+    name: *const c_char,
+    count: c_int,
+    values: *mut *mut ffi::sqlite3_value,
+) -> c_int {
+    guard(|| unsafe {
+        let name = if name.is_null() {
+            None
+        } else {
+            CStr::from_ptr(name).to_str().ok()
+        };
+        match cursor::<T>(raw).filter(index, name, &arguments(count, values)) {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => fail((*raw).pVtab, &error),
+        }
+    })
+}
+
+extern "C" fn next<T: Cursor>(raw: *mut ffi::sqlite3_vtab_cursor) -> c_int {
+    guard(|| unsafe {
+        match cursor::<T>(raw).next() {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => fail((*raw).pVtab, &error),
+        }
+    })
+}
+
+extern "C" fn eof<T: Cursor>(raw: *mut ffi::sqlite3_vtab_cursor) -> c_int {
+    catch_unwind(AssertUnwindSafe(|| unsafe {
+        c_int::from(cursor::<T>(raw).eof())
+    }))
+    .unwrap_or(1)
+}
+
+extern "C" fn column_value<T: Cursor>(
+    raw: *mut ffi::sqlite3_vtab_cursor,
+    context: *mut ffi::sqlite3_context,
+    index: c_int,
+) -> c_int {
+    guard(|| unsafe {
+        match cursor::<T>(raw).column(index as usize) {
+            Ok(value) => {
+                crate::function::set_result(context, &value);
+                ffi::SQLITE_OK
+            }
+            Err(error) => fail((*raw).pVtab, &error),
+        }
+    })
+}
+
+extern "C" fn rowid<T: Cursor>(
+    raw: *mut ffi::sqlite3_vtab_cursor,
+    output: *mut ffi::sqlite3_int64,
+) -> c_int {
+    guard(|| unsafe {
+        match cursor::<T>(raw).rowid() {
+            Ok(value) => {
+                *output = value as ffi::sqlite3_int64;
+                ffi::SQLITE_OK
+            }
+            Err(error) => fail((*raw).pVtab, &error),
+        }
+    })
+}
+
+extern "C" fn update<T: Table>(
+    vtab: *mut ffi::sqlite3_vtab,
+    count: c_int,
+    values: *mut *mut ffi::sqlite3_value,
+    output: *mut ffi::sqlite3_int64,
+) -> c_int {
+    guard(|| unsafe {
+        let arguments = arguments(count, values);
+        let table = table::<T>(vtab);
+        let result = match &arguments[..] {
+            [Value::Integer(rowid)] => table.delete(*rowid),
+            [Value::Null, rowid, values @ ..] => {
+                let rowid = match rowid {
+                    Value::Integer(rowid) => Some(*rowid),
+                    _ => None,
+                };
+                table.insert(rowid, values).map(|rowid| {
+                    *output = rowid as ffi::sqlite3_int64;
+                })
+            }
+            [Value::Integer(old), Value::Integer(new), values @ ..] => {
+                table.update(*old, *new, values)
+            }
+            _ => return ffi::SQLITE_MISMATCH,
+        };
+        match result {
+            Ok(_) => ffi::SQLITE_OK,
+            Err(error) => fail(vtab, &error),
+        }
+    })
+}
+
+extern "C" fn destroy<T: Module>(data: *mut c_void) {
+    let _ = catch_unwind(|| unsafe { drop(Box::from_raw(data as *mut Application<T>)) });
+}
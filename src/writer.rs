@@ -0,0 +1,268 @@
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+
+/// A writer owning a connection on a dedicated thread.
+///
+/// Writes are submitted as callbacks, which are queued and run one after another. The callbacks
+/// queued within a flush interval are run in a single transaction, each within its own savepoint,
+/// so that a failed or panicking callback does not affect the others. Submitting returns once the
+/// transaction has been committed.
+pub struct Writer {
+    sender: Option<Sender<Job>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Options for creating a writer.
+#[derive(Clone, Copy, Debug)]
+pub struct WriterOptions {
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+/// A write that has been submitted but not necessarily completed.
+pub struct Pending<T> {
+    slot: Arc<Slot<T>>,
+}
+
+type Job = Box<dyn FnOnce(std::result::Result<&Connection, &Error>) -> (bool, Reply) + Send>;
+
+type Reply = Box<dyn FnOnce(&Result<()>) + Send>;
+
+struct Slot<T> {
+    state: Mutex<(Option<Result<T>>, Option<Waker>)>,
+    condition: Condvar,
+}
+
+struct Responder<T> {
+    slot: Arc<Slot<T>>,
+    done: bool,
+}
+
+impl Writer {
+    /// Open a connection to a new or existing database on a dedicated thread.
+    pub fn open<T: AsRef<Path>>(path: T, options: WriterOptions) -> Result<Writer> {
+        let path = path.as_ref().to_path_buf();
+        let (sender, receiver) = channel::<Job>();
+        let (status_sender, status_receiver) = sync_channel(1);
+        let thread = std::thread::spawn(move || {
+            let connection = match Connection::open(path) {
+                Ok(connection) => {
+                    let _ = status_sender.send(Ok(()));
+                    connection
+                }
+                Err(error) => {
+                    let _ = status_sender.send(Err(error));
+                    return;
+                }
+            };
+            run(&connection, receiver, options);
+        });
+        match status_receiver.recv() {
+            Ok(Ok(_)) => {}
+            Ok(Err(error)) => return Err(error),
+            _ => raise!("failed to start a writer"),
+        }
+        Ok(Writer {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// Submit a write and wait for it to be committed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sqlite::{Writer, WriterOptions};
+    ///
+    /// # let directory = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = directory.path().join("database.sqlite3");
+    /// let writer = Writer::open(path, WriterOptions::new())?;
+    /// writer.submit(|connection| connection.execute("CREATE TABLE users (name TEXT)"))?;
+    /// # Ok::<(), sqlite::Error>(())
+    /// ```
+    #[inline]
+    pub fn submit<F, T>(&self, callback: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.submit_async(callback).wait()
+    }
+
+    /// Submit a write without waiting for it to be committed.
+    ///
+    /// The result can be either awaited or waited for via `Pending::wait`.
+    pub fn submit_async<F, T>(&self, callback: F) -> Pending<T>
+    where
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let slot = Arc::new(Slot {
+            state: Mutex::new((None, None)),
+            condition: Condvar::new(),
+        });
+        let mut responder = Responder {
+            slot: slot.clone(),
+            done: false,
+        };
+        let job: Job = Box::new(move |connection| {
+            let result = match connection {
+                // A panic rolls back the savepoint of the write like an error does.
+                Ok(connection) => match catch_unwind(AssertUnwindSafe(|| callback(connection))) {
+                    Ok(result) => result,
+                    _ => Err(Error::new(None, Some("a write has panicked".into()))),
+                },
+                Err(error) => Err(error.clone()),
+            };
+            let failed = result.is_err();
+            let reply = Box::new(move |status: &Result<()>| match status {
+                Ok(_) => responder.respond(result),
+                Err(error) => responder.respond(result.and(Err(error.clone()))),
+            });
+            (failed, reply)
+        });
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(job);
+        }
+        Pending { slot }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl WriterOptions {
+    /// Create options for creating a writer.
+    ///
+    /// By default, up to 1000 writes are batched within a flush interval of 10 milliseconds.
+    #[inline]
+    pub fn new() -> Self {
+        WriterOptions {
+            batch_size: 1000,
+            flush_interval: Duration::from_millis(10),
+        }
+    }
+
+    /// Set the maximum number of writes per transaction.
+    pub fn with_batch_size(mut self, value: usize) -> Self {
+        self.batch_size = value;
+        self
+    }
+
+    /// Set the time to wait for further writes after the first one of a transaction.
+    pub fn with_flush_interval(mut self, value: Duration) -> Self {
+        self.flush_interval = value;
+        self
+    }
+}
+
+impl Default for WriterOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Pending<T> {
+    /// Wait for the write to be committed.
+    pub fn wait(self) -> Result<T> {
+        let mut state = self.slot.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.0.take() {
+                return result;
+            }
+            state = self.slot.condition.wait(state).unwrap();
+        }
+    }
+}
+
+impl<T> Future for Pending<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.state.lock().unwrap();
+        match state.0.take() {
+            Some(result) => Poll::Ready(result),
+            _ => {
+                state.1 = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Responder<T> {
+    fn respond(&mut self, result: Result<T>) {
+        self.done = true;
+        let mut state = self.slot.state.lock().unwrap();
+        state.0 = Some(result);
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+        self.slot.condition.notify_all();
+    }
+}
+
+impl<T> Drop for Responder<T> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.respond(Err(Error::new(None, Some("the writer has stopped".into()))));
+        }
+    }
+}
+
+fn run(connection: &Connection, receiver: Receiver<Job>, options: WriterOptions) {
+    while let Ok(job) = receiver.recv() {
+        let deadline = Instant::now() + options.flush_interval;
+        let mut jobs = vec![job];
+        while jobs.len() < options.batch_size.max(1) {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(timeout) {
+                Ok(job) => jobs.push(job),
+                Err(_) => break,
+            }
+        }
+        let mut status = connection.execute("BEGIN IMMEDIATE");
+        let mut replies = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            if status.is_ok() {
+                status = connection.execute("SAVEPOINT writer");
+            }
+            if let Err(error) = status.as_ref() {
+                replies.push(job(Err(error)).1);
+                continue;
+            }
+            let (failed, reply) = job(Ok(connection));
+            status = if failed {
+                connection.execute("ROLLBACK TO writer; RELEASE writer")
+            } else {
+                connection.execute("RELEASE writer")
+            };
+            replies.push(reply);
+        }
+        let status = status.and_then(|_| connection.execute("COMMIT"));
+        if status.is_err() {
+            let _ = connection.execute("ROLLBACK");
+        }
+        for reply in replies {
+            reply(&status);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use sqlite::Backoff;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn delay() {
+    let backoff = Backoff::new()
+        .with_initial(Duration::from_millis(10))
+        .with_maximum(Duration::from_millis(50))
+        .with_factor(3)
+        .with_attempts(5);
+    let delays = (0..5).map(|i| backoff.delay(i)).collect::<Vec<_>>();
+    assert_eq!(
+        delays,
+        vec![
+            Some(Duration::from_millis(10)),
+            Some(Duration::from_millis(30)),
+            Some(Duration::from_millis(50)),
+            Some(Duration::from_millis(50)),
+            None,
+        ],
+    );
+}
+
+#[test]
+fn retry() {
+    let backoff = Backoff::new().with_attempts(3);
+    let mut count = 0;
+    let error = backoff
+        .retry(|| -> sqlite::Result<()> {
+            count += 1;
+            Err(sqlite::Error::new(
+                Some(sqlite::ffi::SQLITE_BUSY as isize),
+                None,
+            ))
+        })
+        .unwrap_err();
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_BUSY as isize));
+    assert_eq!(count, 3);
+
+    let mut count = 0;
+    let value = ok!(backoff.retry(|| {
+        count += 1;
+        Ok::<_, sqlite::Error>(count)
+    }));
+    assert_eq!(value, 1);
+}
+
+#[test]
+fn sleep() {
+    assert!(sqlite::sleep(1) >= 1);
+}
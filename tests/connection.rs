@@ -1,4 +1,4 @@
-use sqlite::{Connection, OpenFlags, State};
+use sqlite::{Connection, OpenFlags, State, Value};
 
 mod common;
 
@@ -43,6 +43,26 @@ async fn open_thread_safe_async() {
     }
 }
 
+#[test]
+fn open_untrusted() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    assert!(Connection::open_untrusted(&path).is_err());
+    setup_users(&path);
+
+    let connection = ok!(Connection::open_untrusted(&path));
+    ok!(connection.execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)"));
+    assert!(connection.execute("SELECT \"missing\" FROM users").is_err());
+    ok!(connection.execute("PRAGMA writable_schema = ON"));
+    assert!(connection
+        .execute("UPDATE sqlite_master SET sql = '' WHERE name = 'users'")
+        .is_err());
+    ok!(connection.set_defensive(false));
+    ok!(connection.set_trusted_schema(true));
+}
+
 #[test]
 fn open_thread_safe_sync() {
     use std::sync::Arc;
@@ -74,6 +94,290 @@ fn execute() {
     }
 }
 
+#[test]
+fn execute_with_error_context() {
+    use sqlite::ErrorContext;
+
+    let mut connection = setup_users(":memory:");
+    let error = connection.execute("SELECT * FROM missing").unwrap_err();
+    assert_eq!(error.sql(), None);
+
+    connection.set_error_context(ErrorContext::Sql);
+    let error = connection.execute("SELECT * FROM missing").unwrap_err();
+    assert_eq!(error.sql(), Some("SELECT * FROM missing"));
+    assert_eq!(
+        error.to_string(),
+        "no such table: missing (code 1) in \"SELECT * FROM missing\"",
+    );
+    let error = connection.prepare("SELECT * FORM users").err().unwrap();
+    assert_eq!(error.sql(), Some("SELECT * FORM users"));
+    let error = connection
+        .execute("SELECT 1;\n SELECT * FROM missing;\n SELECT 2")
+        .unwrap_err();
+    assert_eq!(error.sql(), Some("SELECT * FROM missing;"));
+    let error = connection
+        .execute("SELECT 1; SELECT * FORM users; SELECT 2")
+        .unwrap_err();
+    assert_eq!(error.sql(), Some("SELECT * FORM users;"));
+
+    ok!(connection.execute("CREATE UNIQUE INDEX users_id ON users (id)"));
+    let mut statement = ok!(connection.prepare("INSERT INTO users (id) VALUES (?)"));
+    ok!(statement.bind((1, 1)));
+    let error = statement.next().unwrap_err();
+    assert_eq!(error.sql(), Some("INSERT INTO users (id) VALUES (?)"));
+}
+
+#[test]
+fn set_redaction() {
+    use sqlite::{ErrorContext, Redaction};
+
+    let mut connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "CREATE TABLE users (name TEXT, email TEXT, api_token TEXT, age INTEGER CHECK (age > 0))",
+    ));
+    connection.set_error_context(ErrorContext::ExpandedSql);
+    connection.set_redaction(Redaction::new().with_column("EMAIL").with_column("*_token"));
+
+    let query = "
+        INSERT INTO users /* :email */ VALUES (:name, :email, @api_token, ?4) -- ':name'
+    ";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind((":name", "O'Hara")));
+    ok!(statement.bind((":email", "ohara@example.com")));
+    ok!(statement.bind(("@api_token", "secret")));
+    ok!(statement.bind((4, -1)));
+    let error = statement.next().unwrap_err();
+    assert_eq!(
+        error.sql(),
+        Some(
+            "
+        INSERT INTO users /* :email */ VALUES ('O''Hara', :email, @api_token, -1) -- ':name'
+    "
+        ),
+    );
+    drop(statement);
+
+    connection.set_redaction(Redaction::new().with_positional());
+    let mut statement = ok!(connection.prepare("INSERT INTO users VALUES (?, ?, ?, ?)"));
+    ok!(statement.bind(("Alice", "alice@example.com", 1.5, 0)));
+    let error = statement.next().unwrap_err();
+    assert_eq!(error.sql(), Some("INSERT INTO users VALUES (?, ?, ?, ?)"),);
+}
+
+#[test]
+fn execute_with() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute_with(
+        "INSERT INTO users (id, name, age) VALUES (?, ?, ?)",
+        (2, "Bob", 69.42),
+    ));
+    ok!(connection.execute_with("DELETE FROM users WHERE id = ?", (1, 1)));
+    let mut statement = ok!(connection.prepare("SELECT id, name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 2);
+    assert_eq!(ok!(statement.read::<String, _>(1)), "Bob");
+    assert_eq!(ok!(statement.next()), State::Done);
+    drop(statement);
+
+    assert!(connection
+        .execute_with("INSERT INTO missing (id) VALUES (?)", (1, 2))
+        .is_err());
+}
+
+#[test]
+fn query_row() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (2, 'Bob')"));
+    let row = ok!(connection.query_row("SELECT name FROM users WHERE id = ?", (1, 2)));
+    assert_eq!(row.read::<&str, _>("name"), "Bob");
+
+    let error = connection
+        .query_row("SELECT name FROM users WHERE id = ?", (1, 3))
+        .unwrap_err();
+    assert_eq!(
+        error.message.as_deref(),
+        Some("the statement has produced no rows")
+    );
+    assert!(connection
+        .query_row("SELECT name FROM users", &[] as &[Value])
+        .is_err());
+}
+
+#[test]
+fn query_optional() {
+    let connection = setup_users(":memory:");
+    let row = ok!(connection.query_optional("SELECT name FROM users WHERE id = ?", (1, 1)));
+    assert_eq!(row.unwrap().read::<&str, _>(0), "Alice");
+    let row = ok!(connection.query_optional("SELECT name FROM users WHERE id = ?", (1, 2)));
+    assert!(row.is_none());
+
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2)"));
+    let error = connection
+        .query_optional("SELECT id FROM users", &[] as &[Value])
+        .unwrap_err();
+    assert_eq!(
+        error.message.as_deref(),
+        Some("the statement has produced more than one row")
+    );
+}
+
+#[test]
+fn query_scalar() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (2, 'Bob')"));
+    let none = &[] as &[Value];
+    assert_eq!(
+        ok!(connection.query_scalar::<i64, _, _>("SELECT count(*) FROM users", none)),
+        2,
+    );
+    assert_eq!(
+        ok!(connection.query_scalar::<String, _, _>("SELECT name FROM users WHERE id = ?", (1, 2))),
+        "Bob",
+    );
+    assert_eq!(
+        ok!(connection
+            .query_scalar::<Option<f64>, _, _>("SELECT age FROM users WHERE id = ?", (1, 2))),
+        None,
+    );
+    assert!(connection
+        .query_scalar::<i64, _, _>("SELECT id FROM users WHERE id = ?", (1, 3))
+        .is_err());
+}
+
+#[test]
+fn exists() {
+    let connection = setup_users(":memory:");
+    assert!(ok!(
+        connection.exists("SELECT 1 FROM users WHERE name = ?", (1, "Alice"))
+    ));
+    assert!(!ok!(
+        connection.exists("SELECT 1 FROM users WHERE name = ?", (1, "Bob"))
+    ));
+
+    let count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    {
+        let count = count.clone();
+        ok!(connection.create_function("touch", 1, move |context| {
+            count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            context.set_result(1);
+            Ok(())
+        }));
+    }
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2), (3)"));
+    assert!(ok!(
+        connection.exists("SELECT touch(id) FROM users WHERE id > ?", (1, 0))
+    ));
+    assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[test]
+fn execute_ddl() {
+    use std::time::Duration;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = setup_users(&path);
+    let other = ok!(Connection::open(&path));
+    ok!(other.execute("BEGIN IMMEDIATE"));
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        ok!(other.execute("COMMIT"));
+    });
+    assert!(ok!(connection.execute_ddl(
+        "CREATE TABLE IF NOT EXISTS posts (title TEXT); CREATE INDEX posts_title ON posts (title)",
+    )));
+    ok!(thread.join());
+    assert!(!ok!(
+        connection.execute_ddl("CREATE TABLE IF NOT EXISTS posts (title TEXT)")
+    ));
+    assert!(connection
+        .execute_ddl("CREATE TABLE posts (title TEXT)")
+        .is_err());
+    ok!(connection.execute("INSERT INTO posts VALUES ('Hello')"));
+}
+
+#[test]
+fn execute_batch() {
+    let connection = ok!(Connection::open(":memory:"));
+    let script = "
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+        -- Comment
+        INSERT INTO users VALUES (1, 'Alice');
+    ";
+    assert_eq!(ok!(connection.execute_batch(script)), 2);
+
+    let script = "
+        INSERT INTO users VALUES (2, 'Bob');
+        INSERT INTO users VALUES (1, 'Carol');
+        INSERT INTO users VALUES (3, 'Dave');
+    ";
+    let error = connection.execute_batch(script).unwrap_err();
+    assert_eq!(error.index, 1);
+    assert_eq!(
+        error.offset,
+        script.find("INSERT INTO users VALUES (1").unwrap()
+    );
+    assert_eq!(error.sql, "INSERT INTO users VALUES (1, 'Carol');");
+    assert_eq!(error.error.extended_code(), Some(1555));
+    assert_eq!(
+        ok!(connection.query_scalar::<i64, _, _>("SELECT count(*) FROM users", &[] as &[Value])),
+        2
+    );
+
+    let script = "DELETE FROM users; SELECT nme FROM users; DELETE FROM users";
+    let error = connection.execute_batch(script).unwrap_err();
+    assert_eq!(error.index, 1);
+    assert_eq!(error.offset, 19);
+    assert_eq!(error.sql, "SELECT nme FROM users;");
+    assert_eq!(error.error.offset(), Some(26));
+    let error: sqlite::Error = error.into();
+    assert_eq!(error.sql(), Some("SELECT nme FROM users;"));
+}
+
+#[test]
+fn execute_with_offset() {
+    let connection = setup_users(":memory:");
+    let error = connection
+        .execute("SELECT 1; -- comment\nSELECT name FROM users WHERE nme = 'Alice'")
+        .unwrap_err();
+    assert_eq!(error.offset(), Some(50));
+    let error = connection.prepare("SELECT * FORM users").err().unwrap();
+    assert_eq!(error.offset(), Some(9));
+}
+
+#[test]
+fn into_raw() {
+    let connection = setup_users(":memory:");
+    connection.set_update_hook(|_, _, _, _| {}).keep();
+    let raw = connection.into_raw();
+    let connection = unsafe { Connection::from_raw(raw) };
+    assert_eq!(connection.as_raw(), raw);
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2)"));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 2);
+}
+
+#[test]
+fn from_raw_borrowed() {
+    let connection = setup_users(":memory:");
+    let count = std::sync::Arc::new(std::sync::Mutex::new(0));
+    {
+        let borrowed = unsafe { Connection::from_raw_borrowed(connection.as_raw()) };
+        let count = count.clone();
+        borrowed
+            .set_update_hook(move |_, _, _, _| *count.lock().unwrap() += 1)
+            .keep();
+        ok!(borrowed.execute("INSERT INTO users (id) VALUES (2)"));
+    }
+    ok!(connection.execute("INSERT INTO users (id) VALUES (3)"));
+    assert_eq!(*count.lock().unwrap(), 1);
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 3);
+}
+
 #[test]
 fn iterate() {
     macro_rules! pair(
@@ -97,6 +401,49 @@ fn iterate() {
     assert!(done);
 }
 
+#[test]
+fn iterate_with_early_exit() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2), (3)"));
+    let mut count = 0;
+    ok!(connection.iterate("SELECT id FROM users", |_| {
+        count += 1;
+        count < 2
+    }));
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn try_iterate() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2), (3)"));
+    let mut ids = Vec::new();
+    let error = connection
+        .try_iterate("SELECT id FROM users", |pairs| {
+            let id = pairs[0].1.unwrap();
+            if id == "2" {
+                return Err(sqlite::Error::new(None, Some("failed".into())));
+            }
+            ids.push(id.to_string());
+            Ok(true)
+        })
+        .unwrap_err();
+    assert_eq!(error.message.as_deref(), Some("failed"));
+    assert_eq!(ids, vec!["1"]);
+
+    let mut count = 0;
+    ok!(connection.try_iterate("SELECT id FROM users", |_| {
+        count += 1;
+        Ok(false)
+    }));
+    assert_eq!(count, 1);
+
+    let error = connection
+        .try_iterate("SELECT nothing FROM users", |_| Ok(true))
+        .unwrap_err();
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_ERROR as isize));
+}
+
 #[test]
 fn set_busy_handler() {
     use std::thread::spawn;
@@ -110,8 +457,8 @@ fn set_busy_handler() {
         .map(|_| {
             let path = path.to_path_buf();
             spawn(move || {
-                let mut connection = ok!(sqlite::open(&path));
-                ok!(connection.set_busy_handler(|_| true));
+                let connection = ok!(sqlite::open(&path));
+                let _hook = ok!(connection.set_busy_handler(|_| true));
                 let query = "INSERT INTO users VALUES (?, ?, ?, ?, ?)";
                 let mut statement = ok!(connection.prepare(query));
                 ok!(statement.bind((1, 2i64)));
@@ -130,6 +477,87 @@ fn set_busy_handler() {
     }
 }
 
+#[test]
+fn busy_statistics() {
+    use std::time::Duration;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    setup_users(&path);
+
+    let second = ok!(sqlite::open(&path));
+    let mut statement = ok!(second.prepare("SELECT * FROM users"));
+
+    let first = ok!(sqlite::open(&path));
+    ok!(first.execute("BEGIN EXCLUSIVE"));
+
+    let _hook = ok!(second.set_busy_handler(|attempt| {
+        std::thread::sleep(Duration::from_millis(1));
+        attempt < 2
+    }));
+    assert!(second.execute("SELECT * FROM users").is_err());
+    assert!(second.execute("SELECT * FROM users").is_err());
+
+    let statistics = second.busy_statistics();
+    assert_eq!(statistics.events(), 2);
+    assert_eq!(statistics.retries(), 4);
+    assert!(statistics.wait() >= Duration::from_millis(6));
+
+    second.reset_busy_statistics();
+    assert_eq!(second.busy_statistics().events(), 0);
+    assert_eq!(second.busy_statistics().wait(), Duration::ZERO);
+
+    ok!(second.set_busy_timeout(20));
+    assert!(statement.next().is_err());
+    let statistics = statement.busy_statistics();
+    assert_eq!(statistics.events(), 1);
+    assert!(statistics.retries() > 0);
+    assert!(statistics.wait() >= Duration::from_millis(15));
+    assert_eq!(second.busy_statistics().retries(), statistics.retries());
+
+    let _hook = ok!(second.set_busy_handler(|_| panic!()));
+    let error = second.execute("SELECT * FROM users").unwrap_err();
+    assert_eq!(error.code, Some(5));
+
+    ok!(first.execute("COMMIT"));
+    assert!(statement.reset().is_err());
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(statement.busy_statistics().events(), 1);
+}
+
+#[test]
+fn set_busy_backoff() {
+    use std::thread::spawn;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    setup_users(&path);
+
+    let guards = (0..10)
+        .map(|i| {
+            let path = path.to_path_buf();
+            spawn(move || {
+                let connection = ok!(sqlite::open(&path));
+                let backoff = sqlite::Backoff::new().with_attempts(1000);
+                let _hook = ok!(connection.set_busy_backoff(backoff));
+                let mut statement = ok!(connection.prepare("INSERT INTO users (id) VALUES (?)"));
+                ok!(statement.bind((1, i + 2)));
+                assert_eq!(ok!(statement.next()), State::Done);
+            })
+        })
+        .collect::<Vec<_>>();
+    for guard in guards {
+        ok!(guard.join());
+    }
+
+    let connection = ok!(sqlite::open(&path));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 11);
+}
+
 #[cfg(feature = "extension")]
 #[test]
 fn enable_extension() {
@@ -170,3 +598,776 @@ fn change_count() {
     assert_eq!(connection.change_count(), 2);
     assert_eq!(connection.total_change_count(), 5);
 }
+
+#[test]
+fn par_scan() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = ok!(Connection::open(directory.path().join("database.sqlite3")));
+    ok!(connection.execute(
+        "
+        PRAGMA journal_mode = WAL;
+        CREATE TABLE numbers (id INTEGER PRIMARY KEY, value INTEGER);
+        WITH RECURSIVE series(value) AS (SELECT 1 UNION ALL SELECT value + 1 FROM series LIMIT 1000)
+        INSERT INTO numbers (id, value) SELECT value * 3, value FROM series;
+        ",
+    ));
+    let partitions = ok!(connection.par_scan("numbers", "id", 7, |cursor| {
+        let mut values = Vec::new();
+        for row in cursor {
+            values.push(row?.read::<i64, _>("value"));
+        }
+        Ok(values)
+    }));
+    assert_eq!(partitions.len(), 7);
+    assert!(partitions.iter().all(|values| !values.is_empty()));
+    assert_eq!(partitions.concat(), (1..=1000).collect::<Vec<_>>());
+
+    let error = connection
+        .par_scan("numbers", "id", 2, |_| -> sqlite::Result<()> {
+            Err(sqlite::Error::new(None, Some("failed".into())))
+        })
+        .unwrap_err();
+    assert_eq!(error.message.as_deref(), Some("failed"));
+
+    ok!(connection.execute(
+        "
+        CREATE TABLE extremes (id INTEGER PRIMARY KEY);
+        INSERT INTO extremes VALUES (-9223372036854775808), (0), (9223372036854775807);
+        CREATE TABLE edges (id INTEGER PRIMARY KEY);
+        INSERT INTO edges VALUES (9223372036854775803), (9223372036854775807);
+        ",
+    ));
+    let read = |cursor: sqlite::CursorWithOwnership| -> sqlite::Result<Vec<i64>> {
+        cursor.map(|row| Ok(row?.read::<i64, _>("id"))).collect()
+    };
+    let partitions = ok!(connection.par_scan("extremes", "id", 3, read));
+    assert_eq!(partitions.concat(), vec![i64::MIN, 0, i64::MAX]);
+    let partitions = ok!(connection.par_scan("edges", "id", 4, read));
+    assert_eq!(partitions.len(), 3);
+    assert_eq!(partitions.concat(), vec![i64::MAX - 4, i64::MAX]);
+
+    let connection = setup_users(":memory:");
+    assert!(connection.par_scan("users", "id", 2, |_| Ok(())).is_err());
+    ok!(connection.execute("DELETE FROM users"));
+    assert!(ok!(connection.par_scan("users", "id", 2, |_| Ok(()))).is_empty());
+}
+
+#[test]
+fn warm_cache() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    ok!(ok!(Connection::open(&path)).execute(
+        "
+        CREATE TABLE numbers (value TEXT);
+        WITH RECURSIVE series(value) AS (SELECT 1 UNION ALL SELECT value + 1 FROM series LIMIT 1000)
+        INSERT INTO numbers SELECT printf('%0100d', value) FROM series;
+        ",
+    ));
+    let connection = ok!(Connection::open(&path));
+    let misses = || {
+        let (mut current, mut highest) = (0, 0);
+        unsafe {
+            sqlite::ffi::sqlite3_db_status(
+                connection.as_raw(),
+                sqlite::ffi::SQLITE_DBSTATUS_CACHE_MISS,
+                &mut current,
+                &mut highest,
+                1,
+            );
+        }
+        current
+    };
+    ok!(connection.execute("BEGIN"));
+    ok!(connection.warm_cache(["numbers"], Some(1000)));
+    assert!(misses() > 10);
+    ok!(connection.iterate("SELECT * FROM numbers", |_| true));
+    assert_eq!(misses(), 0);
+    ok!(connection.execute("COMMIT"));
+
+    let mut statement = ok!(connection.prepare("PRAGMA cache_size"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1000);
+    assert!(connection.warm_cache(["missing"], None).is_err());
+}
+
+#[test]
+fn schema_names() {
+    let connection = ok!(Connection::open(":memory:"));
+    assert_eq!(connection.schema_names(), ["main", "temp"]);
+    ok!(connection.execute(
+        "
+        ATTACH DATABASE ':memory:' AS first;
+        ATTACH DATABASE ':memory:' AS second;
+        DETACH DATABASE first;
+        ",
+    ));
+    assert_eq!(connection.schema_names(), ["main", "temp", "second"]);
+}
+
+#[test]
+fn pragma() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = ok!(Connection::open(directory.path().join("database.sqlite3")));
+    ok!(connection.pragma_set("user_version", 42));
+    assert_eq!(ok!(connection.pragma_query::<i64>("user_version")), 42);
+    ok!(connection.pragma_set("main.journal_mode", "WAL"));
+    assert_eq!(
+        ok!(connection.pragma_query::<String>("journal_mode")),
+        "wal"
+    );
+    ok!(connection.pragma_set("foreign_keys", "ON"));
+    assert_eq!(ok!(connection.pragma_query::<i64>("foreign_keys")), 1);
+    ok!(connection.execute("ATTACH DATABASE ':memory:' AS 'it''s'"));
+    ok!(connection.pragma_set("it's.user_version", 69));
+    assert_eq!(ok!(connection.pragma_query::<i64>("it's.user_version")), 69);
+    assert_eq!(ok!(connection.pragma_query::<i64>("user_version")), 42);
+    let options = ok!(connection.pragma_query_list::<String>("compile_options"));
+    assert!(options
+        .iter()
+        .any(|option| option.starts_with("THREADSAFE=")));
+    assert!(connection.pragma_set("user_version", Value::Null).is_err());
+    ok!(connection.execute("CREATE TABLE users (name TEXT)"));
+    ok!(connection.pragma_set("user_version = 0; DROP TABLE users; --", 1));
+    assert!(ok!(connection.table_list())
+        .iter()
+        .any(|table| table.name() == "users"));
+}
+
+#[test]
+fn table_list() {
+    use sqlite::TableKind;
+
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER) STRICT;
+        CREATE TABLE tags (name TEXT PRIMARY KEY) WITHOUT ROWID;
+        CREATE VIEW names AS SELECT name FROM users;
+        ",
+    ));
+    let tables = ok!(connection.table_list());
+    let find = |name: &str| tables.iter().find(|table| table.name() == name).unwrap();
+
+    let users = find("users");
+    assert_eq!(users.schema(), "main");
+    assert_eq!(users.kind(), TableKind::Table);
+    assert_eq!(users.column_count(), 3);
+    assert!(users.is_strict());
+    assert!(!users.is_without_rowid());
+
+    let tags = find("tags");
+    assert!(tags.is_without_rowid());
+    assert!(!tags.is_strict());
+
+    let names = find("names");
+    assert_eq!(names.kind(), TableKind::View);
+    assert_eq!(names.column_count(), 1);
+}
+
+#[test]
+fn rename_table_and_column() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "
+        PRAGMA legacy_alter_table = ON;
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE posts (user INTEGER REFERENCES users (id));
+        CREATE VIEW names AS SELECT name FROM users;
+        ",
+    ));
+    ok!(connection.rename_table("users", "people"));
+    ok!(connection.rename_column("people", "name", "full name"));
+    let mut statement = ok!(connection
+        .prepare("SELECT sql FROM sqlite_master WHERE name IN ('posts', 'names') ORDER BY name",));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert!(ok!(statement.read::<String, _>(0)).contains("\"full name\" FROM \"people\""));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert!(ok!(statement.read::<String, _>(0)).contains("REFERENCES \"people\""));
+    drop(statement);
+
+    let mut statement = ok!(connection.prepare("PRAGMA legacy_alter_table"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1);
+}
+
+#[test]
+fn rebuild_table() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age REAL);
+        CREATE TABLE posts (user INTEGER REFERENCES users (id));
+        CREATE INDEX users_name ON users (name);
+        CREATE VIEW names AS SELECT name FROM users;
+        INSERT INTO users VALUES (1, 'Alice', 42), (2, NULL, NULL);
+        INSERT INTO posts VALUES (1);
+        ",
+    ));
+    ok!(connection.rebuild_table(
+        "users",
+        "(id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+        "id, coalesce(name, '')",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT * FROM names ORDER BY name"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "");
+    drop(statement);
+    assert_eq!(ok!(connection.indexes("users"))[0].name(), "users_name");
+    assert_eq!(ok!(connection.table_list())[0].column_count(), 2);
+
+    let error = connection
+        .rebuild_table(
+            "users",
+            "(id INTEGER PRIMARY KEY, name TEXT)",
+            "id + 10, name",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("foreign key"));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users WHERE id < 10"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 2);
+    drop(statement);
+
+    let mut statement = ok!(connection.prepare("PRAGMA foreign_keys"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1);
+}
+
+#[test]
+fn indexes() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "
+        CREATE TABLE users (id INTEGER, name TEXT, email TEXT UNIQUE, deleted INTEGER);
+        CREATE INDEX users_active ON users (id) WHERE deleted = 0;
+        CREATE INDEX users_name ON users (id, lower(name));
+        ",
+    ));
+    let indexes = ok!(connection.indexes("users"));
+    assert_eq!(indexes.len(), 3);
+
+    let email = &indexes[0];
+    assert_eq!(email.name(), "sqlite_autoindex_users_1");
+    assert!(email.is_unique());
+    assert_eq!(email.columns(), [Some("email".to_string())]);
+    assert_eq!(email.sql(), None);
+
+    let active = &indexes[1];
+    assert_eq!(active.name(), "users_active");
+    assert_eq!(active.table(), "users");
+    assert_eq!(active.columns(), [Some("id".to_string())]);
+    assert!(active.is_partial());
+    assert!(!active.has_expressions());
+    assert!(!active.is_unique());
+
+    let name = &indexes[2];
+    assert_eq!(name.columns(), [Some("id".to_string()), None]);
+    assert!(name.has_expressions());
+    assert!(!name.is_partial());
+    assert_eq!(
+        name.sql(),
+        Some("CREATE INDEX users_name ON users (id, lower(name))"),
+    );
+}
+
+#[test]
+fn views_and_triggers() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute(
+        "
+        CREATE TABLE users (name TEXT);
+        CREATE TABLE log (name TEXT);
+        CREATE VIEW names AS SELECT name FROM users;
+        CREATE TRIGGER b AFTER INSERT ON users BEGIN INSERT INTO log VALUES (new.name); END;
+        CREATE TRIGGER a AFTER DELETE ON users BEGIN INSERT INTO log VALUES (old.name); END;
+        CREATE TRIGGER c AFTER DELETE ON log BEGIN SELECT 1; END;
+        ",
+    ));
+    let views = ok!(connection.views());
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].name(), "names");
+    assert_eq!(
+        views[0].sql(),
+        "CREATE VIEW names AS SELECT name FROM users"
+    );
+
+    let triggers = ok!(connection.triggers("users"));
+    let names = triggers
+        .iter()
+        .map(|trigger| trigger.name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(triggers[0].table(), "users");
+    assert!(triggers[0]
+        .sql()
+        .starts_with("CREATE TRIGGER a AFTER DELETE"));
+    assert!(ok!(connection.triggers("missing")).is_empty());
+}
+
+#[test]
+fn result_code() {
+    use sqlite::ResultCode;
+
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER PRIMARY KEY)"));
+    ok!(connection.execute("INSERT INTO users VALUES (1)"));
+    let error = connection
+        .execute("INSERT INTO users VALUES (1)")
+        .unwrap_err();
+    let code = error.result_code().unwrap();
+    assert_eq!(code, ResultCode::ConstraintPrimaryKey);
+    assert_eq!(code.as_raw(), 1555);
+    assert!(code.is_extended());
+    assert_eq!(code.primary(), ResultCode::Constraint);
+    assert!(!code.primary().is_extended());
+    assert_eq!(ResultCode::from_raw(4242), ResultCode::Other(4242));
+}
+
+#[test]
+fn text_encoding() {
+    use sqlite::Encoding;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let flags = OpenFlags::new()
+        .with_create()
+        .with_read_write()
+        .with_encoding(Encoding::Utf16be);
+    {
+        let connection = ok!(Connection::open_with_flags(&path, flags));
+        assert_eq!(ok!(connection.text_encoding()), Encoding::Utf16be);
+        ok!(connection.execute("CREATE TABLE users (name TEXT)"));
+    }
+    let flags = flags.with_encoding(Encoding::Utf8);
+    let connection = ok!(Connection::open_with_flags(&path, flags));
+    assert_eq!(ok!(connection.text_encoding()), Encoding::Utf16be);
+    assert_eq!(
+        ok!(sqlite::inspect_header(&path)).encoding(),
+        Encoding::Utf16be
+    );
+
+    let connection = ok!(Connection::open(":memory:"));
+    assert_eq!(ok!(connection.text_encoding()), Encoding::Utf8);
+}
+
+#[test]
+fn interrupt_handle() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread::{sleep, spawn};
+    use std::time::Duration;
+
+    let connection = ok!(Connection::open(":memory:"));
+    let handle = connection.interrupt_handle();
+    let done = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let done = done.clone();
+        spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                handle.interrupt();
+                sleep(Duration::from_millis(10));
+            }
+            handle
+        })
+    };
+    let mut statement = ok!(connection.prepare(
+        "WITH RECURSIVE numbers(value) AS (SELECT 1 UNION ALL SELECT value + 1 FROM numbers) \
+         SELECT count(*) FROM numbers",
+    ));
+    let error = statement.next().unwrap_err();
+    done.store(true, Ordering::SeqCst);
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_INTERRUPT as isize));
+
+    let handle = ok!(thread.join().map_err(|_| ()));
+    drop(statement);
+    drop(connection);
+    handle.interrupt();
+}
+
+#[test]
+fn last_insert_rowid() {
+    let connection = ok!(Connection::open(":memory:"));
+    assert_eq!(connection.last_insert_rowid(), 0);
+    ok!(connection.execute("CREATE TABLE users (name TEXT)"));
+    ok!(connection.execute("INSERT INTO users VALUES ('Alice')"));
+    assert_eq!(connection.last_insert_rowid(), 1);
+    ok!(connection.execute("INSERT INTO users (rowid, name) VALUES (42, 'Bob')"));
+    assert_eq!(connection.last_insert_rowid(), 42);
+}
+
+#[test]
+fn copy_table_to() {
+    use sqlite::CopyOptions;
+
+    let source = setup_users(":memory:");
+    ok!(source.execute("CREATE INDEX users_name ON users (name)"));
+    ok!(source.execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)"));
+    ok!(source.execute("INSERT INTO users VALUES (3, 'Carol', 69.42, NULL, NULL)"));
+
+    let target = ok!(Connection::open(":memory:"));
+    let options = CopyOptions::new().with_batch_size(2).with_indices();
+    assert_eq!(ok!(source.copy_table_to(&target, "users", options)), 3);
+    assert!(source.copy_table_to(&target, "users", options).is_err());
+    let options = options.with_replace();
+    assert_eq!(ok!(source.copy_table_to(&target, "users", options)), 3);
+    assert!(source.copy_table_to(&target, "missing", options).is_err());
+
+    let mut statement = ok!(target.prepare("SELECT name FROM sqlite_master WHERE type = 'index'"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "users_name");
+
+    let mut statement = ok!(target.prepare("SELECT * FROM users ORDER BY id"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>("name")), "Alice");
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>("photo")), vec![0x42, 0x69]);
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Option<f64>, _>("age")), None);
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>("name")), "Carol");
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let source = ok!(Connection::open(":memory:"));
+    ok!(source.execute(
+        "
+        CREATE TABLE items (name TEXT, size INTEGER AS (length(name)));
+        INSERT INTO items (rowid, name) VALUES (7, 'seven'), (42, 'forty-two');
+        ",
+    ));
+    let target = ok!(Connection::open(":memory:"));
+    assert_eq!(ok!(source.copy_table_to(&target, "items", options)), 2);
+    let mut statement = ok!(target.prepare("SELECT rowid, name, size FROM items ORDER BY rowid"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 7);
+    assert_eq!(ok!(statement.read::<i64, _>(2)), 5);
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 42);
+    assert_eq!(ok!(statement.read::<String, _>(1)), "forty-two");
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let source = ok!(Connection::open(":memory:"));
+    ok!(source.execute(
+        "
+        CREATE TABLE parents (id INTEGER PRIMARY KEY);
+        CREATE TABLE children (
+            parent INTEGER REFERENCES parents (id) DEFERRABLE INITIALLY DEFERRED
+        );
+        INSERT INTO children VALUES (1);
+        ",
+    ));
+    ok!(target.execute("PRAGMA foreign_keys = ON"));
+    ok!(target.execute("CREATE TABLE parents (id INTEGER PRIMARY KEY)"));
+    let options = CopyOptions::new().with_replace();
+    assert!(source.copy_table_to(&target, "children", options).is_err());
+    ok!(target.execute("BEGIN"));
+}
+
+#[test]
+fn capabilities() {
+    use temporary::Directory;
+
+    let connection = ok!(Connection::open(":memory:"));
+    let capabilities = ok!(connection.capabilities());
+    assert!(!capabilities.compile_options().is_empty());
+    assert!(capabilities.has_compile_option("THREADSAFE"));
+    assert!(capabilities.has_compile_option("SQLITE_THREADSAFE"));
+    assert!(!capabilities.has_compile_option("MISSING"));
+    assert!(capabilities.supports_journal_mode("delete"));
+    assert!(capabilities.supports_journal_mode("WAL"));
+    assert!(!capabilities.supports_journal_mode("missing"));
+
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = setup_users(directory.path().join("database.sqlite3"));
+    let capabilities = ok!(connection.capabilities());
+    assert!(capabilities.supports_journal_mode("wal"));
+    assert_eq!(ok!(std::fs::read_dir(directory.path())).count(), 1);
+}
+
+#[test]
+fn attach() {
+    use sqlite::AttachOptions;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("other & more.sqlite3");
+    setup_users(&path);
+
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.attach(&path, "other \"schema\""));
+    ok!(connection.execute(r#"INSERT INTO "other ""schema"""."users" (id) VALUES (2)"#));
+    ok!(connection.detach("other \"schema\""));
+    assert!(connection.detach("other \"schema\"").is_err());
+
+    let flags = OpenFlags::new().with_create().with_read_write().with_uri();
+    let connection = ok!(Connection::open_with_flags(":memory:", flags));
+    let options = AttachOptions::new().with_parameter("mode", "ro");
+    ok!(connection.attach_with_options(&path, "other", &options));
+    let mut statement = ok!(connection.prepare("SELECT COUNT(*) FROM other.users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 2);
+    assert!(connection
+        .execute("INSERT INTO other.users (id) VALUES (3)")
+        .is_err());
+}
+
+#[test]
+fn backup() {
+    use sqlite::Backup;
+
+    let source = setup_users(":memory:");
+    let destination = ok!(Connection::open(":memory:"));
+    {
+        let mut backup = ok!(Backup::new(&source, &destination));
+        while !ok!(backup.step(1)) {
+            assert!(backup.remaining() < backup.page_count());
+        }
+        assert_eq!(backup.remaining(), 0);
+    }
+    let mut statement = ok!(destination.prepare("SELECT name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+}
+
+#[test]
+fn backup_to_path() {
+    use sqlite::BackupOptions;
+    use std::time::Duration;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("backup.sqlite3");
+
+    let source = setup_users(":memory:");
+    for id in 2..1000 {
+        ok!(source.execute(format!(
+            "INSERT INTO users VALUES ({id}, 'Bob', NULL, randomblob(100), NULL)"
+        )));
+    }
+    let options = BackupOptions::new()
+        .with_pages_per_step(5)
+        .with_pause(Duration::from_millis(1));
+    ok!(source.backup_to_path(&path, &options));
+
+    let connection = ok!(Connection::open(&path));
+    let mut statement = ok!(connection.prepare("SELECT COUNT(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 999);
+}
+
+#[test]
+fn serialize() {
+    let source = setup_users(":memory:");
+    let data = ok!(source.serialize("main"));
+    assert!(data.starts_with(b"SQLite format 3\0"));
+    assert!(source.serialize("missing").is_err());
+
+    let target = ok!(Connection::open(":memory:"));
+    ok!(target.deserialize("main", &data));
+    ok!(target.execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)"));
+    let mut statement = ok!(target.prepare("SELECT name FROM users ORDER BY id"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Bob");
+    drop(statement);
+    assert!(ok!(target.serialize("main")).len() >= data.len());
+
+    let empty = ok!(Connection::open(":memory:"));
+    assert!(ok!(empty.serialize("main")).is_empty());
+    assert!(target.deserialize("missing", &data).is_err());
+}
+
+#[test]
+fn busy_statements() {
+    let mut connection = setup_users(":memory:");
+    assert!(connection.busy_statements().is_empty());
+    {
+        let mut statement = ok!(connection.prepare("SELECT * FROM users"));
+        let statements = connection.busy_statements();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].sql(), "SELECT * FROM users");
+        assert!(!statements[0].is_busy());
+        assert_eq!(ok!(statement.next()), State::Row);
+        assert!(connection.busy_statements()[0].is_busy());
+        assert_eq!(connection.strong_statement_count(), 1);
+    }
+    assert_eq!(connection.strong_statement_count(), 0);
+    assert!(connection.busy_statements().is_empty());
+    connection.set_leak_detection(true);
+}
+
+#[test]
+fn set_tag() {
+    use sqlite::{Trace, TraceFlags};
+    use std::sync::{Arc, Mutex};
+
+    let connection = ok!(Connection::open(":memory:"));
+    assert_eq!(connection.tag(), None);
+    let tags = Arc::new(Mutex::new(Vec::new()));
+    let handle = connection.tag_handle();
+    let _hook = {
+        let tags = tags.clone();
+        connection.set_trace(TraceFlags::new().with_statement(), move |trace, tag| {
+            if let Trace::Statement(_) = trace {
+                tags.lock()
+                    .unwrap()
+                    .push((tag.map(String::from), handle.get()));
+            }
+        })
+    };
+    ok!(connection.execute("SELECT 1"));
+    connection.set_tag("tenant-42");
+    assert_eq!(connection.tag().as_deref(), Some("tenant-42"));
+    ok!(connection.execute("SELECT 1"));
+    let tag = Some("tenant-42".to_string());
+    assert_eq!(
+        *tags.lock().unwrap(),
+        [(None, None), (tag.clone(), tag.clone())],
+    );
+    assert_eq!(connection.busy_statistics().tag(), tag.as_deref());
+    let statement = ok!(connection.prepare("SELECT 1"));
+    assert_eq!(statement.busy_statistics().tag(), tag.as_deref());
+}
+
+#[test]
+fn prepare_with_flags() {
+    use sqlite::PrepareFlags;
+
+    let connection = setup_users(":memory:");
+    let query = "SELECT count(*) FROM pragma_table_info('users')";
+    let flags = PrepareFlags::new().with_persistent();
+    let mut statement = ok!(connection.prepare_with_flags(query, flags));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 5);
+
+    let flags = PrepareFlags::new().with_no_vtab();
+    assert!(connection.prepare_with_flags(query, flags).is_err());
+    assert!(connection
+        .prepare_with_flags("SELECT * FROM users", flags)
+        .is_ok());
+}
+
+#[test]
+fn prepare_next() {
+    let connection = ok!(Connection::open(":memory:"));
+    let mut script = "
+        CREATE TABLE users (id INTEGER, name TEXT);;
+        -- a comment
+        INSERT INTO users VALUES (?1, 'Alice');
+        INSERT INTO users VALUES (?1, 'Bob') /* another comment */
+    ";
+    let mut count = 0;
+    let mut id = 0;
+    while let Some(mut statement) = ok!(connection.prepare_next(&mut script)) {
+        if let Some(index) = ok!(statement.parameter_index("?1")) {
+            id += 1;
+            ok!(statement.bind((index, id)));
+        }
+        assert_eq!(ok!(statement.next()), State::Done);
+        count += 1;
+    }
+    assert_eq!(count, 3);
+    assert_eq!(script, "");
+    let mut statement = ok!(connection.prepare("SELECT sum(id) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 3);
+    drop(statement);
+
+    let mut script = "SELECT 1; SELECT * FROM missing; SELECT 2";
+    assert!(ok!(connection.prepare_next(&mut script)).is_some());
+    assert_eq!(script, " SELECT * FROM missing; SELECT 2");
+    assert!(connection.prepare_next(&mut script).is_err());
+    assert_eq!(script, " SELECT * FROM missing; SELECT 2");
+
+    let mut script = " -- nothing\n";
+    assert!(ok!(connection.prepare_next(&mut script)).is_none());
+}
+
+#[test]
+fn prepare_cached() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (name TEXT)"));
+    let insert = "INSERT INTO users VALUES (?)";
+    for name in ["Alice", "Bob"] {
+        let mut statement = ok!(connection.prepare_cached(insert));
+        ok!(statement.bind((1, name)));
+        assert_eq!(ok!(statement.next()), State::Done);
+    }
+    assert_eq!(connection.strong_statement_count(), 1);
+    {
+        let first = ok!(connection.prepare_cached(insert));
+        let second = ok!(connection.prepare_cached(insert));
+        assert_ne!(first.as_raw(), second.as_raw());
+    }
+    assert_eq!(connection.strong_statement_count(), 2);
+
+    let mut statement = ok!(connection.prepare_cached(insert));
+    assert!(statement.next().is_ok());
+    drop(statement);
+    let mut statement = ok!(connection.prepare_cached("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 3);
+    drop(statement);
+
+    connection.set_statement_cache_capacity(1);
+    assert_eq!(connection.strong_statement_count(), 1);
+    connection.clear_statement_cache();
+    assert_eq!(connection.strong_statement_count(), 0);
+    connection.set_statement_cache_capacity(0);
+    drop(ok!(connection.prepare_cached(insert)));
+    assert_eq!(connection.strong_statement_count(), 0);
+}
+
+#[test]
+fn strong_statement_count() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = setup_users(&path);
+    let statement = ok!(connection.prepare("SELECT * FROM users"));
+    assert_eq!(connection.strong_statement_count(), 1);
+    std::mem::forget(statement);
+    assert_eq!(connection.strong_statement_count(), 1);
+    drop(connection);
+
+    let connection = ok!(Connection::open(&path));
+    ok!(connection.execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)"));
+}
+
+#[test]
+fn try_clone() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = setup_users(&path);
+    ok!(connection.add_initializer(|connection| {
+        connection.create_function("answer", 0, |context| {
+            context.set_result(42i64);
+            Ok(())
+        })
+    }));
+    ok!(connection.add_initializer(|connection| connection.execute("PRAGMA cache_size = 42")));
+
+    let other = ok!(connection.try_clone());
+    let mut statement = ok!(other.prepare("SELECT answer(), name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 42);
+    assert_eq!(ok!(statement.read::<String, _>(1)), "Alice");
+    let mut statement = ok!(other.prepare("PRAGMA cache_size"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 42);
+    let another = ok!(other.try_clone());
+    assert!(another.prepare("SELECT answer()").is_ok());
+
+    let connection = ok!(Connection::open(":memory:"));
+    assert!(connection.try_clone().is_err());
+}
@@ -20,6 +20,14 @@ fn bind_iter() {
 
     let mut cursor = ok!(statement.iter().bind_iter(map));
     assert!(cursor.next().is_none());
+
+    let mut map = HashMap::<String, Value>::new();
+    map.insert(":name".into(), "Alice".into());
+    map.insert(":id".into(), 69.into());
+    let mut cursor = ok!(statement.iter().bind_iter(&map));
+    assert!(cursor.next().is_none());
+    let mut cursor = ok!(statement.iter().bind_iter(map));
+    assert!(cursor.next().is_none());
 }
 
 #[test]
@@ -44,6 +52,33 @@ fn iter() {
     assert_eq!(count, 2);
 }
 
+#[test]
+fn iter_with_arena() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users VALUES (2, 'Bob', NULL, X'01', NULL)"));
+    ok!(connection.execute("INSERT INTO users VALUES (3, 'Eve', NULL, X'03', NULL)"));
+    let query = "SELECT name, photo FROM users ORDER BY id";
+    let mut statement = ok!(connection.prepare(query));
+
+    let mut names = Vec::new();
+    let mut addresses = Vec::new();
+    for row in statement.iter().with_arena(1).map(|row| ok!(row)) {
+        let name = row.read::<&str, _>("name");
+        names.push(name.to_string());
+        addresses.push(name.as_ptr());
+        assert_eq!(row.read::<&[u8], _>("photo").len(), name.len() / 2);
+    }
+    assert_eq!(names, ["Alice", "Bob", "Eve"]);
+    assert!(addresses.iter().all(|address| *address == addresses[0]));
+
+    let rows = statement
+        .iter()
+        .with_arena(2)
+        .map(|row| Vec::<Value>::from(ok!(row)))
+        .collect::<Vec<_>>();
+    assert_eq!(rows[1], vec![Value::from("Bob"), Value::from(vec![1])]);
+}
+
 #[test]
 fn iter_column_count() {
     let connection = setup_english(":memory:");
@@ -102,6 +137,77 @@ fn iter_with_exception() {
     assert!(matches!(results[0], Err(_)));
 }
 
+#[test]
+fn map_into() {
+    struct User {
+        id: i64,
+        name: String,
+    }
+
+    impl TryFrom<sqlite::Row> for User {
+        type Error = sqlite::Error;
+
+        fn try_from(row: sqlite::Row) -> sqlite::Result<Self> {
+            Ok(User {
+                id: row.try_read("id")?,
+                name: row.try_read::<&str, _>("name")?.into(),
+            })
+        }
+    }
+
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)"));
+    let mut statement = ok!(connection.prepare("SELECT id, name FROM users ORDER BY id"));
+    let users = ok!(statement
+        .iter()
+        .map_into::<User>()
+        .collect::<sqlite::Result<Vec<_>>>());
+    assert_eq!(
+        users
+            .iter()
+            .map(|user| (user.id, user.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(1, "Alice"), (2, "Bob")],
+    );
+
+    let mut statement = ok!(connection.prepare("SELECT id FROM users"));
+    assert!(statement
+        .iter()
+        .map_into::<User>()
+        .all(|user| user.is_err()));
+}
+
+#[test]
+fn into_hash_map() {
+    let connection = setup_users(":memory:");
+    let mut statement = ok!(connection.prepare("SELECT id, name, email FROM users"));
+    let row = ok!(ok!(statement.iter().next()));
+    let map = HashMap::<String, Value>::from(row);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["id"], Value::Integer(1));
+    assert_eq!(map["name"], Value::String("Alice".into()));
+    assert_eq!(map["email"], Value::Null);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn into_json_map() {
+    let connection = setup_users(":memory:");
+    let mut statement = ok!(connection.prepare("SELECT * FROM users"));
+    let row = ok!(ok!(statement.iter().next()));
+    let map = serde_json::Map::from(row);
+    assert_eq!(
+        serde_json::Value::Object(map),
+        serde_json::json!({
+            "id": 1,
+            "name": "Alice",
+            "age": 42.69,
+            "photo": [0x42, 0x69],
+            "email": null,
+        }),
+    );
+}
+
 #[test]
 fn next_index() {
     let connection = setup_users(":memory:");
@@ -144,6 +250,22 @@ fn next_read_with_name_and_option() {
     assert!(row.read::<Option<&str>, _>("email").is_none());
 }
 
+#[test]
+fn next_read_with_name_and_null() {
+    let connection = setup_users(":memory:");
+    let query = "SELECT * FROM users";
+    let mut statement = ok!(connection.prepare(query));
+
+    let row = ok!(ok!(statement.iter().next()));
+    assert_eq!(row.read::<Value, _>("email"), Value::Null);
+    assert_eq!(row.read::<Option<Value>, _>("email"), None);
+    assert_eq!(row.read::<Option<Value>, _>("id"), Some(Value::Integer(1)));
+    row.read::<(), _>("email");
+    assert_eq!(row.read::<Option<()>, _>("email"), None);
+    assert!(row.try_read::<(), _>("id").is_err());
+    assert!(row.try_read::<Option<()>, _>("id").is_err());
+}
+
 #[test]
 fn next_take() {
     let connection = setup_users(":memory:");
@@ -155,6 +277,40 @@ fn next_take() {
     assert_eq!(row.take("name"), Value::Null);
 }
 
+#[test]
+fn next_try_take() {
+    let connection = setup_users(":memory:");
+    let query = "SELECT * FROM users";
+    let mut statement = ok!(connection.prepare(query));
+
+    let mut row = ok!(ok!(statement.iter().next()));
+    assert_eq!(ok!(row.try_take("name")), Value::String("Alice".into()));
+    assert_eq!(ok!(row.try_take(1)), Value::Null);
+    assert!(row.try_take("missing").is_err());
+    assert!(row.try_take(5).is_err());
+}
+
+#[test]
+fn next_split() {
+    let connection = setup_users(":memory:");
+    let query = "SELECT * FROM users";
+    let mut statement = ok!(connection.prepare(query));
+
+    let row = ok!(ok!(statement.iter().next()));
+    let (first, second) = ok!(row.split(["name", "id", "name"]));
+    assert_eq!(
+        Vec::<Value>::from(first),
+        vec![Value::String("Alice".into()), Value::Integer(1)],
+    );
+    assert_eq!(second.read::<f64, _>("age"), 42.69);
+    assert_eq!(second.read::<Option<&str>, _>(2), None);
+    assert!(!second.contains("name"));
+    assert!(!second.contains(3));
+
+    let row = ok!(ok!(statement.iter().reset()).next().unwrap());
+    assert!(row.split(["missing"]).is_err());
+}
+
 #[test]
 fn next_try_read_with_index() {
     let connection = setup_users(":memory:");
@@ -225,6 +381,50 @@ fn next_try_read_with_name_and_option() {
     assert!(ok!(row.try_read::<Option<&str>, _>("email")).is_none());
 }
 
+#[test]
+fn prefetch() {
+    let connection = ok!(sqlite::Connection::open_thread_safe(":memory:"));
+    ok!(connection.execute("CREATE TABLE numbers (value INTEGER)"));
+    ok!(connection.execute(
+        "WITH RECURSIVE series(value) AS (SELECT 1 UNION ALL SELECT value + 1 FROM series LIMIT 1000) \
+         INSERT INTO numbers SELECT value FROM series",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT value FROM numbers"));
+
+    let sum = ok!(statement.iter().prefetch(4, |rows| {
+        rows.map(|row| ok!(row).read::<i64, _>("value"))
+            .sum::<i64>()
+    }));
+    assert_eq!(sum, 500500);
+
+    let mut cursor = statement.iter();
+    let values = ok!(cursor.prefetch(0, |rows| {
+        rows.take(2)
+            .map(|row| ok!(row).read::<i64, _>(0))
+            .collect::<Vec<_>>()
+    }));
+    assert_eq!(values, [1, 2]);
+    assert_eq!(ok!(ok!(cursor.next())).read::<i64, _>(0), 4);
+
+    let flags = sqlite::OpenFlags::new().with_read_write().with_no_mutex();
+    let connection = ok!(sqlite::Connection::open_with_flags(":memory:", flags));
+    let mut statement = ok!(connection.prepare("SELECT 1"));
+    assert!(statement.iter().prefetch(1, |_| ()).is_err());
+}
+
+#[test]
+fn try_next_wide() {
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT 1, 2, 3, 4, 5, 6, 7, 8, 9"));
+    let values = ok!(ok!(statement.iter().try_next()));
+    assert_eq!(values.len(), 9);
+    assert_eq!(values[8], Value::Integer(9));
+    assert_eq!(
+        values.into_vec(),
+        (1..=9).map(Value::Integer).collect::<Vec<_>>()
+    );
+}
+
 #[test]
 fn try_next_try_into() {
     let connection = setup_users(":memory:");
@@ -0,0 +1,51 @@
+#![cfg(feature = "derive")]
+
+use sqlite::{Connection, SqlNewtype, State, Value};
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[derive(Clone, Copy, Debug, PartialEq, SqlNewtype)]
+struct UserId(i64);
+
+#[derive(Clone, Debug, PartialEq, SqlNewtype)]
+struct UserName(String);
+
+#[test]
+fn sql_newtype() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+
+    let mut statement = ok!(connection.prepare("INSERT INTO users VALUES (?, ?)"));
+    ok!(statement.bind((1, UserId(1))));
+    ok!(statement.bind((2, &UserName("Alice".into()))));
+    assert_eq!(ok!(statement.next()), State::Done);
+    ok!(statement.reset());
+    ok!(statement.bind((1, UserId(2))));
+    ok!(statement.bind((2, None::<UserName>)));
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let mut statement = ok!(connection.prepare("SELECT id, name FROM users ORDER BY id"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<UserId, _>(0)), UserId(1));
+    assert_eq!(
+        ok!(statement.read::<UserName, _>("name")),
+        UserName("Alice".into())
+    );
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Option<UserName>, _>(1)), None);
+
+    let rows = ok!(connection
+        .prepare("SELECT id, name FROM users ORDER BY id")
+        .map(|statement| statement.into_iter().collect::<Result<Vec<_>, _>>()));
+    let rows = ok!(rows);
+    assert_eq!(rows[0].read::<UserId, _>("id"), UserId(1));
+    assert_eq!(
+        rows[0].read::<UserName, _>("name"),
+        UserName("Alice".into())
+    );
+    assert!(rows[1].try_read::<UserName, _>("name").is_err());
+
+    assert_eq!(Value::from(UserId(42)), Value::Integer(42));
+    assert_eq!(ok!(UserId::try_from(&Value::Integer(42))), UserId(42));
+    assert!(UserId::try_from(&Value::Float(4.2)).is_err());
+}
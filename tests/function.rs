@@ -0,0 +1,256 @@
+use sqlite::{Aggregate, Connection, Result, State, Type, Value};
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[derive(Default)]
+struct Median(Vec<f64>);
+
+impl Aggregate for Median {
+    fn step(&mut self, arguments: &[Value]) -> Result<()> {
+        match &arguments[0] {
+            Value::Null => {}
+            value => self.0.push(
+                value
+                    .try_into::<f64>()
+                    .or_else(|_| value.try_into::<i64>().map(|value| value as f64))?,
+            ),
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Value> {
+        if self.0.is_empty() {
+            return Ok(Value::Null);
+        }
+        self.0.sort_by(|one, other| one.partial_cmp(other).unwrap());
+        let middle = self.0.len() / 2;
+        Ok(Value::Float(if self.0.len().is_multiple_of(2) {
+            (self.0[middle - 1] + self.0[middle]) / 2.0
+        } else {
+            self.0[middle]
+        }))
+    }
+}
+
+#[test]
+fn create_aggregate() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_aggregate("median", 1, Median::default));
+    ok!(connection.execute(
+        "
+        CREATE TABLE scores (team TEXT, value INTEGER);
+        INSERT INTO scores VALUES ('a', 1), ('a', 5), ('a', 3);
+        INSERT INTO scores VALUES ('b', 2), ('b', 4), ('b', NULL);
+        ",
+    ));
+    let mut statement =
+        ok!(connection
+            .prepare("SELECT team, median(value) FROM scores GROUP BY team ORDER BY team"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "a");
+    assert_eq!(ok!(statement.read::<f64, _>(1)), 3.0);
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "b");
+    assert_eq!(ok!(statement.read::<f64, _>(1)), 3.0);
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let mut statement = ok!(connection.prepare("SELECT median(value) FROM scores WHERE 0"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Value, _>(0)), Value::Null);
+}
+
+#[test]
+fn create_aggregate_error() {
+    struct Failing;
+
+    impl Aggregate for Failing {
+        fn step(&mut self, _: &[Value]) -> Result<()> {
+            Err(sqlite::Error::new(None, Some("failed to step".into())))
+        }
+
+        fn finalize(self) -> Result<Value> {
+            panic!()
+        }
+    }
+
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_aggregate("failing", -1, || Failing));
+    let error = ok!(connection.prepare("SELECT failing(1, 2)"))
+        .next()
+        .unwrap_err();
+    assert_eq!(error.message.as_deref(), Some("failed to step"));
+    let error = ok!(connection.prepare("SELECT failing() WHERE 0"))
+        .next()
+        .unwrap_err();
+    assert_eq!(error.message.as_deref(), Some("a function has panicked"));
+}
+
+#[test]
+fn create_function() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_function("describe", -1, |context| {
+        let mut kinds = Vec::new();
+        for index in 0..context.argument_count() {
+            kinds.push(format!("{:?}", context.argument_type(index)?));
+        }
+        context.set_result(kinds.join(","));
+        Ok(())
+    }));
+    ok!(connection.create_function("bytes", 1, |context| {
+        match context.argument(0)? {
+            Value::Integer(count) => {
+                context.set_blob_with(count as usize, |buffer| buffer.fill(42))
+            }
+            _ => context.set_null(),
+        }
+        Ok(())
+    }));
+    ok!(connection.create_function("fail", 0, |context| {
+        context.set_error("failed to compute", None);
+        Ok(())
+    }));
+
+    let mut statement = ok!(connection.prepare("SELECT describe(1, 2.0, 'a', X'00', NULL)"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(
+        ok!(statement.read::<String, _>(0)),
+        "Integer,Float,String,Binary,Null",
+    );
+
+    let mut statement = ok!(connection.prepare("SELECT bytes(3), bytes(3), bytes('a'), bytes(0)"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(0)), vec![42; 3]);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(1)), vec![42; 3]);
+    assert_eq!(ok!(statement.column_type(2)), Type::Null);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(3)), Vec::<u8>::new());
+
+    let error = ok!(connection.prepare("SELECT fail()")).next().unwrap_err();
+    assert_eq!(error.message.as_deref(), Some("failed to compute"));
+}
+
+#[test]
+fn create_overloaded_function() {
+    let connection = ok!(Connection::open(":memory:"));
+    let mut calls = 0;
+    ok!(
+        connection.create_overloaded_function("total", &[1, 2, -1], move |context| {
+            calls += 1;
+            let mut sum = 0;
+            for index in 0..context.argument_count() {
+                sum += i64::try_from(context.argument(index)?)?;
+            }
+            context.set_result(format!("{}:{}:{}", context.argument_count(), sum, calls));
+            Ok(())
+        })
+    );
+
+    let mut statement = ok!(connection.prepare("SELECT total(1), total(1, 2), total(1, 2, 3)"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "1:1:1");
+    assert_eq!(ok!(statement.read::<String, _>(1)), "2:3:2");
+    assert_eq!(ok!(statement.read::<String, _>(2)), "3:6:3");
+
+    assert!(connection
+        .create_overloaded_function("nothing", &[], |_| Ok(()))
+        .is_err());
+}
+
+#[test]
+fn freeze_time() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection
+        .execute("CREATE TABLE events (name TEXT, created_at TEXT DEFAULT CURRENT_TIMESTAMP)",));
+    ok!(connection.freeze_time(1_700_000_000));
+    ok!(connection.execute("INSERT INTO events (name) VALUES ('launch')"));
+
+    let mut statement = ok!(connection.prepare(
+        "SELECT created_at, CURRENT_DATE, CURRENT_TIME, datetime('now', '+1 day'), \
+         unixepoch(), strftime('%s'), julianday('NOW'), date('2000-01-01') FROM events",
+    ));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "2023-11-14 22:13:20");
+    assert_eq!(ok!(statement.read::<String, _>(1)), "2023-11-14");
+    assert_eq!(ok!(statement.read::<String, _>(2)), "22:13:20");
+    assert_eq!(ok!(statement.read::<String, _>(3)), "2023-11-15 22:13:20");
+    assert_eq!(ok!(statement.read::<i64, _>(4)), 1_700_000_000);
+    assert_eq!(ok!(statement.read::<String, _>(5)), "1700000000");
+    assert_eq!(
+        ok!(statement.read::<f64, _>(6)),
+        1_700_000_000.0 / 86_400.0 + 2_440_587.5,
+    );
+    assert_eq!(ok!(statement.read::<String, _>(7)), "2000-01-01");
+    drop(statement);
+
+    let reference = ok!(Connection::open(":memory:"));
+    let none = &[] as &[Value];
+    for query in [
+        "SELECT date('2023-02-31')",
+        "SELECT datetime('2023-01-31 12:00:00.5', '+1 month', 'subsec')",
+        "SELECT unixepoch('2023-01-01', 'subsec')",
+        "SELECT strftime('%Y %j %H', 1700000000, 'unixepoch', 'start of month')",
+        "SELECT julianday('2023-01-01', '+1 year', 'weekday 0')",
+    ] {
+        let expected = ok!(reference.query_scalar::<Value, _, _>(query, none));
+        assert_eq!(
+            ok!(connection.query_scalar::<Value, _, _>(query, none)),
+            expected
+        );
+    }
+    assert!(connection
+        .execute("CREATE INDEX events_day ON events (date(created_at))")
+        .is_err());
+
+    ok!(connection.freeze_time(0));
+    let mut statement = ok!(connection.prepare("SELECT CURRENT_TIMESTAMP"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "1970-01-01 00:00:00");
+    drop(statement);
+
+    ok!(connection.unfreeze_time());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let mut statement = ok!(connection.prepare("SELECT unixepoch(), date('2000-01-01', '+1 day')"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert!((ok!(statement.read::<i64, _>(0)) - now).abs() <= 1);
+    assert_eq!(ok!(statement.read::<String, _>(1)), "2000-01-02");
+    drop(statement);
+    assert!(connection
+        .execute("CREATE TABLE days (day TEXT AS (datetime('now')))")
+        .is_err());
+
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.unfreeze_time());
+    assert!(connection.functions().is_empty());
+}
+
+#[test]
+fn remove_function() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_function("answer", 0, |context| {
+        context.set_result(42i64);
+        Ok(())
+    }));
+    ok!(connection.create_overloaded_function("Total", &[1, -1], |_| Ok(())));
+    ok!(connection.create_aggregate("median", 1, Median::default));
+    assert_eq!(
+        connection.functions(),
+        vec![
+            ("answer".into(), 0),
+            ("median".into(), 1),
+            ("total".into(), -1),
+            ("total".into(), 1),
+        ],
+    );
+
+    ok!(connection.remove_function("answer", 0));
+    ok!(connection.remove_function("total", -1));
+    assert_eq!(
+        connection.functions(),
+        vec![("median".into(), 1), ("total".into(), 1)],
+    );
+    assert!(connection.prepare("SELECT answer()").is_err());
+    assert!(connection.prepare("SELECT total(1, 2)").is_err());
+    assert!(connection.prepare("SELECT total(1)").is_ok());
+}
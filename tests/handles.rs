@@ -0,0 +1,74 @@
+#![cfg(feature = "handles")]
+
+use sqlite::handles::{ConnectionHandle, Registry, StatementHandle};
+use sqlite::{ErrorContext, Redaction, State, Value};
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn workflow() {
+    let mut registry = Registry::new();
+    let first = ok!(registry.open(":memory:"));
+    let second = ok!(registry.open(":memory:"));
+    assert_ne!(first, second);
+    ok!(registry.execute(first, "CREATE TABLE users (id INTEGER, name TEXT)"));
+
+    let insert = ok!(registry.prepare(first, "INSERT INTO users VALUES (:id, :name)"));
+    for (id, name) in [(1, "Alice"), (2, "Bob")] {
+        ok!(registry.reset(insert));
+        ok!(registry.bind(insert, 1, Value::Integer(id)));
+        ok!(registry.bind_by_name(insert, ":name", Value::from(name)));
+        assert_eq!(ok!(registry.step(insert)), State::Done);
+        assert_eq!(ok!(registry.change_count(first)), 1);
+        assert_eq!(ok!(registry.last_insert_rowid(first)), id);
+    }
+    ok!(registry.finalize(insert));
+    assert!(registry.finalize(insert).is_err());
+    assert!(registry.step(insert).is_err());
+
+    let select = ok!(registry.prepare(first, "SELECT name FROM users ORDER BY id"));
+    assert_eq!(ok!(registry.column_count(select)), 1);
+    assert_eq!(ok!(registry.column_name(select, 0)), "name");
+    assert_eq!(ok!(registry.step(select)), State::Row);
+    assert_eq!(ok!(registry.read(select, 0)), Value::from("Alice"));
+    assert!(registry.read(select, 1).is_err());
+
+    assert!(registry.prepare(second, "SELECT * FROM users").is_err());
+    ok!(registry.close(first));
+    assert!(registry.read(select, 0).is_err());
+    assert!(registry.execute(first, "SELECT 1").is_err());
+    assert!(registry.close(first).is_err());
+    ok!(registry.execute(second, "SELECT 1"));
+}
+
+#[test]
+fn handles() {
+    let mut registry = Registry::new();
+    let connection = ok!(registry.open(":memory:"));
+    let statement = ok!(registry.prepare(connection, "SELECT 1"));
+    let raw = u32::from(statement);
+    assert_eq!(StatementHandle::from(raw), statement);
+    assert_eq!(ok!(registry.step(StatementHandle::from(raw))), State::Row);
+    let raw = u32::from(connection);
+    ok!(registry.close(ConnectionHandle::from(raw)));
+}
+
+#[test]
+fn set_redaction() {
+    let mut registry = Registry::new();
+    let connection = ok!(registry.open(":memory:"));
+    ok!(registry.set_error_context(connection, ErrorContext::ExpandedSql));
+    ok!(registry.set_redaction(connection, Redaction::new().with_column("email")));
+    ok!(registry.execute(
+        connection,
+        "CREATE TABLE users (name TEXT, email TEXT NOT NULL)"
+    ));
+    let statement = ok!(registry.prepare(connection, "INSERT INTO users VALUES (:name, :email)"));
+    ok!(registry.bind(statement, 1, Value::from("Alice")));
+    ok!(registry.bind(statement, 2, Value::Null));
+    let error = registry.step(statement).unwrap_err();
+    assert_eq!(
+        error.sql().unwrap(),
+        "INSERT INTO users VALUES ('Alice', :email)"
+    );
+}
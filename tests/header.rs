@@ -0,0 +1,68 @@
+use sqlite::{Check, Encoding};
+use temporary::Directory;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn inspect_header() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = ok!(sqlite::open(&path));
+    ok!(connection.execute(
+        "
+        PRAGMA page_size = 8192;
+        PRAGMA user_version = 42;
+        PRAGMA application_id = -69;
+        PRAGMA journal_mode = WAL;
+        CREATE TABLE users (name TEXT);
+        ",
+    ));
+    drop(connection);
+
+    let header = ok!(sqlite::inspect_header(&path));
+    assert_eq!(header.page_size(), 8192);
+    assert_eq!(header.write_version(), 2);
+    assert_eq!(header.read_version(), 2);
+    assert_eq!(header.encoding(), Encoding::Utf8);
+    assert_eq!(header.user_version(), 42);
+    assert_eq!(header.application_id(), -69);
+    assert_eq!(header.page_count(), 2);
+    assert_eq!(header.library_version() as usize, sqlite::version());
+
+    let path = directory.path().join("text.txt");
+    ok!(std::fs::write(&path, "SQLite format 2"));
+    assert!(sqlite::inspect_header(&path).is_err());
+    assert!(sqlite::inspect_header(directory.path().join("missing")).is_err());
+}
+
+#[test]
+fn is_database() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = ok!(sqlite::open(&path));
+    ok!(connection.execute(
+        "
+        CREATE TABLE users (name TEXT);
+        CREATE INDEX users_name ON users (name);
+        INSERT INTO users VALUES ('Alice'), ('Bob');
+        ",
+    ));
+    let bytes = ok!(connection.serialize("main"));
+    drop(connection);
+
+    for check in [Check::Header, Check::Quick, Check::Full] {
+        assert!(sqlite::is_database(&path, check));
+        assert!(sqlite::is_database(&bytes, check));
+        assert!(!sqlite::is_database(&bytes[..50], check));
+        assert!(!sqlite::is_database(
+            &directory.path().join("missing"),
+            check
+        ));
+    }
+
+    let mut corrupted = bytes.clone();
+    let size = corrupted.len();
+    corrupted[size - 4096..].fill(0x42);
+    assert!(sqlite::is_database(&corrupted, Check::Header));
+    assert!(!sqlite::is_database(&corrupted, Check::Full));
+}
@@ -0,0 +1,324 @@
+use std::sync::{Arc, Mutex};
+
+use sqlite::{Operation, Trace, TraceFlags};
+
+mod common;
+
+use common::setup_users;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn record() {
+    use sqlite::{Connection, Redaction, Session};
+
+    let mut connection = ok!(Connection::open(":memory:"));
+    connection.set_redaction(Redaction::new().with_column("email"));
+    let recorder = connection.record();
+    ok!(connection.execute("CREATE TABLE users (name TEXT,\n email TEXT)"));
+    {
+        let query = "INSERT INTO users VALUES (:name, :email)";
+        let mut statement = ok!(connection.prepare(query));
+        for name in ["Alice", "Bob"] {
+            ok!(statement.reset());
+            ok!(statement.bind((":name", name)));
+            ok!(statement.bind((":email", "secret")));
+            ok!(statement.next());
+        }
+        assert_eq!(recorder.session().records().len(), 3);
+    }
+    ok!(connection.execute("SELECT * FROM users"));
+    ok!(connection.execute("CREATE INDEX users_name ON users (name)"));
+    let session = recorder.finish();
+    ok!(connection.execute("SELECT 1"));
+
+    let records = session.records();
+    assert_eq!(records.len(), 5);
+    assert_eq!(
+        records[0].sql(),
+        "CREATE TABLE users (name TEXT,\n email TEXT)"
+    );
+    assert_eq!(
+        records[1].sql(),
+        "INSERT INTO users VALUES ('Alice', :email)"
+    );
+    assert_eq!(records[2].sql(), "INSERT INTO users VALUES ('Bob', :email)");
+    assert_eq!(records[3].sql(), "SELECT * FROM users");
+    assert_eq!(records[4].sql(), "CREATE INDEX users_name ON users (name)");
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.changes())
+            .collect::<Vec<_>>(),
+        [0, 1, 1, 0, 0],
+    );
+
+    let mut buffer = Vec::new();
+    ok!(session.write(&mut buffer));
+    assert_eq!(ok!(Session::read(&buffer[..])), session);
+
+    let other = ok!(Connection::open(":memory:"));
+    ok!(session.save(&other, "session"));
+    assert_eq!(ok!(Session::load(&other, "session")), session);
+
+    let replayed = ok!(session.replay(&other));
+    assert_eq!(
+        replayed
+            .records()
+            .iter()
+            .map(|record| record.changes())
+            .collect::<Vec<_>>(),
+        [0, 1, 1, 0, 0],
+    );
+    assert!(session.replay(&other).is_err());
+}
+
+#[test]
+fn set_authorizer() {
+    use sqlite::Authorization;
+
+    let connection = setup_users(":memory:");
+    let actions = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let actions = actions.clone();
+        connection.set_authorizer(move |action| {
+            if action.code() == sqlite::ffi::SQLITE_READ as isize {
+                actions.lock().unwrap().push((
+                    action.first().map(String::from),
+                    action.second().map(String::from),
+                    action.database().map(String::from),
+                ));
+            }
+            match (action.code() as i32, action.second()) {
+                (sqlite::ffi::SQLITE_DELETE, _) => Authorization::Deny,
+                (_, Some("name")) => Authorization::Ignore,
+                (_, Some("age")) => panic!(),
+                _ => Authorization::Allow,
+            }
+        })
+    };
+
+    let mut statement = ok!(connection.prepare("SELECT id, name FROM users"));
+    ok!(statement.next());
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1);
+    assert_eq!(ok!(statement.read::<Option<String>, _>(1)), None);
+    drop(statement);
+    assert_eq!(
+        actions.lock().unwrap()[0],
+        (Some("users".into()), Some("id".into()), Some("main".into()),),
+    );
+
+    for query in ["DELETE FROM users", "SELECT age FROM users"] {
+        let error = connection.execute(query).unwrap_err();
+        assert_eq!(error.code, Some(sqlite::ffi::SQLITE_AUTH as isize));
+    }
+
+    drop(hook);
+    ok!(connection.execute("DELETE FROM users"));
+}
+
+#[test]
+fn set_commit_hook() {
+    let connection = setup_users(":memory:");
+    let count = Arc::new(Mutex::new(0));
+    {
+        let count = count.clone();
+        connection
+            .set_commit_hook(move || {
+                let mut count = count.lock().unwrap();
+                *count += 1;
+                if *count > 1 {
+                    return Err(sqlite::Error::new(None, Some("vetoed".into())));
+                }
+                Ok(())
+            })
+            .keep();
+    }
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (2, 'Bob')"));
+    let error = connection
+        .execute("INSERT INTO users (id, name) VALUES (3, 'Eve')")
+        .unwrap_err();
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_CONSTRAINT as isize));
+    assert_eq!(
+        error.extended_code(),
+        Some(sqlite::ffi::SQLITE_CONSTRAINT_COMMITHOOK as isize)
+    );
+    connection.remove_commit_hook();
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (4, 'Carol')"));
+    assert_eq!(*count.lock().unwrap(), 2);
+
+    let mut statement = ok!(connection.prepare("SELECT group_concat(id) FROM users"));
+    ok!(statement.next());
+    assert_eq!(ok!(statement.read::<String, _>(0)), "1,2,4");
+}
+
+#[cfg(feature = "preupdate")]
+#[test]
+fn set_preupdate_hook() {
+    use sqlite::Value;
+
+    let connection = setup_users(":memory:");
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let changes = changes.clone();
+        connection.set_preupdate_hook(move |change| {
+            let old = change.read_old(1).ok();
+            let new = change.read_new(1).ok();
+            changes.lock().unwrap().push((
+                change.operation(),
+                change.table().to_string(),
+                change.column_count(),
+                change.depth(),
+                old,
+                new,
+            ));
+        })
+    };
+    ok!(connection.execute("UPDATE users SET name = 'Bob' WHERE id = 1"));
+    ok!(connection.execute("DELETE FROM users"));
+    hook.keep();
+    connection.remove_preupdate_hook();
+    ok!(connection.execute("INSERT INTO users (id) VALUES (2)"));
+    assert_eq!(
+        *changes.lock().unwrap(),
+        vec![
+            (
+                Operation::Update,
+                "users".into(),
+                5,
+                0,
+                Some(Value::from("Alice")),
+                Some(Value::from("Bob")),
+            ),
+            (
+                Operation::Delete,
+                "users".into(),
+                5,
+                0,
+                Some(Value::from("Bob")),
+                None,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn set_progress_handler() {
+    let connection = setup_users(":memory:");
+    let query = "WITH RECURSIVE numbers(value) AS \
+                 (SELECT 1 UNION ALL SELECT value + 1 FROM numbers WHERE value < 100000) \
+                 SELECT count(*) FROM numbers";
+    let count = Arc::new(Mutex::new(0));
+    let hook = {
+        let count = count.clone();
+        connection.set_progress_handler(1000, move || {
+            let mut count = count.lock().unwrap();
+            *count += 1;
+            *count < 10
+        })
+    };
+    let error = ok!(connection.prepare(query)).next().unwrap_err();
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_INTERRUPT as isize));
+    assert_eq!(*count.lock().unwrap(), 10);
+    drop(hook);
+    let mut statement = ok!(connection.prepare(query));
+    assert_eq!(ok!(statement.next()), sqlite::State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 100000);
+}
+
+#[test]
+fn set_rollback_hook() {
+    let connection = setup_users(":memory:");
+    let count = Arc::new(Mutex::new(0));
+    let _hook = {
+        let count = count.clone();
+        connection.set_rollback_hook(move || *count.lock().unwrap() += 1)
+    };
+    ok!(connection.execute("BEGIN; DELETE FROM users; ROLLBACK"));
+    ok!(connection.execute("BEGIN; DELETE FROM users; COMMIT"));
+    assert_eq!(*count.lock().unwrap(), 1);
+    let _other = connection.set_commit_hook(|| Err(sqlite::Error::new(None, None)));
+    assert!(connection
+        .execute("INSERT INTO users (id) VALUES (2)")
+        .is_err());
+    assert_eq!(*count.lock().unwrap(), 2);
+    connection.remove_rollback_hook();
+    ok!(connection.execute("BEGIN; ROLLBACK"));
+    assert_eq!(*count.lock().unwrap(), 2);
+}
+
+#[test]
+fn set_trace() {
+    let connection = setup_users(":memory:");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let flags = TraceFlags::new()
+        .with_statement()
+        .with_profile()
+        .with_row()
+        .with_close();
+    {
+        let events = events.clone();
+        connection
+            .set_trace(flags, move |trace, _| {
+                events.lock().unwrap().push(match trace {
+                    Trace::Statement(sql) => format!("statement: {sql}"),
+                    Trace::Profile(sql, _) => format!("profile: {sql}"),
+                    Trace::Row(sql) => format!("row: {sql}"),
+                    Trace::Close => "close".into(),
+                });
+            })
+            .keep();
+    }
+    {
+        let mut statement = ok!(connection.prepare("SELECT name FROM users WHERE id = ?"));
+        ok!(statement.bind((1, 1)));
+        while ok!(statement.next()) == sqlite::State::Row {}
+    }
+    drop(connection);
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            "statement: SELECT name FROM users WHERE id = ?",
+            "row: SELECT name FROM users WHERE id = ?",
+            "profile: SELECT name FROM users WHERE id = ?",
+            "close",
+        ],
+    );
+}
+
+#[test]
+fn set_update_hook() {
+    let connection = setup_users(":memory:");
+    let operations = Arc::new(Mutex::new(Vec::new()));
+    let set_update_hook = || {
+        let operations = operations.clone();
+        connection.set_update_hook(move |operation, database, table, rowid| {
+            operations.lock().unwrap().push((
+                operation,
+                database.to_string(),
+                table.to_string(),
+                rowid,
+            ));
+        })
+    };
+    let hook = set_update_hook();
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (2, 'Bob')"));
+    let other = set_update_hook();
+    drop(hook);
+    ok!(connection.execute("UPDATE users SET age = 69 WHERE id = 2"));
+    ok!(connection.execute("DELETE FROM users WHERE id = 1"));
+    drop(other);
+    ok!(connection.execute("DELETE FROM users"));
+    assert_eq!(
+        *operations.lock().unwrap(),
+        vec![
+            (Operation::Insert, "main".into(), "users".into(), 2),
+            (Operation::Update, "main".into(), "users".into(), 2),
+            (Operation::Delete, "main".into(), "users".into(), 1),
+        ],
+    );
+
+    let _hook = connection.set_update_hook(|_, _, _, _| panic!());
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (3, 'Eve')"));
+    ok!(connection.execute("INSERT INTO users (id, name) VALUES (4, 'Carol')"));
+}
@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn config_log() {
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    {
+        let messages = messages.clone();
+        ok!(sqlite::config_log(move |code, message| {
+            if message.contains("slow") {
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            messages.lock().unwrap().push((code, message.to_string()));
+        }));
+    }
+    let connection = ok!(sqlite::open(":memory:"));
+    assert!(connection.execute("SELECT * FROM missing").is_err());
+    let thread = std::thread::spawn(|| {
+        let connection = ok!(sqlite::open(":memory:"));
+        assert!(connection.execute("SELECT * FROM slow").is_err());
+    });
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(connection.execute("SELECT * FROM fast").is_err());
+    ok!(thread.join());
+    let logged = messages.lock().unwrap();
+    assert!(logged.iter().any(
+        |(code, message)| *code == sqlite::ffi::SQLITE_ERROR as isize
+            && message.contains("no such table: missing")
+    ));
+    for table in ["slow", "fast"] {
+        let table = format!("no such table: {table}");
+        assert!(logged.iter().any(|(_, message)| message.contains(&table)));
+    }
+    drop(logged);
+
+    let mut connection = ok!(sqlite::open(":memory:"));
+    connection.set_tag("tenant-42");
+    assert!(connection.execute("SELECT * FROM tagged").is_err());
+    let logged = messages.lock().unwrap();
+    assert!(logged
+        .iter()
+        .any(|(_, message)| message.starts_with("[tenant-42] no such table: tagged")));
+    assert!(!logged
+        .iter()
+        .any(|(_, message)| message.starts_with("[tenant-42] no such table: missing")));
+    drop(logged);
+    connection.set_leak_detection(true);
+    let statement = ok!(connection.prepare("SELECT 1"));
+    std::mem::forget(statement);
+    drop(connection);
+    let logged = messages.lock().unwrap();
+    assert!(logged.iter().any(
+        |(code, message)| *code == sqlite::ffi::SQLITE_WARNING as isize
+            && message.starts_with("[tenant-42] a statement has not been finalized")
+            && message.ends_with("SELECT 1")
+    ));
+    drop(logged);
+
+    let error = sqlite::config_log(|_, _| {}).unwrap_err();
+    assert_eq!(error.code, Some(sqlite::ffi::SQLITE_MISUSE as isize));
+}
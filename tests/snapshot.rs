@@ -0,0 +1,59 @@
+use sqlite::{Connection, SnapshotScheduler, State};
+use std::time::Duration;
+use temporary::Directory;
+
+mod common;
+
+use common::setup_users;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn snapshot() {
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = setup_users(":memory:");
+
+    let scheduler = SnapshotScheduler::new(directory.path()).with_keep(2);
+    let first = ok!(scheduler.snapshot(&connection));
+    let second = ok!(scheduler.snapshot(&connection));
+    let third = ok!(scheduler.snapshot(&connection));
+    assert_eq!(ok!(scheduler.snapshots()), vec![second, third.clone()]);
+    assert!(!first.exists());
+
+    let other = SnapshotScheduler::new(directory.path()).with_prefix("other");
+    ok!(other.snapshot(&connection));
+    assert_eq!(ok!(other.snapshots()).len(), 1);
+    assert_eq!(ok!(scheduler.snapshots()).len(), 2);
+
+    let connection = ok!(Connection::open(third));
+    let mut statement = ok!(connection.prepare("SELECT name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn snapshot_with_compression() {
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = setup_users(":memory:");
+
+    let scheduler = SnapshotScheduler::new(directory.path()).with_compression(3);
+    let path = ok!(scheduler.snapshot(&connection));
+    assert!(path.to_str().unwrap().ends_with(".sqlite3.zst"));
+    assert_eq!(ok!(scheduler.snapshots()), vec![path]);
+}
+
+#[test]
+fn spawn() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    setup_users(&path);
+    let snapshots = directory.path().join("snapshots");
+    ok!(std::fs::create_dir(&snapshots));
+
+    let scheduler = SnapshotScheduler::new(&snapshots).with_keep(3);
+    let handle = ok!(scheduler.clone().spawn(&path, Duration::from_millis(10)));
+    std::thread::sleep(Duration::from_millis(200));
+    ok!(handle.stop());
+    assert_eq!(ok!(scheduler.snapshots()).len(), 3);
+}
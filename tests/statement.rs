@@ -82,6 +82,15 @@ fn bind_with_name() {
     ok!(statement.reset());
     assert!(statement.bind((":missing", 404)).is_err());
 
+    ok!(statement.reset());
+    let name = String::from(":name");
+    ok!(statement.bind((String::from(":id"), 2i64)));
+    ok!(statement.bind((&name, "Bob")));
+    ok!(statement.bind((std::borrow::Cow::from(":age"), 69.42)));
+    ok!(statement.bind(&[(Box::<str>::from(":email"), ())][..]));
+    ok!(statement.bind(&[(String::from(":photo"), Value::Null)][..]));
+    assert_eq!(ok!(statement.next()), State::Done);
+
     ok!(statement.reset());
     ok!(statement.bind(
         &[
@@ -95,6 +104,235 @@ fn bind_with_name() {
     assert_eq!(ok!(statement.next()), State::Done);
 }
 
+#[test]
+fn bind_with_params() {
+    use sqlite::Params;
+
+    let connection = setup_users(":memory:");
+    let params = Params::new()
+        .set(":id", 2)
+        .set(":name", "Alice")
+        .set(":age", 69.42)
+        .set(":name", "Bob")
+        .set(":unused", Value::Null);
+    assert_eq!(params.get(":name"), Some(&Value::String("Bob".into())));
+    assert_eq!(params.iter().count(), 4);
+
+    let query = "INSERT INTO users (id, name, age) VALUES (:id, :name, :age)";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind(&params));
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let query = "SELECT age FROM users WHERE id = :id AND name = :name";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind(params.clone()));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<f64, _>(0)), 69.42);
+
+    let params = [(String::from(":id"), 2)].into_iter().collect::<Params>();
+    let mut statement = ok!(connection.prepare("SELECT name FROM users WHERE id = :id"));
+    ok!(statement.bind(&params));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Bob");
+}
+
+#[test]
+fn bind_with_tuple() {
+    let connection = setup_users(":memory:");
+    let query = "INSERT INTO users VALUES (?, ?, ?, ?, ?)";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind((2i64, "Bob", 69.42, &[0x69u8, 0x42u8][..], ())));
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let mut statement = ok!(connection.prepare("SELECT name FROM users WHERE id = ?"));
+    ok!(statement.bind((2i64,)));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Bob");
+
+    let mut statement = ok!(connection.prepare("SELECT ?"));
+    assert!(statement.bind((1, 2, 3)).is_err());
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn bind_with_time() {
+    use time::macros::{date, datetime, time};
+    use time::{Date, OffsetDateTime, Time};
+
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare(
+        "SELECT ?, ?, ?, date(?1), time(?2), unixepoch(?3), '2024-02-29 12:34:56', 1700000000"
+    ));
+    ok!(statement.bind((
+        date!(2024 - 02 - 29),
+        time!(12:34:56.5),
+        datetime!(2024-02-29 12:34:56.5 +01:00),
+    )));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "2024-02-29");
+    assert_eq!(ok!(statement.read::<String, _>(1)), "12:34:56.5");
+    assert_eq!(
+        ok!(statement.read::<String, _>(2)),
+        "2024-02-29T12:34:56.5+01:00"
+    );
+    assert_eq!(ok!(statement.read::<String, _>(3)), "2024-02-29");
+    assert_eq!(ok!(statement.read::<String, _>(4)), "12:34:56");
+    assert_eq!(ok!(statement.read::<i64, _>(5)), 1709206496);
+    assert_eq!(ok!(statement.read::<Date, _>(0)), date!(2024 - 02 - 29));
+    assert_eq!(ok!(statement.read::<Time, _>(1)), time!(12:34:56.5));
+    assert_eq!(ok!(statement.read::<Time, _>(4)), time!(12:34:56));
+    assert_eq!(
+        ok!(statement.read::<OffsetDateTime, _>(2)),
+        datetime!(2024-02-29 12:34:56.5 +01:00),
+    );
+    assert_eq!(
+        ok!(statement.read::<OffsetDateTime, _>(6)),
+        datetime!(2024-02-29 12:34:56 UTC),
+    );
+    assert_eq!(
+        ok!(statement.read::<OffsetDateTime, _>(7)),
+        datetime!(2023-11-14 22:13:20 UTC),
+    );
+    assert!(statement.read::<Date, _>(1).is_err());
+
+    ok!(statement.reset());
+    let row = ok!(statement.iter().next().unwrap());
+    assert_eq!(row.read::<Date, _>(0), date!(2024 - 02 - 29));
+    assert_eq!(row.read::<Option<Time>, _>(1), Some(time!(12:34:56.5)));
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn bind_with_uuid() {
+    use uuid::Uuid;
+
+    let uuid = ok!(Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT ?, ?, typeof(?1), typeof(?2), NULL"));
+    ok!(statement.bind((1, uuid)));
+    ok!(statement.bind((2, uuid.hyphenated())));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(0)), uuid.as_bytes());
+    assert_eq!(
+        ok!(statement.read::<String, _>(1)),
+        "67e55044-10b1-426f-9247-bb680e5fe0c8",
+    );
+    assert_eq!(ok!(statement.read::<String, _>(2)), "blob");
+    assert_eq!(ok!(statement.read::<String, _>(3)), "text");
+    assert_eq!(ok!(statement.read::<Uuid, _>(0)), uuid);
+    assert_eq!(ok!(statement.read::<Uuid, _>(1)), uuid);
+    assert_eq!(ok!(statement.read::<Option<Uuid>, _>(4)), None);
+    assert!(statement.read::<Uuid, _>(2).is_err());
+
+    assert_eq!(Value::from(uuid), Value::Binary(uuid.as_bytes().to_vec()));
+    assert_eq!(ok!(Uuid::try_from(&Value::from(uuid.hyphenated()))), uuid);
+}
+
+#[test]
+fn bind_with_bool() {
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT ?, ?, typeof(?1), 42, NULL"));
+    ok!(statement.bind((1, true)));
+    ok!(statement.bind((2, false)));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1);
+    assert_eq!(ok!(statement.read::<i64, _>(1)), 0);
+    assert_eq!(ok!(statement.read::<String, _>(2)), "integer");
+    assert!(ok!(statement.read::<bool, _>(0)));
+    assert!(!ok!(statement.read::<bool, _>(1)));
+    assert!(ok!(statement.read::<bool, _>(3)));
+    assert_eq!(ok!(statement.read::<Option<bool>, _>(4)), None);
+
+    assert_eq!(Value::from(true), Value::Integer(1));
+    assert!(!ok!(bool::try_from(&Value::Integer(0))));
+    assert_eq!(ok!(Option::<bool>::try_from(&Value::Null)), None);
+    assert!(bool::try_from(&Value::String("true".into())).is_err());
+}
+
+#[test]
+fn bind_with_cow_and_box() {
+    use std::borrow::Cow;
+
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT ?, ?, ?, ?"));
+    let text: Box<str> = "Bob".into();
+    let blob: Box<[u8]> = vec![0x42].into();
+    ok!(statement.bind((
+        Cow::Borrowed("Alice"),
+        Cow::Borrowed(&[0x69u8][..]),
+        &text,
+        blob.clone(),
+    )));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(1)), vec![0x69]);
+    assert_eq!(ok!(statement.read::<String, _>(2)), "Bob");
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(3)), vec![0x42]);
+
+    assert_eq!(
+        Value::from(Cow::Owned::<str>("Alice".into())),
+        Value::String("Alice".into()),
+    );
+    assert_eq!(Value::from(text), Value::String("Bob".into()));
+    assert_eq!(Value::from(blob), Value::Binary(vec![0x42]));
+}
+
+#[test]
+fn bind_with_float() {
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT ?, 1e300, NULL"));
+    ok!(statement.bind((1, 0.5f32)));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<f64, _>(0)), 0.5);
+    assert_eq!(ok!(statement.read::<f32, _>(0)), 0.5);
+    assert!(statement.read::<f32, _>(1).is_err());
+    assert_eq!(ok!(statement.read::<Option<f32>, _>(2)), None);
+
+    assert_eq!(Value::from(0.25f32), Value::Float(0.25));
+    assert_eq!(ok!(f32::try_from(&Value::Float(0.25))), 0.25);
+    assert!(f32::try_from(&Value::Float(f64::MAX)).is_err());
+    assert!(ok!(f32::try_from(&Value::Float(f64::INFINITY))).is_infinite());
+}
+
+#[test]
+fn bind_with_integer() {
+    let connection = ok!(sqlite::open(":memory:"));
+    let mut statement = ok!(connection.prepare("SELECT ?, ?, ?, ?, ?, ?, ?, -1, 300"));
+    ok!(statement.bind((-8i8, -16i16, -32i32, 16u16, 32u32, 64u64, 42usize)));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i8, _>(0)), -8);
+    assert_eq!(ok!(statement.read::<i16, _>(1)), -16);
+    assert_eq!(ok!(statement.read::<i32, _>(2)), -32);
+    assert_eq!(ok!(statement.read::<u16, _>(3)), 16);
+    assert_eq!(ok!(statement.read::<u32, _>(4)), 32);
+    assert_eq!(ok!(statement.read::<u64, _>(5)), 64);
+    assert_eq!(ok!(statement.read::<usize, _>(6)), 42);
+    assert!(statement.read::<u64, _>(7).is_err());
+    assert!(statement.read::<i8, _>(8).is_err());
+    assert_eq!(ok!(statement.read::<Option<u16>, _>(8)), Some(300));
+
+    assert!(statement.bind((1, u64::MAX)).is_err());
+    assert_eq!(ok!(i8::try_from(&Value::Integer(127))), 127);
+    assert!(i8::try_from(&Value::Integer(128)).is_err());
+    assert_eq!(ok!(Option::<i32>::try_from(&Value::Null)), None);
+}
+
+#[test]
+fn bind_zeroblob() {
+    use sqlite::Zeroblob;
+
+    let connection = setup_users(":memory:");
+    let query = "INSERT INTO users VALUES (2, 'Bob', NULL, ?, NULL)";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind((1, Zeroblob(42))));
+    assert_eq!(ok!(statement.next()), State::Done);
+
+    let query = "SELECT photo FROM users WHERE id = 2";
+    let mut statement = ok!(connection.prepare(query));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(0)), vec![0; 42]);
+}
+
 #[test]
 fn count() {
     let connection = setup_english(":memory:");
@@ -117,6 +355,58 @@ fn count() {
     assert_eq!(count, 6);
 }
 
+#[test]
+fn execute_batch() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)"));
+    let mut statement = ok!(connection.prepare("INSERT INTO users VALUES (?, ?)"));
+
+    let users: Vec<[Value; 2]> = vec![[1.into(), "Alice".into()], [2.into(), "Bob".into()]];
+    assert_eq!(
+        ok!(statement.execute_batch(users.iter().map(|user| &user[..]))),
+        2
+    );
+
+    let users: Vec<[Value; 2]> = vec![[3.into(), "Eve".into()], [3.into(), "Carol".into()]];
+    let result = statement.execute_batch_in_transaction(users.iter().map(|user| &user[..]));
+    assert!(result.is_err());
+
+    let users = [&[Value::Integer(3)][..]];
+    assert_eq!(ok!(statement.execute_batch_in_transaction(users)), 1);
+
+    let mut statement =
+        ok!(connection.prepare("SELECT group_concat(coalesce(name, id)) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice,Bob,3");
+}
+
+#[test]
+fn read_raw_value() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("CREATE TABLE copies (a, b, c, d, e)"));
+    let mut source = ok!(connection.prepare("SELECT * FROM users"));
+    assert_eq!(ok!(source.next()), State::Row);
+    let values = (0..5)
+        .map(|index| ok!(source.read_raw_value(index)))
+        .collect::<Vec<_>>();
+    assert_eq!(values[0].kind(), Type::Integer);
+    assert_eq!(values[1].to_value(), Value::from("Alice"));
+    assert_eq!(values[4].kind(), Type::Null);
+
+    let mut target = ok!(connection.prepare("INSERT INTO copies VALUES (?, ?, ?, ?, ?)"));
+    ok!(target.bind(&values[..]));
+    assert_eq!(ok!(target.next()), State::Done);
+
+    let mut statement = ok!(connection.prepare("SELECT * FROM copies"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.column_type(0)), Type::Integer);
+    assert_eq!(ok!(statement.column_type(1)), Type::String);
+    assert_eq!(ok!(statement.column_type(2)), Type::Float);
+    assert_eq!(ok!(statement.column_type(3)), Type::Binary);
+    assert_eq!(ok!(statement.column_type(4)), Type::Null);
+    assert_eq!(ok!(statement.read::<Vec<u8>, _>(3)), vec![0x42, 0x69]);
+}
+
 #[test]
 fn read_with_index() {
     let connection = setup_users(":memory:");
@@ -153,6 +443,35 @@ fn read_with_index_and_option() {
     assert_eq!(ok!(statement.next()), State::Done);
 }
 
+#[test]
+fn read_with_index_and_null() {
+    let connection = setup_users(":memory:");
+    let query = "SELECT * FROM users";
+    let mut statement = ok!(connection.prepare(query));
+
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<Option<Value>, _>(4)), None);
+    assert_eq!(
+        ok!(statement.read::<Option<Value>, _>(0)),
+        Some(Value::Integer(1))
+    );
+    ok!(statement.read::<(), _>(4));
+    assert_eq!(ok!(statement.read::<Option<()>, _>(4)), None);
+    assert!(statement.read::<(), _>(0).is_err());
+    assert!(statement.read::<Option<()>, _>(0).is_err());
+
+    let value = Value::Null;
+    assert_eq!(ok!(<Option<Value>>::try_from(&value)), None);
+    assert_eq!(ok!(<Option<()>>::try_from(&value)), None);
+    assert_eq!(ok!(<Option<i64>>::try_from(&value)), None);
+    ok!(<()>::try_from(&value));
+    let value = Value::Integer(42);
+    assert_eq!(ok!(<Value>::try_from(&value)), Value::Integer(42));
+    assert_eq!(ok!(i64::try_from(value)), 42);
+    assert_eq!(ok!(<Option<f64>>::try_from(Value::Float(4.2))), Some(4.2));
+    assert_eq!(ok!(<Option<()>>::try_from(Value::Null)), None);
+}
+
 #[test]
 fn read_with_name_and_option() {
     let connection = setup_users(":memory:");
@@ -213,6 +532,136 @@ fn column_name() {
     assert_eq!("user_photo", ok!(statement.column_name(3)));
 }
 
+#[test]
+fn column_name_live() {
+    let connection = setup_users(":memory:");
+    let mut statement = ok!(connection.prepare("SELECT * FROM users"));
+    assert_eq!(ok!(statement.column_name_live(1)), "name");
+
+    ok!(connection.execute("ALTER TABLE users RENAME COLUMN name TO full_name"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.column_name(1)), "name");
+    assert_eq!(ok!(statement.column_name_live(1)), "full_name");
+    assert!(statement.column_name_live(5).is_err());
+}
+
+#[test]
+fn into_raw() {
+    let connection = setup_users(":memory:");
+    let statement = ok!(connection.prepare("SELECT name FROM users"));
+    let raw = statement.into_raw();
+    let mut statement = unsafe { sqlite::Statement::from_raw(raw) };
+    assert_eq!(statement.as_raw(), raw);
+    assert_eq!(statement.column_names(), ["name"]);
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+}
+
+#[cfg(feature = "column-metadata")]
+#[test]
+fn column_origin() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("CREATE VIEW adults AS SELECT name AS full_name FROM users"));
+    let query = "SELECT id AS key, adults.full_name, 1 FROM users, adults";
+    let statement = ok!(connection.prepare(query));
+    assert_eq!(
+        ok!(statement.column_database_name(0)).as_deref(),
+        Some("main")
+    );
+    assert_eq!(
+        ok!(statement.column_table_name(0)).as_deref(),
+        Some("users")
+    );
+    assert_eq!(ok!(statement.column_origin_name(0)).as_deref(), Some("id"));
+    assert_eq!(
+        ok!(statement.column_table_name(1)).as_deref(),
+        Some("users")
+    );
+    assert_eq!(
+        ok!(statement.column_origin_name(1)).as_deref(),
+        Some("name")
+    );
+    assert_eq!(ok!(statement.column_database_name(2)), None);
+    assert_eq!(ok!(statement.column_table_name(2)), None);
+    assert_eq!(ok!(statement.column_origin_name(2)), None);
+    assert!(statement.column_origin_name(3).is_err());
+}
+
+#[test]
+fn sql() {
+    use sqlite::Redaction;
+
+    let mut connection = setup_users(":memory:");
+    let query = "SELECT * FROM users WHERE name = :name AND email = :email";
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind((":name", "Bob")));
+    assert_eq!(statement.sql(), query);
+    assert_eq!(
+        ok!(statement.expanded_sql()),
+        "SELECT * FROM users WHERE name = 'Bob' AND email = NULL",
+    );
+    drop(statement);
+
+    connection.set_redaction(Redaction::new().with_column("name"));
+    let mut statement = ok!(connection.prepare(query));
+    ok!(statement.bind((":name", "Bob")));
+    assert_eq!(
+        ok!(statement.expanded_sql()),
+        "SELECT * FROM users WHERE name = :name AND email = NULL",
+    );
+}
+
+#[cfg(feature = "normalize")]
+#[test]
+fn normalized_sql() {
+    let connection = setup_users(":memory:");
+    let statement = ok!(connection.prepare("SELECT  name FROM users WHERE id = 42"));
+    assert_eq!(
+        ok!(statement.normalized_sql()),
+        "SELECT name FROM users WHERE id=?;"
+    );
+}
+
+#[test]
+fn column_decltype() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("CREATE VIEW adults AS SELECT name, age FROM users WHERE age >= 18"));
+    let statement = ok!(connection.prepare("SELECT *, 1 + 1 FROM users"));
+    assert_eq!(
+        ok!(statement.column_decltype(0)).as_deref(),
+        Some("INTEGER")
+    );
+    assert_eq!(
+        ok!(statement.column_decltype("photo")).as_deref(),
+        Some("BLOB")
+    );
+    assert_eq!(ok!(statement.column_decltype(5)), None);
+    assert!(statement.column_decltype(6).is_err());
+
+    let statement = ok!(connection.prepare("SELECT age FROM adults"));
+    assert_eq!(ok!(statement.column_decltype(0)).as_deref(), Some("REAL"));
+}
+
+#[test]
+fn column_affinity() {
+    let connection = setup_users(":memory:");
+    ok!(connection.execute("CREATE VIEW adults AS SELECT name, age FROM users WHERE age >= 18"));
+    let statement = ok!(connection.prepare("SELECT *, 1 + 1 FROM users"));
+    assert_eq!(ok!(statement.column_affinity(0)), Some(Type::Integer));
+    assert_eq!(ok!(statement.column_affinity(1)), Some(Type::String));
+    assert_eq!(ok!(statement.column_affinity(2)), Some(Type::Float));
+    assert_eq!(ok!(statement.column_affinity(3)), Some(Type::Binary));
+    assert_eq!(ok!(statement.column_affinity(5)), None);
+    let statement = ok!(connection.prepare("SELECT * FROM adults"));
+    assert_eq!(ok!(statement.column_affinity("age")), Some(Type::Float));
+
+    assert_eq!(Type::from_decltype("BIGINT"), Type::Integer);
+    assert_eq!(Type::from_decltype("NATIVE CHARACTER(70)"), Type::String);
+    assert_eq!(Type::from_decltype(""), Type::Binary);
+    assert_eq!(Type::from_decltype("DOUBLE PRECISION"), Type::Float);
+    assert_eq!(Type::from_decltype("DECIMAL(10,5)"), Type::Float);
+}
+
 #[test]
 fn column_type() {
     let connection = setup_users(":memory:");
@@ -298,3 +747,22 @@ fn workflow_2() {
     let age = ok!(statement.read::<i64, _>("age"));
     assert_eq!(age, 50);
 }
+
+#[test]
+#[allow(deprecated)]
+fn deprecated() {
+    let connection = setup_users(":memory:");
+    let mut statement = ok!(connection.prepare("SELECT name FROM users WHERE id = :id"));
+    ok!(statement.bind_by_name(":id", 1));
+    assert_eq!(statement.cursor().count(), 1);
+
+    let cursor = ok!(statement
+        .cursor()
+        .bind_by_name(vec![(":id", Value::Integer(2))]));
+    assert_eq!(cursor.count(), 0);
+
+    ok!(statement.reset());
+    ok!(statement.bind_by_name(":id", 1));
+    let rows = statement.into_cursor().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 1);
+}
@@ -0,0 +1,355 @@
+use sqlite::{Connection, MultiTx, ResultCode, State};
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+fn count(connection: &Connection, table: &str) -> i64 {
+    let mut statement = ok!(connection.prepare(format!("SELECT COUNT(*) FROM {table}")));
+    assert_eq!(ok!(statement.next()), State::Row);
+    ok!(statement.read::<i64, _>(0))
+}
+
+fn setup_shards(connection: &Connection) {
+    ok!(connection.execute(
+        "
+        ATTACH DATABASE ':memory:' AS first;
+        ATTACH DATABASE ':memory:' AS second;
+        CREATE TABLE first.users (id INTEGER);
+        CREATE TABLE second.users (id INTEGER);
+        ",
+    ));
+}
+
+#[test]
+fn multi_tx_commit() {
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+
+    let transaction = ok!(MultiTx::begin(&connection));
+    assert_eq!(transaction.schemas(), &["main", "first", "second"]);
+    ok!(connection.execute("INSERT INTO first.users VALUES (1)"));
+    ok!(connection.execute("INSERT INTO second.users VALUES (2)"));
+    ok!(transaction.commit());
+
+    assert_eq!(count(&connection, "first.users"), 1);
+    assert_eq!(count(&connection, "second.users"), 1);
+}
+
+#[test]
+fn multi_tx_commit_failure() {
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+    ok!(connection.execute(
+        "
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE authors (id INTEGER PRIMARY KEY);
+        CREATE TABLE posts (author INTEGER REFERENCES authors DEFERRABLE INITIALLY DEFERRED);
+        ",
+    ));
+
+    let transaction = ok!(MultiTx::begin(&connection));
+    ok!(connection.execute("INSERT INTO posts VALUES (1)"));
+    ok!(connection.execute("INSERT INTO second.users VALUES (2)"));
+    assert!(transaction.commit().is_err());
+
+    assert_eq!(count(&connection, "posts"), 0);
+    assert_eq!(count(&connection, "second.users"), 0);
+    ok!(ok!(MultiTx::begin(&connection)).commit());
+}
+
+#[test]
+fn multi_tx_drop() {
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+
+    {
+        let _transaction = ok!(MultiTx::begin(&connection));
+        ok!(connection.execute("INSERT INTO first.users VALUES (1)"));
+        ok!(connection.execute("INSERT INTO second.users VALUES (2)"));
+    }
+
+    assert_eq!(count(&connection, "first.users"), 0);
+    assert_eq!(count(&connection, "second.users"), 0);
+}
+
+#[test]
+fn multi_tx_is_atomic() {
+    use temporary::Directory;
+
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+    let transaction = ok!(MultiTx::begin(&connection));
+    assert!(!ok!(transaction.is_atomic()));
+    ok!(transaction.rollback());
+
+    let directory = ok!(Directory::new("sqlite"));
+    let connection = ok!(sqlite::open(directory.path().join("main.sqlite3")));
+    let path = directory.path().join("other.sqlite3");
+    ok!(connection.execute(format!("ATTACH DATABASE '{}' AS other", path.display())));
+    let transaction = ok!(MultiTx::begin(&connection));
+    assert!(ok!(transaction.is_atomic()));
+    ok!(transaction.rollback());
+}
+
+#[test]
+fn multi_tx_savepoint() {
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+
+    let transaction = ok!(MultiTx::begin(&connection));
+    ok!(connection.execute("INSERT INTO first.users VALUES (1)"));
+    {
+        let savepoint = ok!(transaction.savepoint("second"));
+        assert_eq!(savepoint.name(), "second");
+        ok!(connection.execute("INSERT INTO second.users VALUES (2)"));
+        ok!(savepoint.rollback());
+    }
+    {
+        let savepoint = ok!(transaction.savepoint("second"));
+        ok!(connection.execute("INSERT INTO second.users VALUES (3)"));
+        ok!(savepoint.release());
+    }
+    ok!(transaction.commit());
+
+    assert_eq!(count(&connection, "first.users"), 1);
+    assert_eq!(count(&connection, "second.users"), 1);
+}
+
+#[test]
+fn multi_tx_begin_with_behavior() {
+    use sqlite::TransactionBehavior;
+
+    let connection = ok!(sqlite::open(":memory:"));
+    setup_shards(&connection);
+
+    for behavior in [
+        TransactionBehavior::Deferred,
+        TransactionBehavior::Immediate,
+        TransactionBehavior::Exclusive,
+    ] {
+        assert!(behavior.is_supported(&connection));
+        let transaction = ok!(MultiTx::begin_with_behavior(&connection, behavior));
+        ok!(transaction.commit());
+    }
+
+    let behavior = TransactionBehavior::Concurrent;
+    if behavior.is_supported(&connection) {
+        let transaction = ok!(MultiTx::begin_with_behavior(&connection, behavior));
+        ok!(transaction.commit());
+    } else {
+        let error = MultiTx::begin_with_behavior(&connection, behavior)
+            .err()
+            .unwrap();
+        assert_eq!(error.result_code(), Some(ResultCode::Misuse));
+        let error = connection
+            .transaction_with_behavior(behavior)
+            .err()
+            .unwrap();
+        assert_eq!(error.result_code(), Some(ResultCode::Misuse));
+    }
+}
+
+#[test]
+fn transaction_commit() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+
+    let transaction = ok!(connection.transaction());
+    ok!(transaction.execute("INSERT INTO users VALUES (1)"));
+    ok!(transaction.commit());
+
+    assert_eq!(count(&connection, "users"), 1);
+}
+
+#[test]
+fn transaction_commit_failure() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute(
+        "
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE users (id INTEGER PRIMARY KEY);
+        CREATE TABLE posts (user INTEGER REFERENCES users DEFERRABLE INITIALLY DEFERRED);
+        ",
+    ));
+
+    let transaction = ok!(connection.transaction());
+    ok!(transaction.execute("INSERT INTO posts VALUES (1)"));
+    assert!(transaction.commit().is_err());
+
+    assert_eq!(count(&connection, "posts"), 0);
+    ok!(ok!(connection.transaction()).commit());
+}
+
+#[test]
+fn transaction_drop() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+
+    {
+        let transaction = ok!(connection.transaction());
+        ok!(transaction.execute("INSERT INTO users VALUES (1)"));
+    }
+    assert_eq!(count(&connection, "users"), 0);
+
+    let transaction = ok!(connection.transaction());
+    ok!(transaction.execute("INSERT INTO users VALUES (1)"));
+    ok!(transaction.rollback());
+    assert_eq!(count(&connection, "users"), 0);
+}
+
+#[test]
+fn transaction_savepoint() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+
+    let transaction = ok!(connection.transaction());
+    ok!(transaction.execute("INSERT INTO users VALUES (1)"));
+    {
+        let _savepoint = ok!(transaction.savepoint("nested"));
+        ok!(transaction.execute("INSERT INTO users VALUES (2)"));
+    }
+    ok!(transaction.commit());
+
+    assert_eq!(count(&connection, "users"), 1);
+}
+
+#[test]
+fn with_transaction() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+
+    let value = ok!(connection.with_transaction(|transaction| {
+        ok!(transaction.execute("INSERT INTO users VALUES (1)"));
+        Ok(42)
+    }));
+    assert_eq!(value, 42);
+    assert_eq!(count(&connection, "users"), 1);
+
+    let result = connection.with_transaction(|transaction| {
+        ok!(transaction.execute("INSERT INTO users VALUES (2)"));
+        transaction.execute("INSERT INTO missing VALUES (3)")
+    });
+    assert!(result.is_err());
+    assert_eq!(count(&connection, "users"), 1);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        connection.with_transaction::<_, ()>(|transaction| {
+            ok!(transaction.execute("INSERT INTO users VALUES (4)"));
+            panic!()
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(count(&connection, "users"), 1);
+}
+
+#[test]
+fn transaction_with_behavior() {
+    use sqlite::TransactionBehavior;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let one = ok!(sqlite::open(&path));
+    let two = ok!(sqlite::open(&path));
+    ok!(one.execute("CREATE TABLE users (id INTEGER)"));
+
+    let transaction = ok!(one.transaction_with_behavior(TransactionBehavior::Deferred));
+    assert!(two
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .is_ok());
+    ok!(transaction.rollback());
+
+    let transaction = ok!(one.transaction_with_behavior(TransactionBehavior::Immediate));
+    assert!(two
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .is_err());
+    ok!(transaction.commit());
+
+    let transaction = ok!(one.transaction_with_behavior(TransactionBehavior::Exclusive));
+    assert!(two.execute("SELECT * FROM users").is_err());
+    ok!(transaction.commit());
+}
+
+#[test]
+fn pinned_reader() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = ok!(sqlite::open(&path));
+    ok!(connection.execute(
+        "
+        PRAGMA journal_mode = WAL;
+        CREATE TABLE users (id INTEGER);
+        INSERT INTO users VALUES (1);
+        ",
+    ));
+    let writer = ok!(sqlite::open(&path));
+
+    let reader = ok!(connection.pinned_reader());
+    ok!(writer.execute("INSERT INTO users VALUES (2)"));
+    assert_eq!(count(&reader, "users"), 1);
+    ok!(writer.execute("INSERT INTO users VALUES (3)"));
+    assert_eq!(count(&reader, "users"), 1);
+    ok!(reader.finish());
+    assert_eq!(count(&connection, "users"), 3);
+
+    {
+        let _reader = ok!(connection.pinned_reader());
+        ok!(writer.execute("DELETE FROM users"));
+        assert_eq!(count(&connection, "users"), 3);
+    }
+    assert_eq!(count(&connection, "users"), 0);
+
+    let reader = ok!(connection.pinned_reader());
+    ok!(reader.execute("INSERT INTO users VALUES (4)"));
+    ok!(reader.finish());
+    assert_eq!(count(&connection, "users"), 0);
+}
+
+#[test]
+fn with_version_check() {
+    let connection = ok!(sqlite::open(":memory:"));
+    ok!(connection.execute(
+        "
+        CREATE TABLE users (name TEXT, version INTEGER NOT NULL DEFAULT 0);
+        INSERT INTO users (name) VALUES ('Alice');
+        ",
+    ));
+    let result = ok!(connection.with_version_check("users", 1, 0, |transaction| {
+        ok!(transaction.execute("UPDATE users SET name = 'Bob' WHERE rowid = 1"));
+        Ok(42)
+    }));
+    assert_eq!(result, Some(42));
+
+    let result = ok!(connection.with_version_check("users", 1, 0, |transaction| {
+        transaction.execute("UPDATE users SET name = 'Carol' WHERE rowid = 1")
+    }));
+    assert!(result.is_none());
+    assert!(ok!(connection.with_version_check("users", 2, 0, |_| Ok(()))).is_none());
+
+    let result = connection.with_version_check("users", 1, 1, |transaction| {
+        ok!(transaction.execute("UPDATE users SET name = 'Dave' WHERE rowid = 1"));
+        transaction.execute("SELECT * FROM missing")
+    });
+    assert!(result.is_err());
+
+    let mut statement = ok!(connection.prepare("SELECT name, version FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Bob");
+    assert_eq!(ok!(statement.read::<i64, _>(1)), 1);
+}
+
+#[test]
+fn data_version() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let connection = ok!(sqlite::open(&path));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+    let version = ok!(connection.data_version());
+    ok!(connection.execute("INSERT INTO users VALUES (1)"));
+    assert_eq!(ok!(connection.data_version()), version);
+    ok!(ok!(sqlite::open(&path)).execute("INSERT INTO users VALUES (2)"));
+    assert_ne!(ok!(connection.data_version()), version);
+}
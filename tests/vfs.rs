@@ -0,0 +1,617 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use sqlite::vfs::{File, FileFlags, LockLevel, OsFile, OsVfs, Vfs};
+use sqlite::{Connection, OpenFlags, Result, State};
+
+mod common;
+
+use common::setup_users;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+struct CountingVfs {
+    inner: OsVfs,
+    writes: Arc<AtomicUsize>,
+}
+
+struct CountingFile {
+    inner: OsFile,
+    writes: Arc<AtomicUsize>,
+}
+
+impl Vfs for CountingVfs {
+    type File = CountingFile;
+
+    fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+        Ok(CountingFile {
+            inner: self.inner.open(path, flags)?,
+            writes: self.writes.clone(),
+        })
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        self.inner.exists(path)
+    }
+
+    fn full_path(&self, path: &str) -> Result<String> {
+        self.inner.full_path(path)
+    }
+}
+
+impl File for CountingFile {
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        self.inner.read(offset, buffer)
+    }
+
+    fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.write(offset, buffer)
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.inner.truncate(size)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        self.inner.sync()
+    }
+
+    fn size(&mut self) -> Result<u64> {
+        self.inner.size()
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    fn is_reserved(&mut self) -> Result<bool> {
+        self.inner.is_reserved()
+    }
+}
+
+#[test]
+fn register() {
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let writes = Arc::new(AtomicUsize::new(0));
+    let vfs = CountingVfs {
+        inner: ok!(OsVfs::new()),
+        writes: writes.clone(),
+    };
+    ok!(sqlite::vfs::register("counting", vfs, false));
+
+    let flags = OpenFlags::new().with_create().with_read_write();
+    {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            &path, flags, "counting"
+        ));
+        ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+        ok!(connection.execute("INSERT INTO users VALUES (1, 'Alice')"));
+        let capabilities = ok!(connection.capabilities());
+        assert!(capabilities.supports_journal_mode("delete"));
+        assert!(!capabilities.supports_journal_mode("wal"));
+    }
+    assert!(writes.load(Ordering::SeqCst) > 0);
+
+    let connection = ok!(Connection::open(&path));
+    let mut statement = ok!(connection.prepare("SELECT name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+
+    let other = directory.path().join("other.sqlite3");
+    setup_users(&other);
+    let count = writes.load(Ordering::SeqCst);
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        &other,
+        OpenFlags::new().with_read_only(),
+        "counting",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 1);
+    assert_eq!(writes.load(Ordering::SeqCst), count);
+}
+
+#[cfg(unix)]
+#[test]
+fn register_uri() {
+    use std::os::unix::fs::PermissionsExt;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let reference = directory.path().join("reference.sqlite3");
+    ok!(std::fs::write(&reference, b""));
+    ok!(std::fs::set_permissions(
+        &reference,
+        std::fs::Permissions::from_mode(0o600)
+    ));
+    let vfs = CountingVfs {
+        inner: ok!(OsVfs::new()),
+        writes: Arc::new(AtomicUsize::new(0)),
+    };
+    ok!(sqlite::vfs::register("counting-uri", vfs, false));
+
+    let path = directory.path().join("database.sqlite3");
+    let uri = format!("file:{}?modeof={}", path.display(), reference.display());
+    let flags = OpenFlags::new().with_create().with_read_write().with_uri();
+    {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            &uri,
+            flags,
+            "counting-uri"
+        ));
+        ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+    }
+    let mode = ok!(std::fs::metadata(&path)).permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn register_copied_path() {
+    use temporary::Directory;
+
+    struct CopyingVfs(OsVfs);
+
+    impl Vfs for CopyingVfs {
+        type File = OsFile;
+
+        fn open(&self, path: Option<&str>, flags: FileFlags) -> Result<Self::File> {
+            self.0.open(path.map(String::from).as_deref(), flags)
+        }
+
+        fn delete(&self, path: &str) -> Result<()> {
+            self.0.delete(path)
+        }
+
+        fn exists(&self, path: &str) -> Result<bool> {
+            self.0.exists(path)
+        }
+
+        fn full_path(&self, path: &str) -> Result<String> {
+            self.0.full_path(path)
+        }
+    }
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    ok!(sqlite::vfs::register(
+        "copying",
+        CopyingVfs(ok!(OsVfs::new())),
+        false
+    ));
+    let flags = OpenFlags::new().with_create().with_read_write();
+    let connection = ok!(Connection::open_with_flags_and_vfs(&path, flags, "copying"));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER)"));
+    ok!(connection.execute("INSERT INTO users VALUES (1)"));
+    drop(connection);
+
+    let connection = ok!(Connection::open(&path));
+    assert_eq!(
+        ok!(connection.query_scalar::<i64, _, _>("SELECT id FROM users", &[] as &[sqlite::Value])),
+        1
+    );
+}
+
+#[test]
+fn register_missing() {
+    let flags = OpenFlags::new().with_create().with_read_write();
+    assert!(Connection::open_with_flags_and_vfs(":memory:", flags, "missing").is_err());
+}
+
+#[test]
+fn encrypted() {
+    use sqlite::vfs::{Cipher, EncryptedVfs, MemoryVfs};
+
+    struct XorCipher(u8);
+
+    impl Cipher for XorCipher {
+        fn overhead(&self) -> usize {
+            1
+        }
+
+        fn encrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>> {
+            let mut value = block.iter().map(|byte| byte ^ self.0).collect::<Vec<_>>();
+            value.push(self.0 ^ index as u8);
+            Ok(value)
+        }
+
+        fn decrypt(&self, index: u64, block: &[u8]) -> Result<Vec<u8>> {
+            let (data, tag) = block.split_at(block.len() - 1);
+            if tag[0] != self.0 ^ index as u8 {
+                return Err(sqlite::Error::new(Some(26), None));
+            }
+            Ok(data.iter().map(|byte| byte ^ self.0).collect())
+        }
+    }
+
+    let memory = MemoryVfs::new();
+    let vfs = EncryptedVfs::new(memory.clone(), XorCipher(42)).with_block_size(1024);
+    ok!(sqlite::vfs::register("encrypted", vfs, false));
+    let vfs = EncryptedVfs::new(memory.clone(), XorCipher(69)).with_block_size(1024);
+    ok!(sqlite::vfs::register("encrypted-other", vfs, false));
+
+    let flags = OpenFlags::new().with_create().with_read_write();
+    {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            "database",
+            flags,
+            "encrypted",
+        ));
+        ok!(connection.execute("PRAGMA page_size = 1024"));
+        ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+        for id in 0..100 {
+            ok!(connection.execute(format!(
+                "INSERT INTO users VALUES ({id}, 'Alice in Wonderland')"
+            )));
+        }
+        ok!(connection.execute("BEGIN"));
+        ok!(connection.execute("DELETE FROM users WHERE id >= 50"));
+        ok!(connection.execute("ROLLBACK"));
+    }
+    let data = memory.buffer("database").read().unwrap().clone();
+    assert!(!data.starts_with(b"SQLite format 3"));
+    assert!(!data.windows(5).any(|window| window == b"Alice"));
+
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "database",
+        flags,
+        "encrypted",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 100);
+
+    match Connection::open_with_flags_and_vfs("database", flags, "encrypted-other") {
+        Ok(connection) => assert!(connection.execute("SELECT count(*) FROM users").is_err()),
+        Err(error) => assert_eq!(error.code, Some(26)),
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn encrypted_chacha() {
+    use sqlite::vfs::{ChaChaCipher, EncryptedVfs, MemoryVfs};
+
+    let memory = MemoryVfs::new();
+    let vfs = EncryptedVfs::new(memory.clone(), ChaChaCipher::new(&[42; 32]));
+    ok!(sqlite::vfs::register("encrypted-chacha", vfs, false));
+    let vfs = EncryptedVfs::new(memory.clone(), ChaChaCipher::new(&[69; 32]));
+    ok!(sqlite::vfs::register("encrypted-chacha-other", vfs, false));
+
+    let flags = OpenFlags::new().with_create().with_read_write();
+    {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            "database",
+            flags,
+            "encrypted-chacha",
+        ));
+        ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+        ok!(connection.execute("INSERT INTO users VALUES (1, 'Alice')"));
+    }
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "database",
+        flags,
+        "encrypted-chacha",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+
+    match Connection::open_with_flags_and_vfs("database", flags, "encrypted-chacha-other") {
+        Ok(connection) => assert!(connection.execute("SELECT count(*) FROM users").is_err()),
+        Err(error) => assert_eq!(error.code, Some(26)),
+    }
+}
+
+#[test]
+fn fault() {
+    use std::time::{Duration, Instant};
+
+    use sqlite::vfs::{Fault, FaultVfs, MemoryVfs, Operation};
+
+    let vfs = FaultVfs::new(MemoryVfs::new());
+    ok!(sqlite::vfs::register("fault", vfs.clone(), false));
+    let flags = OpenFlags::new().with_create().with_read_write();
+    let count = |connection: &Connection| {
+        let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+        assert_eq!(ok!(statement.next()), State::Row);
+        ok!(statement.read::<i64, _>(0))
+    };
+
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "database", flags, "fault"
+    ));
+    ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+    ok!(connection.execute("INSERT INTO users VALUES (1, 'Alice')"));
+    assert!(vfs.count(Operation::Write) > 0);
+
+    vfs.inject(Operation::Write, 0, Fault::Error(778));
+    let error = connection
+        .execute("INSERT INTO users VALUES (2, 'Bob')")
+        .unwrap_err();
+    assert_eq!(error.code, Some(10));
+    assert_eq!(error.extended_code(), Some(778));
+    assert_eq!(count(&connection), 1);
+
+    vfs.inject(Operation::Write, 1, Fault::PartialWrite(10));
+    assert!(connection
+        .execute("INSERT INTO users VALUES (2, 'Bob')")
+        .is_err());
+    drop(connection);
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "database", flags, "fault"
+    ));
+    assert_eq!(count(&connection), 1);
+
+    vfs.inject(Operation::Sync, 0, Fault::Delay(Duration::from_millis(100)));
+    let start = Instant::now();
+    ok!(connection.execute("INSERT INTO users VALUES (2, 'Bob')"));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(count(&connection), 2);
+
+    vfs.inject(Operation::Read, 1000, Fault::Error(266));
+    vfs.clear();
+    assert_eq!(count(&connection), 2);
+}
+
+#[test]
+fn memory() {
+    use sqlite::vfs::MemoryVfs;
+    use temporary::Directory;
+
+    let vfs = MemoryVfs::new();
+    ok!(sqlite::vfs::register("memory", vfs.clone(), false));
+
+    let flags = OpenFlags::new().with_create().with_read_write();
+    {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            "first", flags, "memory"
+        ));
+        ok!(connection.execute("CREATE TABLE users (id INTEGER, name TEXT)"));
+        ok!(connection.execute("INSERT INTO users VALUES (1, 'Alice')"));
+
+        let other = ok!(Connection::open_with_flags_and_vfs(
+            "first", flags, "memory"
+        ));
+        let mut statement = ok!(other.prepare("SELECT name FROM users"));
+        assert_eq!(ok!(statement.next()), State::Row);
+        assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+    }
+    assert_eq!(vfs.paths(), vec!["first".to_string()]);
+    let buffer = vfs.buffer("first");
+    assert!(buffer.read().unwrap().starts_with(b"SQLite format 3\0"));
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    setup_users(&path);
+    *vfs.buffer("second").write().unwrap() = ok!(std::fs::read(&path));
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "second", flags, "memory"
+    ));
+    let mut statement = ok!(connection.prepare("SELECT name FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+
+    let flags = OpenFlags::new().with_read_write();
+    assert!(Connection::open_with_flags_and_vfs("missing", flags, "memory").is_err());
+}
+
+#[test]
+fn memory_busy() {
+    use sqlite::vfs::MemoryVfs;
+
+    ok!(sqlite::vfs::register(
+        "memory-busy",
+        MemoryVfs::new(),
+        false
+    ));
+    let flags = OpenFlags::new().with_create().with_read_write();
+    let first = ok!(Connection::open_with_flags_and_vfs(
+        "database",
+        flags,
+        "memory-busy"
+    ));
+    let second = ok!(Connection::open_with_flags_and_vfs(
+        "database",
+        flags,
+        "memory-busy"
+    ));
+    ok!(first.execute("CREATE TABLE users (id INTEGER)"));
+    ok!(first.execute("BEGIN IMMEDIATE"));
+    ok!(first.execute("INSERT INTO users VALUES (1)"));
+    assert!(second.execute("BEGIN IMMEDIATE").is_err());
+    ok!(first.execute("COMMIT"));
+    ok!(second.execute("BEGIN IMMEDIATE"));
+    ok!(second.execute("INSERT INTO users VALUES (2)"));
+    ok!(second.execute("COMMIT"));
+    let mut statement = ok!(first.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 2);
+}
+
+#[test]
+fn quota() {
+    use sqlite::vfs::{MemoryVfs, QuotaVfs};
+
+    let vfs = QuotaVfs::new(MemoryVfs::new(), 64 * 1024);
+    ok!(sqlite::vfs::register("quota", vfs.clone(), false));
+    let flags = OpenFlags::new().with_create().with_read_write();
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        "database", flags, "quota"
+    ));
+    ok!(connection.execute("CREATE TABLE blobs (value BLOB)"));
+    let mut count = 0;
+    let error = loop {
+        match connection.execute("INSERT INTO blobs VALUES (randomblob(1000))") {
+            Ok(_) => count += 1,
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error.code, Some(13));
+    assert!(count > 10 && count < 64);
+
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM blobs"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), count);
+    assert!(vfs.usage("database") > 32 * 1024);
+    assert!(vfs.usage("database") <= 64 * 1024);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use sqlite::vfs::HttpVfs;
+    use temporary::Directory;
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    {
+        let connection = setup_users(&path);
+        ok!(connection.execute("PRAGMA page_size = 1024; VACUUM"));
+    }
+    let data = Arc::new(ok!(std::fs::read(&path)));
+
+    let listener = ok!(TcpListener::bind("127.0.0.1:0"));
+    let address = ok!(listener.local_addr());
+    let requests = Arc::new(AtomicUsize::new(0));
+    {
+        let requests = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let data = data.clone();
+                let requests = requests.clone();
+                std::thread::spawn(move || {
+                    let mut stream = ok!(stream);
+                    let mut reader = BufReader::new(ok!(stream.try_clone()));
+                    loop {
+                        let mut range = None;
+                        let mut partial = true;
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            if ok!(reader.read_line(&mut line)) == 0 {
+                                return;
+                            }
+                            if line.starts_with("GET /full") {
+                                partial = false;
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some(value) = line.to_lowercase().strip_prefix("range: bytes=") {
+                                let (start, end) = value.trim().split_once('-').unwrap();
+                                let start: usize = ok!(start.parse());
+                                let end: usize = ok!(end.parse());
+                                range = Some((start, end.min(data.len() - 1)));
+                            }
+                        }
+                        requests.fetch_add(1, Ordering::SeqCst);
+                        let (start, end) = range.unwrap();
+                        if !partial {
+                            let header = format!(
+                                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                                data.len(),
+                            );
+                            ok!(stream.write_all(header.as_bytes()));
+                            ok!(stream.write_all(&data));
+                            continue;
+                        }
+                        let header = format!(
+                            "HTTP/1.1 206 Partial Content\r\n\
+                             Content-Range: bytes {}-{}/{}\r\n\
+                             Content-Length: {}\r\n\r\n",
+                            start,
+                            end,
+                            data.len(),
+                            end - start + 1,
+                        );
+                        ok!(stream.write_all(header.as_bytes()));
+                        ok!(stream.write_all(&data[start..=end]));
+                    }
+                });
+            }
+        });
+    }
+
+    let vfs = HttpVfs::new().with_chunk_size(1024).with_cache_size(2);
+    ok!(sqlite::vfs::register("http", vfs, false));
+    let url = format!("http://{address}/database.sqlite3");
+    let connection = ok!(Connection::open_with_flags_and_vfs(
+        url,
+        OpenFlags::new().with_read_only(),
+        "http",
+    ));
+    let mut statement = ok!(connection.prepare("SELECT name FROM users WHERE id = 1"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+    assert!(requests.load(Ordering::SeqCst) > 1);
+    assert!(connection
+        .execute("INSERT INTO users VALUES (2, 'Bob', NULL, NULL, NULL)")
+        .is_err());
+
+    let url = format!("http://{address}/full/database.sqlite3");
+    let result =
+        Connection::open_with_flags_and_vfs(url, OpenFlags::new().with_read_only(), "http")
+            .and_then(|connection| connection.execute("SELECT name FROM users"));
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip() {
+    use std::io::Write;
+
+    use sqlite::vfs::ZipVfs;
+    use temporary::Directory;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    setup_users(&path);
+    let data = ok!(std::fs::read(&path));
+
+    let archive = directory.path().join("archive.zip");
+    let mut writer = ZipWriter::new(ok!(std::fs::File::create(&archive)));
+    for (name, method) in [
+        ("stored.sqlite3", CompressionMethod::Stored),
+        ("nested/deflated.sqlite3", CompressionMethod::Deflated),
+    ] {
+        let options = SimpleFileOptions::default().compression_method(method);
+        ok!(writer.start_file(name, options));
+        ok!(writer.write_all(&data));
+    }
+    ok!(writer.finish());
+
+    ok!(sqlite::vfs::register("zip", ZipVfs::new(), false));
+    for name in ["stored.sqlite3", "nested/deflated.sqlite3"] {
+        let connection = ok!(Connection::open_with_flags_and_vfs(
+            archive.join(name),
+            OpenFlags::new().with_read_only(),
+            "zip",
+        ));
+        let mut statement = ok!(connection.prepare("SELECT name FROM users ORDER BY age"));
+        assert_eq!(ok!(statement.next()), State::Row);
+        assert_eq!(ok!(statement.read::<String, _>(0)), "Alice");
+    }
+    assert!(Connection::open_with_flags_and_vfs(
+        archive.join("missing.sqlite3"),
+        OpenFlags::new().with_read_only(),
+        "zip",
+    )
+    .is_err());
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use sqlite::vtab::{Cursor, IndexInfo, Module, Operator, Table};
+use sqlite::{Connection, Result, State, Value};
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+struct Series;
+
+struct SeriesTable;
+
+struct SeriesCursor {
+    current: i64,
+    stop: i64,
+}
+
+impl Module for Series {
+    type Table = SeriesTable;
+
+    fn connect(&self, _: &[&str]) -> Result<(String, Self::Table)> {
+        Ok((
+            "CREATE TABLE x (value INTEGER, start HIDDEN, stop HIDDEN)".into(),
+            SeriesTable,
+        ))
+    }
+}
+
+impl Table for SeriesTable {
+    type Cursor = SeriesCursor;
+
+    fn best_index(&self, info: &mut IndexInfo) -> Result<()> {
+        let mut mask = 0;
+        for (index, constraint) in info.constraints().into_iter().enumerate() {
+            if !constraint.usable || constraint.operator != Operator::Equal {
+                continue;
+            }
+            match constraint.column {
+                Some(1) => {
+                    info.use_constraint(index, 0, true);
+                    mask |= 1;
+                }
+                Some(2) => {
+                    info.use_constraint(index, 1, true);
+                    mask |= 2;
+                }
+                _ => {}
+            }
+        }
+        if mask != 3 {
+            return Err(sqlite::Error::new(
+                None,
+                Some("start and stop are required".into()),
+            ));
+        }
+        info.set_estimated_cost(1.0);
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(SeriesCursor {
+            current: 0,
+            stop: -1,
+        })
+    }
+}
+
+impl Cursor for SeriesCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, arguments: &[Value]) -> Result<()> {
+        match arguments {
+            [Value::Integer(start), Value::Integer(stop)] => {
+                self.current = *start;
+                self.stop = *stop;
+                Ok(())
+            }
+            _ => Err(sqlite::Error::new(
+                None,
+                Some("start and stop should be integers".into()),
+            )),
+        }
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.current += 1;
+        Ok(())
+    }
+
+    fn eof(&self) -> bool {
+        self.current > self.stop
+    }
+
+    fn column(&self, index: usize) -> Result<Value> {
+        Ok(match index {
+            0 => Value::Integer(self.current),
+            _ => Value::Null,
+        })
+    }
+
+    fn rowid(&self) -> Result<i64> {
+        Ok(self.current)
+    }
+}
+
+#[derive(Clone, Default)]
+struct Store(Arc<Mutex<BTreeMap<i64, Vec<Value>>>>);
+
+struct StoreCursor {
+    rows: Vec<(i64, Vec<Value>)>,
+    position: usize,
+}
+
+impl Module for Store {
+    type Table = Store;
+
+    fn connect(&self, arguments: &[&str]) -> Result<(String, Self::Table)> {
+        Ok((
+            format!("CREATE TABLE x ({})", arguments.join(", ")),
+            self.clone(),
+        ))
+    }
+}
+
+impl Table for Store {
+    type Cursor = StoreCursor;
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(StoreCursor {
+            rows: self.snapshot(),
+            position: 0,
+        })
+    }
+
+    fn insert(&mut self, rowid: Option<i64>, values: &[Value]) -> Result<i64> {
+        let mut rows = self.0.lock().unwrap();
+        let rowid = rowid.unwrap_or_else(|| rows.keys().last().map_or(1, |rowid| rowid + 1));
+        rows.insert(rowid, values.to_vec());
+        Ok(rowid)
+    }
+
+    fn update(&mut self, old: i64, new: i64, values: &[Value]) -> Result<()> {
+        let mut rows = self.0.lock().unwrap();
+        rows.remove(&old);
+        rows.insert(new, values.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, rowid: i64) -> Result<()> {
+        self.0.lock().unwrap().remove(&rowid);
+        Ok(())
+    }
+}
+
+impl Cursor for StoreCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, _: &[Value]) -> Result<()> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.position += 1;
+        Ok(())
+    }
+
+    fn eof(&self) -> bool {
+        self.position >= self.rows.len()
+    }
+
+    fn column(&self, index: usize) -> Result<Value> {
+        Ok(self.rows[self.position].1[index].clone())
+    }
+
+    fn rowid(&self) -> Result<i64> {
+        Ok(self.rows[self.position].0)
+    }
+}
+
+impl Store {
+    fn snapshot(&self) -> Vec<(i64, Vec<Value>)> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(rowid, values)| (*rowid, values.clone()))
+            .collect()
+    }
+}
+
+#[test]
+fn create_module_eponymous() {
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_module("series", Series));
+    ok!(connection.execute("CREATE TABLE numbers (value INTEGER)"));
+    ok!(connection.execute("INSERT INTO numbers VALUES (2), (4), (8)"));
+
+    let mut statement = ok!(connection
+        .prepare("SELECT sum(value) FROM series(1, 5) WHERE value IN (SELECT value FROM numbers)"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 6);
+
+    let error = connection
+        .prepare("SELECT value FROM series")
+        .err()
+        .unwrap();
+    assert!(error
+        .message
+        .unwrap()
+        .contains("start and stop are required"));
+}
+
+#[test]
+fn create_module_writable() {
+    let store = Store::default();
+    let connection = ok!(Connection::open(":memory:"));
+    ok!(connection.create_module("store", store.clone()));
+    ok!(connection.execute("CREATE VIRTUAL TABLE users USING store(name TEXT, age INTEGER)"));
+    ok!(connection.execute("INSERT INTO users VALUES ('Alice', 42), ('Bob', 69)"));
+    ok!(connection.execute("UPDATE users SET age = 43 WHERE name = 'Alice'"));
+    ok!(connection.execute("DELETE FROM users WHERE name = 'Bob'"));
+    assert_eq!(
+        store.snapshot(),
+        vec![(1, vec![Value::from("Alice"), Value::Integer(43)])],
+    );
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use sqlite::{Connection, State, Writer, WriterOptions};
+use temporary::Directory;
+
+macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+#[test]
+fn submit() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let options = WriterOptions::new().with_flush_interval(Duration::from_millis(50));
+    let writer = ok!(Writer::open(&path, options));
+    ok!(writer.submit(|connection| connection.execute("CREATE TABLE users (name TEXT UNIQUE)")));
+
+    let pending = (0..10)
+        .map(|i| {
+            writer.submit_async(move |connection| {
+                connection.execute(format!("INSERT INTO users VALUES ('user{}')", i % 5))?;
+                Ok(connection.change_count())
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = pending
+        .into_iter()
+        .map(|pending| pending.wait())
+        .collect::<Vec<_>>();
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 5);
+    let error = results[5].as_ref().unwrap_err();
+    assert_eq!(
+        error.extended_code(),
+        Some(sqlite::ffi::SQLITE_CONSTRAINT_UNIQUE as isize)
+    );
+    drop(writer);
+
+    let connection = ok!(Connection::open(&path));
+    let mut statement = ok!(connection.prepare("SELECT count(*) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<i64, _>(0)), 5);
+}
+
+#[test]
+fn submit_with_panic() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let writer = ok!(Writer::open(&path, WriterOptions::new()));
+    ok!(writer.submit(|connection| connection.execute("CREATE TABLE users (name TEXT)")));
+
+    let one =
+        writer.submit_async(|connection| connection.execute("INSERT INTO users VALUES ('Alice')"));
+    let two = writer.submit_async(|connection| -> sqlite::Result<()> {
+        connection.execute("INSERT INTO users VALUES ('Bob')")?;
+        panic!()
+    });
+    ok!(one.wait());
+    assert!(two.wait().is_err());
+    ok!(writer.submit(|connection| connection.execute("INSERT INTO users VALUES ('Carol')")));
+    drop(writer);
+
+    let connection = ok!(Connection::open(&path));
+    let mut statement = ok!(connection.prepare("SELECT group_concat(name) FROM users"));
+    assert_eq!(ok!(statement.next()), State::Row);
+    assert_eq!(ok!(statement.read::<String, _>(0)), "Alice,Carol");
+}
+
+#[tokio::test]
+async fn submit_async() {
+    let directory = ok!(Directory::new("sqlite"));
+    let path = directory.path().join("database.sqlite3");
+    let writer = ok!(Writer::open(&path, WriterOptions::new()));
+    ok!(writer
+        .submit_async(|connection| connection.execute("CREATE TABLE users (name TEXT)"))
+        .await);
+    let count = ok!(writer
+        .submit_async(|connection| {
+            connection.execute("INSERT INTO users VALUES ('Alice'), ('Bob')")?;
+            Ok(connection.change_count())
+        })
+        .await);
+    assert_eq!(count, 2);
+}